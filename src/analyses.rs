@@ -1,12 +1,43 @@
-use crate::{AtstError, Solution};
+use crate::config::ConfigError;
+use crate::{AtstError, Solution, SourceMetrics, Test};
 use regex::{Regex, RegexSet};
-use std::process::Command;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::Mutex;
+use yaml_rust::Yaml;
+
+/// Constructor of a custom analyser from its YAML configuration
+pub type AnalyserConstructor = fn(&Yaml) -> Result<Box<dyn Analyser>, ConfigError>;
+
+/// Custom analysers registered by users of the library
+static CUSTOM_ANALYSERS: Mutex<BTreeMap<String, AnalyserConstructor>> = Mutex::new(BTreeMap::new());
+
+/// Register a custom analyser which can be then used in the project configuration
+/// as `analyser: <name>`. Built-in analysers cannot be overridden.
+pub fn register_analyser(name: &str, constructor: AnalyserConstructor) {
+    CUSTOM_ANALYSERS
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), constructor);
+}
 
 /// List of all supported analysers
 pub enum AnalyserKind {
     NoCall,
+    RequireCall,
     NoHeader,
     NoGlobals,
+    NoFloatEq,
+    NoGoto,
+    NoRecursion,
+    MaxFunctionLength,
+    CheckAlloc,
+    UnsafeInput,
+    PairBalance,
+    SourceSize,
+    TemplateSimilarity,
+    HardcodedOutput,
+    Custom(AnalyserConstructor),
 
     Unsupported,
 }
@@ -15,18 +46,174 @@ impl AnalyserKind {
     pub fn from(str: &str) -> Self {
         match str {
             "no-call" => AnalyserKind::NoCall,
+            "require-call" => AnalyserKind::RequireCall,
             "no-header" => AnalyserKind::NoHeader,
             "no-globals" => AnalyserKind::NoGlobals,
-            _ => AnalyserKind::Unsupported,
+            "no-float-eq" => AnalyserKind::NoFloatEq,
+            "no-goto" => AnalyserKind::NoGoto,
+            "no-recursion" => AnalyserKind::NoRecursion,
+            "max-function-length" => AnalyserKind::MaxFunctionLength,
+            "check-alloc" => AnalyserKind::CheckAlloc,
+            "unsafe-input" => AnalyserKind::UnsafeInput,
+            "pair-balance" => AnalyserKind::PairBalance,
+            "source-size" => AnalyserKind::SourceSize,
+            "template-similarity" => AnalyserKind::TemplateSimilarity,
+            "hardcoded-output" => AnalyserKind::HardcodedOutput,
+            _ => match CUSTOM_ANALYSERS.lock().unwrap().get(str) {
+                Some(constructor) => AnalyserKind::Custom(*constructor),
+                None => AnalyserKind::Unsupported,
+            },
+        }
+    }
+}
+
+/// Single problem found by an analyser
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Line in the original source file (if known)
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn new(line: Option<usize>, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+/// Severity of heuristic analysers
+/// Findings of analysers with the `Report` severity are only reported and do not change
+/// the solution score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Report,
+    Penalty(f64),
+}
+
+impl Severity {
+    pub fn penalty(&self) -> f64 {
+        match self {
+            Severity::Report => 0.0,
+            Severity::Penalty(penalty) => *penalty,
         }
     }
 }
 
 /// Source file analysis
-/// If analyse() returns true, penalty() will be added to the solution score
-pub trait Analyser {
-    fn analyse(&self, solution: &Solution) -> Result<bool, AtstError>;
+/// If analyse() returns some findings, penalty_for() the findings (by default penalty())
+/// will be added to the solution score
+/// Analysers are shared by solutions evaluated in parallel.
+pub trait Analyser: Send + Sync {
+    fn name(&self) -> &str;
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError>;
     fn penalty(&self) -> f64;
+
+    fn penalty_for(&self, findings: &[Finding]) -> f64 {
+        match findings.is_empty() {
+            true => 0.0,
+            false => self.penalty(),
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Penalty(self.penalty())
+    }
+
+    /// Score of the solution if the analyser has some findings and the tests of
+    /// the solution should not be run (None if the analyser does not gate tests)
+    fn gate(&self) -> Option<f64> {
+        None
+    }
+
+    /// Level of the findings used to group them in reports
+    /// Findings of analysers which are only reported are warnings by default.
+    fn level(&self) -> Level {
+        match self.severity() {
+            Severity::Report => Level::Warning,
+            Severity::Penalty(_) => Level::Error,
+        }
+    }
+}
+
+/// Level of analyser findings (only used for reporting, does not affect scoring)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn from(str: &str) -> Option<Self> {
+        match str {
+            "warning" => Some(Level::Warning),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Level::Warning => write!(f, "warning"),
+            Level::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Analyser with options common to all analyses set in the configuration
+/// If `gate` is set, findings prevent running tests of the solution and the solution gets
+/// the given score instead. If `level` is set, it overrides the level of the findings.
+/// Otherwise, the inner analyser is used as is.
+pub struct ConfiguredAnalyser {
+    inner: Box<dyn Analyser>,
+    gate: Option<f64>,
+    level: Option<Level>,
+}
+
+impl ConfiguredAnalyser {
+    pub fn new(inner: Box<dyn Analyser>, gate: Option<f64>, level: Option<Level>) -> Self {
+        Self { inner, gate, level }
+    }
+}
+
+impl Analyser for ConfiguredAnalyser {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        self.inner.analyse(solution)
+    }
+
+    fn penalty(&self) -> f64 {
+        self.inner.penalty()
+    }
+
+    fn penalty_for(&self, findings: &[Finding]) -> f64 {
+        self.inner.penalty_for(findings)
+    }
+
+    fn severity(&self) -> Severity {
+        self.inner.severity()
+    }
+
+    fn gate(&self) -> Option<f64> {
+        self.gate.or_else(|| self.inner.gate())
+    }
+
+    fn level(&self) -> Level {
+        self.level.unwrap_or_else(|| self.inner.level())
+    }
+}
+
+/// Finding of an analyser recorded for a solution
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyserFinding {
+    pub analyser: String,
+    pub severity: Severity,
+    pub level: Level,
+    pub finding: Finding,
 }
 
 /// Check that the program does not call one of given functions
@@ -42,13 +229,47 @@ impl NoCallAnalyser {
 }
 
 impl Analyser for NoCallAnalyser {
-    fn analyse(&self, solution: &Solution) -> Result<bool, AtstError> {
-        let re = RegexSet::new(self.funs.iter().map(|f| format!(r"{}\s*\(", f))).map_err(|_| {
-            AtstError::InternalError {
-                msg: "no-call analyser regex error".to_string(),
-            }
-        })?;
-        Ok(re.is_match(&solution.source))
+    fn name(&self) -> &str {
+        "no-call"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        Ok(find_calls(&solution.source, &self.funs, "no-call")?
+            .into_iter()
+            .map(|(pos, fun)| Finding::new(solution.source_line(pos), format!("call of '{}'", fun)))
+            .collect())
+    }
+
+    fn penalty(&self) -> f64 {
+        self.penalty
+    }
+}
+
+/// Check that the program calls each of given functions
+pub struct RequireCallAnalyser {
+    funs: Vec<String>,
+    penalty: f64,
+}
+
+impl RequireCallAnalyser {
+    pub fn new(funs: Vec<String>, penalty: f64) -> Self {
+        Self { funs, penalty }
+    }
+}
+
+impl Analyser for RequireCallAnalyser {
+    fn name(&self) -> &str {
+        "require-call"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let calls = find_calls(&solution.source, &self.funs, "require-call")?;
+        Ok(self
+            .funs
+            .iter()
+            .filter(|fun| !calls.iter().any(|(_, called)| called == *fun))
+            .map(|fun| Finding::new(None, format!("no call of '{}'", fun)))
+            .collect())
     }
 
     fn penalty(&self) -> f64 {
@@ -69,8 +290,15 @@ impl NoHeaderAnalyser {
 }
 
 impl Analyser for NoHeaderAnalyser {
-    fn analyse(&self, solution: &Solution) -> Result<bool, AtstError> {
-        Ok(solution.included.contains(&self.header))
+    fn name(&self) -> &str {
+        "no-header"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        Ok(match solution.included.contains(&self.header) {
+            true => vec![Finding::new(None, format!("include of <{}>", self.header))],
+            false => vec![],
+        })
     }
 
     fn penalty(&self) -> f64 {
@@ -95,8 +323,13 @@ impl NoGlobalsAnalyser {
 }
 
 impl Analyser for NoGlobalsAnalyser {
-    fn analyse(&self, solution: &Solution) -> Result<bool, AtstError> {
-        let nm_output = Command::new("nm")
+    fn name(&self) -> &str {
+        "no-globals"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let nm_output = solution
+            .command("nm")
             .arg(&solution.obj_file)
             .current_dir(&solution.path)
             .output()
@@ -116,10 +349,24 @@ impl Analyser for NoGlobalsAnalyser {
                 msg: "no-globals analyser regex error".to_string(),
             })?;
 
+        // Globals are located by their first occurrence outside of function bodies
+        let source = mask_literals(&solution.source);
+        let bodies = functions(&source);
+        let line_of = |name: &str| {
+            Regex::new(&format!(r"\b{}\b", regex::escape(name)))
+                .ok()?
+                .find_iter(&source)
+                .map(|m| m.start())
+                .find(|pos| !bodies.iter().any(|f| f.contains(pos)))
+                .and_then(|pos| solution.source_line(pos))
+        };
+
         Ok(symbols
             .lines()
             .filter_map(|line| global_regex.captures(line))
-            .any(|sym| !except_regexes.is_match(&sym[1])))
+            .filter(|sym| !except_regexes.is_match(&sym[1]))
+            .map(|sym| Finding::new(line_of(&sym[1]), format!("global variable '{}'", &sym[1])))
+            .collect())
     }
 
     fn penalty(&self) -> f64 {
@@ -127,89 +374,1916 @@ impl Analyser for NoGlobalsAnalyser {
     }
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use crate::test_utils::get_solution;
+/// Check that the program does not compare floating-point values using == or !=
+/// An operand is considered floating-point if it is a floating-point literal or a variable
+/// declared as float or double in the same function (best-effort scan of declarations).
+/// The penalty is given for each comparison found, the total may be capped by `max_penalty`.
+pub struct NoFloatEqAnalyser {
+    penalty: f64,
+    max_penalty: Option<f64>,
+}
 
-    fn test_on(analyser: &dyn Analyser, src: &str, included: &Vec<String>, expected: bool) {
-        let mut solution = get_solution(src, true);
-        solution.included = included.clone();
-        let res = analyser.analyse(&solution);
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), expected);
+impl NoFloatEqAnalyser {
+    pub fn new(penalty: f64, max_penalty: Option<f64>) -> Self {
+        Self {
+            penalty,
+            max_penalty,
+        }
     }
+}
 
-    fn test_on_default(analyser: &dyn Analyser, expected: bool) {
-        let src = r#"#include <stdio.h>
-                     int x;
-                     int main() {
-                         printf("foo");
-                     }
-                  "#;
-        test_on(analyser, src, &vec!["stdio.h".to_string()], expected);
+const FLOAT_LITERAL: &str = r"(?:\d+\.\d*|\.\d+)(?:[eE][+-]?\d+)?[fFlL]?|\d+[eE][+-]?\d+[fFlL]?";
+
+impl Analyser for NoFloatEqAnalyser {
+    fn name(&self) -> &str {
+        "no-float-eq"
     }
 
-    #[test]
-    fn no_call_analyser_match() {
-        let analyser = NoCallAnalyser {
-            funs: vec!["printf".to_string()],
-            penalty: -1.0,
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let regex_err = |_| AtstError::InternalError {
+            msg: "no-float-eq analyser regex error".to_string(),
         };
-        test_on_default(&analyser, true);
+        let decl_re = Regex::new(r"\b(?:float|double)\b([^;(){}]*)").map_err(regex_err)?;
+        let var_re = Regex::new(
+            r"(?s)^\s*(?:(?:const|volatile|long|float|double)\s+)*(\*?)\s*([A-Za-z_]\w*)\s*(?:[=\[].*)?$",
+        )
+        .map_err(regex_err)?;
+        let cmp_re = Regex::new(r"==|!=").map_err(regex_err)?;
+        let left_re =
+            Regex::new(&format!(r"([A-Za-z_]\w*|{}|\d+)\s*$", FLOAT_LITERAL)).map_err(regex_err)?;
+        let right_re = Regex::new(&format!(
+            r"^\s*[-+]?\s*([A-Za-z_]\w*|{}|\d+)(\s*\()?",
+            FLOAT_LITERAL
+        ))
+        .map_err(regex_err)?;
+        let float_re = Regex::new(&format!("^(?:{})$", FLOAT_LITERAL)).map_err(regex_err)?;
+
+        let source = mask_literals(&solution.source);
+        let mut findings = vec![];
+        for item in functions(&source) {
+            let text = &source[item.clone()];
+            // Collect non-pointer floating-point variables declared in the function
+            let float_vars: Vec<&str> = decl_re
+                .captures_iter(text)
+                .flat_map(|decl| decl.get(1).unwrap().as_str().split(','))
+                .filter_map(|var| var_re.captures(var))
+                .filter(|var| var[1].is_empty())
+                .map(|var| var.get(2).unwrap().as_str())
+                .collect();
+            let is_float =
+                |operand: &str| float_re.is_match(operand) || float_vars.contains(&operand);
+
+            for cmp in cmp_re.find_iter(text) {
+                let left = left_re
+                    .captures(&text[..cmp.start()])
+                    .map(|c| c.get(1).unwrap().as_str());
+                // Skip function calls on the right side
+                let right = right_re
+                    .captures(&text[cmp.end()..])
+                    .filter(|c| c.get(2).is_none())
+                    .map(|c| c.get(1).unwrap().as_str());
+                if left.is_some_and(is_float) || right.is_some_and(is_float) {
+                    findings.push(Finding::new(
+                        solution.source_line(item.start + cmp.start()),
+                        format!(
+                            "floating-point comparison '{} {} {}'",
+                            left.unwrap_or("..."),
+                            cmp.as_str(),
+                            right.unwrap_or("...")
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(findings)
     }
 
-    #[test]
-    fn no_call_analyser_nomatch() {
-        let analyser = NoCallAnalyser {
-            funs: vec!["foo".to_string()],
-            penalty: -1.0,
-        };
-        test_on_default(&analyser, false);
+    fn penalty(&self) -> f64 {
+        self.penalty
     }
 
-    #[test]
-    fn no_header_analyser_match() {
-        let analyser = NoHeaderAnalyser {
-            header: "stdio.h".to_string(),
-            penalty: -1.0,
-        };
-        test_on_default(&analyser, true);
+    fn penalty_for(&self, findings: &[Finding]) -> f64 {
+        let total = self.penalty * findings.len() as f64;
+        match self.max_penalty {
+            Some(max) if total.abs() > max.abs() => max,
+            _ => total,
+        }
     }
+}
 
-    #[test]
-    fn no_header_analyser_nomatch() {
-        let analyser = NoHeaderAnalyser {
-            header: "foo.h".to_string(),
-            penalty: -1.0,
-        };
-        test_on_default(&analyser, false);
+/// Check that the program does not use more than `max_allowed` goto statements
+/// If there are too many of them, each goto is reported and the penalty is given once.
+pub struct NoGotoAnalyser {
+    penalty: f64,
+    max_allowed: usize,
+}
+
+impl NoGotoAnalyser {
+    pub fn new(penalty: f64, max_allowed: usize) -> Self {
+        Self {
+            penalty,
+            max_allowed,
+        }
     }
+}
 
-    #[test]
-    fn no_globals_analyser_match() {
-        let analyser = NoGlobalsAnalyser {
-            penalty: -1.0,
-            exceptions: vec![],
+impl Analyser for NoGotoAnalyser {
+    fn name(&self) -> &str {
+        "no-goto"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let goto_re =
+            Regex::new(r"\bgoto\s+([A-Za-z_]\w*)").map_err(|_| AtstError::InternalError {
+                msg: "no-goto analyser regex error".to_string(),
+            })?;
+        // The source is preprocessed, so only literals may contain a false "goto"
+        let source = mask_literals(&solution.source);
+        let gotos: Vec<(usize, &str)> = goto_re
+            .captures_iter(&source)
+            .map(|goto| (goto.get(0).unwrap().start(), goto.get(1).unwrap().as_str()))
+            .collect();
+        if gotos.len() <= self.max_allowed {
+            return Ok(vec![]);
+        }
+        Ok(gotos
+            .iter()
+            .map(|(pos, label)| {
+                Finding::new(
+                    solution.source_line(*pos),
+                    format!(
+                        "'goto {}' ({} gotos, at most {} allowed)",
+                        label,
+                        gotos.len(),
+                        self.max_allowed
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    fn penalty(&self) -> f64 {
+        self.penalty
+    }
+}
+
+/// Check that no function of the program calls itself, directly or through other functions
+/// Builds a call graph of the functions defined in the source and reports each function
+/// lying on a cycle, except for the functions from `exceptions`.
+pub struct NoRecursionAnalyser {
+    penalty: f64,
+    exceptions: Vec<String>,
+}
+
+impl NoRecursionAnalyser {
+    pub fn new(penalty: f64, exceptions: Vec<String>) -> Self {
+        Self {
+            penalty,
+            exceptions,
+        }
+    }
+}
+
+impl Analyser for NoRecursionAnalyser {
+    fn name(&self) -> &str {
+        "no-recursion"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let regex_err = |_| AtstError::InternalError {
+            msg: "no-recursion analyser regex error".to_string(),
         };
-        test_on_default(&analyser, true);
+        let call_re = Regex::new(r"\b([A-Za-z_]\w*)\s*\(").map_err(regex_err)?;
+
+        let source = mask_literals(&solution.source);
+        let defs = function_definitions(&source, "no-recursion")?;
+        let index = |name: &str| defs.iter().position(|f| f.name == name);
+        let calls: Vec<Vec<usize>> = defs
+            .iter()
+            .map(|f| {
+                let mut callees: Vec<usize> = call_re
+                    .captures_iter(&source[f.body.clone()])
+                    .filter_map(|call| index(&call[1]))
+                    .collect();
+                callees.sort_unstable();
+                callees.dedup();
+                callees
+            })
+            .collect();
+
+        Ok(defs
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !self.exceptions.iter().any(|e| e == f.name))
+            .filter_map(|(i, f)| {
+                let cycle = shortest_cycle(&calls, i)?;
+                let message = match cycle.len() {
+                    1 => format!("recursive function '{}'", f.name),
+                    _ => format!(
+                        "recursive function '{}' (through {})",
+                        f.name,
+                        cycle[1..]
+                            .iter()
+                            .map(|c| format!("'{}'", defs[*c].name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                Some(Finding::new(solution.source_line(f.offset), message))
+            })
+            .collect())
     }
 
-    #[test]
-    fn no_globals_analyser_nomatch() {
-        let analyser = NoGlobalsAnalyser {
-            penalty: -1.0,
-            exceptions: vec![],
+    fn penalty(&self) -> f64 {
+        self.penalty
+    }
+}
+
+/// Shortest cycle through the node `start` of the graph given by the lists of successors
+/// Returns the nodes of the cycle, beginning with `start` (None if there is no cycle).
+fn shortest_cycle(successors: &[Vec<usize>], start: usize) -> Option<Vec<usize>> {
+    let mut parent = vec![None; successors.len()];
+    let mut queue = VecDeque::from(vec![start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in &successors[node] {
+            if next == start {
+                let mut cycle = vec![node];
+                while let Some(p) = parent[*cycle.last().unwrap()] {
+                    cycle.push(p);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if parent[next].is_none() {
+                parent[next] = Some(node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Check that no function of the program is longer than `limit` lines
+/// The length of a function is the number of lines of the original source from the opening
+/// to the closing brace of its body, so lines of preprocessor directives (and of the code
+/// excluded by them) are counted, too. If `per_function` is set, the penalty is given for
+/// each function over the limit, otherwise it is given once.
+pub struct MaxFunctionLengthAnalyser {
+    limit: u64,
+    penalty: f64,
+    per_function: bool,
+}
+
+impl MaxFunctionLengthAnalyser {
+    pub fn new(limit: u64, penalty: f64, per_function: bool) -> Self {
+        Self {
+            limit,
+            penalty,
+            per_function,
+        }
+    }
+}
+
+impl Analyser for MaxFunctionLengthAnalyser {
+    fn name(&self) -> &str {
+        "max-function-length"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let source = mask_literals(&solution.source);
+        Ok(function_definitions(&source, "max-function-length")?
+            .into_iter()
+            .filter_map(|function| {
+                let body = function.body.clone();
+                let first = solution.source_line(body.start);
+                let last = solution.source_line(body.end - 1);
+                let lines = match (first, last) {
+                    (Some(first), Some(last)) if last >= first => last - first + 1,
+                    // Without the original lines, count the lines of the preprocessed source
+                    _ => source[body].matches('\n').count() + 1,
+                } as u64;
+                (lines > self.limit).then(|| {
+                    Finding::new(
+                        solution.source_line(function.offset),
+                        format!(
+                            "function '{}' has {} lines (limit {})",
+                            function.name, lines, self.limit
+                        ),
+                    )
+                })
+            })
+            .collect())
+    }
+
+    fn penalty(&self) -> f64 {
+        self.penalty
+    }
+
+    fn penalty_for(&self, findings: &[Finding]) -> f64 {
+        match self.per_function {
+            true => self.penalty * findings.len() as f64,
+            false if findings.is_empty() => 0.0,
+            false => self.penalty,
+        }
+    }
+}
+
+/// Check that the results of allocation functions are checked for NULL before they are used
+/// Finds variables assigned the result of one of `funs` which are dereferenced or returned
+/// before being compared to NULL in the same function (lightweight scan, no dataflow).
+pub struct CheckAllocAnalyser {
+    funs: Vec<String>,
+    severity: Severity,
+}
+
+impl CheckAllocAnalyser {
+    pub fn new(funs: Vec<String>, severity: Severity) -> Self {
+        Self { funs, severity }
+    }
+}
+
+impl Analyser for CheckAllocAnalyser {
+    fn name(&self) -> &str {
+        "check-alloc"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let regex_err = |_| AtstError::InternalError {
+            msg: "check-alloc analyser regex error".to_string(),
         };
-        test_on(&analyser, "int main() {}", &vec![], false);
+        let alloc_re = Regex::new(&format!(
+            r"\b([A-Za-z_]\w*)\s*=\s*(?:\([\w\s\*]+\)\s*)?\b({})\s*\(",
+            self.funs.join("|")
+        ))
+        .map_err(regex_err)?;
+
+        let source = mask_literals(&solution.source);
+        let mut findings = vec![];
+        for item in functions(&source) {
+            let text = &source[item.clone()];
+            for alloc in alloc_re.captures_iter(text) {
+                let var = alloc.get(1).unwrap().as_str();
+                let fun = alloc.get(2).unwrap().as_str();
+                let alloc_pos = alloc.get(0).unwrap().start();
+                // Scan the rest of the function after the allocation statement
+                let rest_start = alloc.get(0).unwrap().end();
+                let rest_start = rest_start + text[rest_start..].find(';').unwrap_or(0);
+                let rest = &text[rest_start..];
+
+                let var = regex::escape(var);
+                let check_re = Regex::new(&format!(
+                    r"!\s*{v}\b|\b{v}\s*[!=]=|[!=]=\s*{v}\b|\b(?:if|while|assert)\s*\(\s*{v}\s*[)&|]",
+                    v = var
+                ))
+                .map_err(regex_err)?;
+                let use_re = Regex::new(&format!(
+                    r"(?:^|[^\w)\]\s]|\breturn)\s*\*\s*{v}\b|\b{v}\s*(?:\[|->)|\breturn\s+{v}\s*;",
+                    v = var
+                ))
+                .map_err(regex_err)?;
+                let reassign_re =
+                    Regex::new(&format!(r"(?:^|[;{{}},])\s*{}\s*=[^=]", var)).map_err(regex_err)?;
+
+                let end = reassign_re.find(rest).map_or(rest.len(), |m| m.start());
+                let checked = check_re.find(&rest[..end]).map(|m| m.start());
+                let used = use_re.find(&rest[..end]).map(|m| m.start());
+                if let Some(used) = used.filter(|u| checked.is_none_or(|c| c > *u)) {
+                    let use_line = solution.source_line(item.start + rest_start + used);
+                    findings.push(Finding::new(
+                        solution.source_line(item.start + alloc_pos),
+                        format!(
+                            "result of '{}' assigned to '{}' is used{} without a NULL check",
+                            fun,
+                            alloc.get(1).unwrap().as_str(),
+                            use_line.map_or(String::new(), |l| format!(" at line {}", l))
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(findings)
     }
 
-    #[test]
-    fn no_globals_analyser_except() {
-        let analyser = NoGlobalsAnalyser {
-            penalty: -1.0,
-            exceptions: vec!["x.*".to_string()],
+    fn penalty(&self) -> f64 {
+        self.severity.penalty()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Rule of the unsafe-input analyser
+/// Findings of rules which are only reported do not count towards the penalty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsafeInputRule {
+    pub id: &'static str,
+    pub report: bool,
+    pub message: String,
+}
+
+impl UnsafeInputRule {
+    /// Built-in rules with their default severities and messages:
+    ///   - `gets`: any call of `gets`,
+    ///   - `scanf-string`: `scanf`/`fscanf` with a literal format containing a string
+    ///     conversion (`%s` or `%[`) without a maximal field width,
+    ///   - `strcpy`: any call of `strcpy` or `strcat` (only reported by default).
+    pub fn defaults() -> Vec<Self> {
+        let rule = |id, report, message: &str| Self {
+            id,
+            report,
+            message: message.to_string(),
         };
-        test_on_default(&analyser, false);
+        vec![
+            rule(
+                "gets",
+                false,
+                "'gets' cannot limit the length of the input (use 'fgets')",
+            ),
+            rule(
+                "scanf-string",
+                false,
+                "string conversion without a maximal field width",
+            ),
+            rule(
+                "strcpy",
+                true,
+                "string is copied without checking the size of the destination",
+            ),
+        ]
+    }
+
+    fn functions(&self) -> &'static [&'static str] {
+        match self.id {
+            "gets" => &["gets"],
+            "scanf-string" => &["scanf", "fscanf"],
+            "strcpy" => &["strcpy", "strcat"],
+            _ => &[],
+        }
+    }
+}
+
+/// Check for calls of input and string functions which may overflow buffers
+/// Messages of the findings are prefixed by the identifiers of the rules.
+pub struct UnsafeInputAnalyser {
+    rules: Vec<UnsafeInputRule>,
+    penalty: f64,
+}
+
+impl UnsafeInputAnalyser {
+    pub fn new(rules: Vec<UnsafeInputRule>, penalty: f64) -> Self {
+        Self { rules, penalty }
+    }
+}
+
+impl Analyser for UnsafeInputAnalyser {
+    fn name(&self) -> &str {
+        "unsafe-input"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let funs: Vec<String> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.functions().iter().map(|f| f.to_string()))
+            .collect();
+        let masked = mask_literals(&solution.source);
+        let mut findings = vec![];
+        for (offset, fun) in find_calls(&solution.source, &funs, "unsafe-input")? {
+            let rule = match self.rules.iter().find(|r| r.functions().contains(&&*fun)) {
+                Some(rule) => rule,
+                None => continue,
+            };
+            // Formats which are not literals cannot be checked
+            let format_arg = match fun.as_str() {
+                "scanf" => Some(0),
+                "fscanf" => Some(1),
+                _ => None,
+            };
+            if let Some(n) = format_arg {
+                let open = offset + masked[offset..].find('(').unwrap_or(0);
+                let format = call_argument(&masked, open, n)
+                    .and_then(|arg| string_literal(&solution.source[arg..]));
+                if !format.is_some_and(|f| has_unbounded_conversion(&f)) {
+                    continue;
+                }
+            }
+            findings.push(Finding::new(
+                solution.source_line(offset),
+                format!("{}: {}", rule.id, rule.message),
+            ));
+        }
+        Ok(findings)
+    }
+
+    fn penalty(&self) -> f64 {
+        self.penalty
+    }
+
+    fn penalty_for(&self, findings: &[Finding]) -> f64 {
+        let penalized = |finding: &Finding| {
+            self.rules
+                .iter()
+                .any(|r| !r.report && finding.message.starts_with(&format!("{}:", r.id)))
+        };
+        match findings.iter().any(penalized) {
+            true => self.penalty,
+            false => 0.0,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self.rules.iter().all(|r| r.report) {
+            true => Severity::Report,
+            false => Severity::Penalty(self.penalty),
+        }
+    }
+}
+
+/// Byte offset of the `n`-th argument (from 0) of the call whose opening parenthesis is
+/// at offset `open` of `masked` (a source with masked literals)
+/// Returns None if the call has fewer arguments.
+fn call_argument(masked: &str, open: usize, n: usize) -> Option<usize> {
+    if n == 0 {
+        return Some(open + 1);
+    }
+    let mut depth = 0;
+    let mut arg = 0;
+    for (i, c) in masked[open..].char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 1 => return None,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 1 => {
+                arg += 1;
+                if arg == n {
+                    return Some(open + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Contents of the string literal at the start of `text` (leading whitespace is skipped)
+/// Adjacent literals are concatenated, escape sequences are kept as they are.
+fn string_literal(text: &str) -> Option<String> {
+    let mut result: Option<String> = None;
+    let mut rest = text.trim_start();
+    while let Some(literal) = rest.strip_prefix('"') {
+        let mut escaped = false;
+        let (end, _) = literal.char_indices().find(|(_, c)| {
+            let end = !escaped && *c == '"';
+            escaped = !escaped && *c == '\\';
+            end
+        })?;
+        result
+            .get_or_insert_with(String::new)
+            .push_str(&literal[..end]);
+        rest = literal[end + 1..].trim_start();
+    }
+    result
+}
+
+/// Check if a scanf `format` contains a string conversion (`%s` or `%[`) which does not
+/// limit the length of the input by a maximal field width
+/// Conversions with suppressed assignment (`%*s`) or allocating the buffer (`%ms`) are safe.
+fn has_unbounded_conversion(format: &str) -> bool {
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        let mut bounded = false;
+        while let Some(c) = chars.next_if(|c| "*0123456789hlLjztqm".contains(*c)) {
+            bounded |= c == '*' || c == 'm' || c.is_ascii_digit();
+        }
+        // `%%` is consumed here, too
+        if matches!(chars.next(), Some('s' | '[')) && !bounded {
+            return true;
+        }
+    }
+    false
+}
+
+/// Check that calls of paired functions (e.g. malloc/free) are balanced
+/// A finding is reported for each pair whose first function is called more times than
+/// the second one, by more than `slack`.
+pub struct PairBalanceAnalyser {
+    pairs: Vec<(String, String)>,
+    slack: i64,
+    severity: Severity,
+}
+
+impl PairBalanceAnalyser {
+    pub fn new(pairs: Vec<(String, String)>, slack: i64, severity: Severity) -> Self {
+        Self {
+            pairs,
+            slack,
+            severity,
+        }
+    }
+}
+
+impl Analyser for PairBalanceAnalyser {
+    fn name(&self) -> &str {
+        "pair-balance"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let funs: Vec<String> = self
+            .pairs
+            .iter()
+            .flat_map(|(open, close)| vec![open.clone(), close.clone()])
+            .collect();
+        let calls = find_calls(&solution.source, &funs, "pair-balance")?;
+        let count = |fun: &str| calls.iter().filter(|(_, f)| f == fun).count();
+
+        Ok(self
+            .pairs
+            .iter()
+            .map(|(open, close)| (open, count(open), close, count(close)))
+            .filter(|(_, opened, _, closed)| *opened as i64 - *closed as i64 > self.slack)
+            .map(|(open, opened, close, closed)| {
+                Finding::new(
+                    None,
+                    format!(
+                        "'{}' called {} times, '{}' called {} times",
+                        open, opened, close, closed
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    fn penalty(&self) -> f64 {
+        self.severity.penalty()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Check that the size of the source is within given bounds
+/// Counts non-blank lines of the source without comments. Optionally, the source is
+/// compared to a template distributed to students and reported if it is (almost) identical.
+pub struct SourceSizeAnalyser {
+    min_lines: Option<usize>,
+    max_lines: Option<usize>,
+    // Normalized lines of the template and the maximal allowed similarity to it
+    template: Option<(String, Vec<String>)>,
+    max_similarity: f64,
+    severity: Severity,
+}
+
+impl SourceSizeAnalyser {
+    pub fn new(
+        min_lines: Option<usize>,
+        max_lines: Option<usize>,
+        template: Option<(String, String)>,
+        severity: Severity,
+    ) -> Self {
+        Self {
+            min_lines,
+            max_lines,
+            template: template.map(|(name, source)| (name, normalized_lines(&source))),
+            max_similarity: 0.95,
+            severity,
+        }
+    }
+}
+
+impl Analyser for SourceSizeAnalyser {
+    fn name(&self) -> &str {
+        "source-size"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let lines = strip_comments(&solution.source)
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .count();
+        let mut findings = vec![];
+        if let Some(min) = self.min_lines.filter(|min| lines < *min) {
+            findings.push(Finding::new(
+                None,
+                format!("source has {} lines, expected at least {}", lines, min),
+            ));
+        }
+        if let Some(max) = self.max_lines.filter(|max| lines > *max) {
+            findings.push(Finding::new(
+                None,
+                format!("source has {} lines, expected at most {}", lines, max),
+            ));
+        }
+        if let Some((name, template)) = &self.template {
+            let ratio = similarity(&normalized_lines(&solution.source), template);
+            if ratio >= self.max_similarity {
+                findings.push(Finding::new(
+                    None,
+                    format!("source is {:.0}% identical to {}", ratio * 100.0, name),
+                ));
+            }
+        }
+        Ok(findings)
+    }
+
+    fn penalty(&self) -> f64 {
+        self.severity.penalty()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Check that the source is not (almost) the same as the template distributed to students
+/// Sources whose line-based similarity to the template exceeds `threshold` are reported.
+pub struct TemplateSimilarityAnalyser {
+    name: String,
+    template: Vec<String>,
+    threshold: f64,
+    severity: Severity,
+}
+
+impl TemplateSimilarityAnalyser {
+    pub fn new(template: (String, String), threshold: f64, severity: Severity) -> Self {
+        Self {
+            name: template.0,
+            template: normalized_lines(&template.1),
+            threshold,
+            severity,
+        }
+    }
+}
+
+impl Analyser for TemplateSimilarityAnalyser {
+    fn name(&self) -> &str {
+        "template-similarity"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        let ratio = similarity(&normalized_lines(&solution.source), &self.template);
+        Ok(match ratio > self.threshold {
+            true => vec![Finding::new(
+                None,
+                format!("similarity to {} is {:.2}", self.name, ratio),
+            )],
+            false => vec![],
+        })
+    }
+
+    fn penalty(&self) -> f64 {
+        self.severity.penalty()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Check that the solution does not print the expected outputs of tests verbatim
+/// Expected outputs of test cases which are at least `min_length` characters long are
+/// searched for in the string literals of the source. The solution is reported if at
+/// least `threshold` (a fraction) of them is found.
+pub struct HardcodedOutputAnalyser {
+    /// Names of the tests and (normalized) expected outputs of their cases
+    outputs: Vec<(String, String)>,
+    threshold: f64,
+    severity: Severity,
+}
+
+impl HardcodedOutputAnalyser {
+    pub fn new(tests: &[Test], min_length: usize, threshold: f64, severity: Severity) -> Self {
+        let outputs = tests
+            .iter()
+            .flat_map(|test| {
+                test.test_cases
+                    .iter()
+                    .filter_map(|tc| tc.stdout.as_deref())
+                    .map(move |stdout| (test.name.clone(), normalize_output(stdout)))
+            })
+            .filter(|(_, stdout)| stdout.chars().count() >= min_length)
+            .collect();
+        Self {
+            outputs,
+            threshold,
+            severity,
+        }
+    }
+}
+
+/// Output without trailing whitespace on lines and leading/trailing blank lines
+fn normalize_output(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+impl Analyser for HardcodedOutputAnalyser {
+    fn name(&self) -> &str {
+        "hardcoded-output"
+    }
+
+    fn analyse(&self, solution: &Solution) -> Result<Vec<Finding>, AtstError> {
+        if self.outputs.is_empty() {
+            return Ok(vec![]);
+        }
+        let literals: Vec<(Option<usize>, String)> = solution
+            .literals()
+            .iter()
+            .map(|(line, literal)| (*line, normalize_output(literal)))
+            .collect();
+        let mut line = None;
+        let mut tests: Vec<&str> = vec![];
+        let mut found = 0;
+        for (test, output) in &self.outputs {
+            if let Some((l, _)) = literals.iter().find(|(_, lit)| lit.contains(output)) {
+                line = line.or(*l);
+                found += 1;
+                if !tests.contains(&test.as_str()) {
+                    tests.push(test);
+                }
+            }
+        }
+        if found == 0 || (found as f64) < self.threshold * self.outputs.len() as f64 {
+            return Ok(vec![]);
+        }
+        Ok(vec![Finding::new(
+            line,
+            format!(
+                "expected outputs of {} of {} test cases are in string literals (tests {})",
+                found,
+                self.outputs.len(),
+                tests
+                    .iter()
+                    .map(|t| format!("'{}'", t))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        )])
+    }
+
+    fn penalty(&self) -> f64 {
+        self.severity.penalty()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Find calls of any of `funs` in `source` (comments and literals are not considered)
+/// Returns the byte offsets and the names of the called functions.
+pub(crate) fn find_calls(
+    source: &str,
+    funs: &[String],
+    analyser: &str,
+) -> Result<Vec<(usize, String)>, AtstError> {
+    if funs.is_empty() {
+        return Ok(vec![]);
+    }
+    let funs: Vec<String> = funs.iter().map(|f| regex::escape(f)).collect();
+    let re = Regex::new(&format!(r"\b({})\s*\(", funs.join("|"))).map_err(|_| {
+        AtstError::InternalError {
+            msg: format!("{} analyser regex error", analyser),
+        }
+    })?;
+    Ok(re
+        .captures_iter(&mask_literals(source))
+        .map(|call| {
+            let m = call.get(1).unwrap();
+            (m.start(), m.as_str().to_string())
+        })
+        .collect())
+}
+
+/// String literals of `source` with the byte offsets where they start
+/// Adjacent literals (separated by whitespace only) are concatenated into one and escape
+/// sequences are decoded, so the result corresponds to the string used by the program.
+pub(crate) fn string_literals(source: &str) -> Vec<(usize, String)> {
+    // Quotes of character literals and escaped quotes are masked, too
+    let masked = mask_literals(source);
+    let quotes: Vec<usize> = masked.match_indices('"').map(|(i, _)| i).collect();
+    let mut result: Vec<(usize, String)> = vec![];
+    let mut prev_end = None;
+    for pair in quotes.chunks_exact(2) {
+        let (open, close) = (pair[0], pair[1]);
+        let contents = unescape(&source[open + 1..close]);
+        match (prev_end, result.last_mut()) {
+            (Some(end), Some((_, last))) if masked[end..open].trim().is_empty() => {
+                last.push_str(&contents)
+            }
+            _ => result.push((open, contents)),
+        }
+        prev_end = Some(close + 1);
+    }
+    result
+}
+
+/// Object-like macros defined in `source` (which is not preprocessed) with their replacement
+/// texts. Lines continued by a backslash are joined, comments are removed, and whitespace
+/// is collapsed. If a macro is defined multiple times, the last definition is used.
+pub(crate) fn object_like_defines(source: &str) -> Vec<(String, String)> {
+    let define_re = Regex::new(r"^\s*#\s*define\s+([A-Za-z_]\w*)(\(|\s|$)").unwrap();
+    let mut defines: Vec<(String, String)> = vec![];
+    // Positions of the macros in `defines`
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for line in strip_comments(source).replace("\\\n", " ").lines() {
+        let define = match define_re.captures(line) {
+            Some(define) if &define[2] != "(" => define,
+            _ => continue,
+        };
+        let name = define[1].to_string();
+        let text = line[define.get(1).unwrap().end()..]
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        match positions.get(&name) {
+            Some(&i) => defines[i].1 = text,
+            None => {
+                positions.insert(name.clone(), defines.len());
+                defines.push((name, text));
+            }
+        }
+    }
+    defines
+}
+
+/// Metrics of `source` (which is not preprocessed, see `SourceMetrics`)
+/// Functions are found in the source without comments and preprocessor directives, so
+/// functions defined by macros are not counted.
+pub(crate) fn source_metrics(source: &str) -> Result<SourceMetrics, AtstError> {
+    let stripped = strip_comments(source);
+    let (mut lines, mut non_blank, mut commented) = (0, 0, 0);
+    for (line, original) in stripped.lines().zip(source.lines()) {
+        if original.trim().is_empty() {
+            continue;
+        }
+        non_blank += 1;
+        if !line.trim().is_empty() {
+            lines += 1;
+        }
+        if line != original {
+            commented += 1;
+        }
+    }
+    let include_re = Regex::new(r"^\s*#\s*include\b").map_err(|_| AtstError::InternalError {
+        msg: "source metrics regex error".to_string(),
+    })?;
+    let code = mask_literals(&stripped)
+        .lines()
+        .map(|line| match line.trim_start().starts_with('#') {
+            true => "",
+            false => line,
+        })
+        .collect::<Vec<&str>>()
+        .join("\n");
+    let functions = function_definitions(&code, "source metrics")?;
+    Ok(SourceMetrics {
+        lines,
+        functions: functions.len(),
+        includes: stripped.lines().filter(|l| include_re.is_match(l)).count(),
+        longest_function: functions
+            .iter()
+            .map(|f| code[f.body.clone()].matches('\n').count() + 1)
+            .max()
+            .unwrap_or(0),
+        comment_ratio: match non_blank {
+            0 => 0.0,
+            n => (commented as f64 / n as f64 * 100.0).round() / 100.0,
+        },
+    })
+}
+
+/// Decode escape sequences of a string literal
+/// Numeric escapes other than `\0` are not supported and are kept without the backslash.
+fn unescape(literal: &str) -> String {
+    let mut result = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Replace contents of string and character literals in `source` by spaces
+/// Keeps the byte offsets and the lines of the source intact.
+pub(crate) fn mask_literals(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut quote = None;
+    let mut escaped = false;
+    for c in source.chars() {
+        match quote {
+            Some(q) if !escaped && c == q => {
+                quote = None;
+                result.push(c);
+            }
+            Some(_) => {
+                escaped = !escaped && c == '\\';
+                match c {
+                    '\n' => result.push('\n'),
+                    _ => result.extend(std::iter::repeat_n(' ', c.len_utf8())),
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                result.push(c);
+            }
+        }
+    }
+    result
+}
+
+/// Replace comments in `source` by spaces (literals are kept)
+/// Keeps the lines of the source intact.
+pub(crate) fn strip_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut quote = None;
+    let mut escaped = false;
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if !escaped && c == q {
+                    quote = None;
+                }
+                escaped = !escaped && c == '\\';
+                result.push(c);
+            }
+            None if c == '/' && chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            None if c == '/' && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        result.push('\n');
+                    }
+                    prev = c;
+                }
+                result.push(' ');
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                result.push(c);
+            }
+        }
+    }
+    result
+}
+
+/// Lines of `source` without comments, preprocessor directives, and blank lines,
+/// with whitespace collapsed to single spaces
+pub(crate) fn normalized_lines(source: &str) -> Vec<String> {
+    strip_comments(source)
+        .lines()
+        .map(|l| l.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect()
+}
+
+/// Similarity of two sequences of lines as a ratio between 0 and 1
+/// Computed as 2*M / T where M is the number of lines of the longest common
+/// subsequence and T is the total number of lines.
+pub(crate) fn similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let mut lcs = vec![0; b.len() + 1];
+    for line in a {
+        let mut diag = 0;
+        for (j, other) in b.iter().enumerate() {
+            let up = lcs[j + 1];
+            lcs[j + 1] = match line == other {
+                true => diag + 1,
+                false => lcs[j + 1].max(lcs[j]),
+            };
+            diag = up;
+        }
+    }
+    2.0 * lcs[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+/// Function defined in a source
+/// `offset` is the byte offset of the function name, `body` is the byte range of the body
+/// (including the braces).
+pub(crate) struct FunctionDefinition<'s> {
+    pub name: &'s str,
+    pub offset: usize,
+    pub body: Range<usize>,
+}
+
+/// Get definitions of functions in `source`
+/// The source should have literals masked (see `mask_literals`).
+pub(crate) fn function_definitions<'s>(
+    source: &'s str,
+    analyser: &str,
+) -> Result<Vec<FunctionDefinition<'s>>, AtstError> {
+    let name_re = Regex::new(r"([A-Za-z_]\w*)\s*\($").map_err(|_| AtstError::InternalError {
+        msg: format!("{} analyser regex error", analyser),
+    })?;
+    Ok(functions(source)
+        .into_iter()
+        .filter_map(|item| {
+            let body = item.start + source[item.clone()].find('{')?;
+            let header = &source[item.start..body];
+            let name = name_re.captures(&header[..=header.find('(')?])?.get(1)?;
+            Some(FunctionDefinition {
+                name: name.as_str(),
+                offset: item.start + name.start(),
+                body: body..item.end,
+            })
+        })
+        .collect())
+}
+
+/// Get byte ranges of function definitions (including their signatures) in `source`
+/// The source should have literals masked (see `mask_literals`).
+pub(crate) fn functions(source: &str) -> Vec<Range<usize>> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in source.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let item = &source[start..i];
+                    // A function has a parameter list before its body
+                    if item.find('(').is_some_and(|p| p < item.find('{').unwrap()) {
+                        result.push(start..i + 1);
+                    }
+                    start = i + 1;
+                }
+            }
+            ';' if depth == 0 => start = i + 1,
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::modules::{Module, Parser};
+    use crate::test_utils::get_solution;
+
+    fn test_on(analyser: &dyn Analyser, src: &str, included: &[String], expected: bool) {
+        let mut solution = get_solution(src, true);
+        solution.included = included.to_vec();
+        let res = analyser.analyse(&solution);
+        assert!(res.is_ok());
+        assert_eq!(!res.unwrap().is_empty(), expected);
+    }
+
+    fn test_on_default(analyser: &dyn Analyser, expected: bool) {
+        let src = r#"#include <stdio.h>
+                     int x;
+                     int main() {
+                         printf("foo");
+                     }
+                  "#;
+        test_on(analyser, src, &["stdio.h".to_string()], expected);
+    }
+
+    #[test]
+    fn no_call_analyser_match() {
+        let analyser = NoCallAnalyser {
+            funs: vec!["printf".to_string()],
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, true);
+    }
+
+    #[test]
+    fn no_call_analyser_nomatch() {
+        let analyser = NoCallAnalyser {
+            funs: vec!["foo".to_string()],
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, false);
+    }
+
+    #[test]
+    fn require_call_analyser_match() {
+        let analyser = RequireCallAnalyser {
+            funs: vec!["printf".to_string(), "qsort".to_string()],
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, true);
+        let solution = get_solution("int main() { qsort(a, n, sizeof(int), cmp); }", true);
+        assert_eq!(
+            analyser.analyse(&solution).unwrap(),
+            vec![Finding::new(None, "no call of 'printf'".to_string())]
+        );
+    }
+
+    #[test]
+    fn require_call_analyser_nomatch() {
+        let analyser = RequireCallAnalyser {
+            funs: vec!["printf".to_string()],
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, false);
+    }
+
+    #[test]
+    fn no_header_analyser_match() {
+        let analyser = NoHeaderAnalyser {
+            header: "stdio.h".to_string(),
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, true);
+    }
+
+    #[test]
+    fn no_header_analyser_nomatch() {
+        let analyser = NoHeaderAnalyser {
+            header: "foo.h".to_string(),
+            penalty: -1.0,
+        };
+        test_on_default(&analyser, false);
+    }
+
+    #[test]
+    fn no_globals_analyser_match() {
+        let analyser = NoGlobalsAnalyser {
+            penalty: -1.0,
+            exceptions: vec![],
+        };
+        test_on_default(&analyser, true);
+    }
+
+    #[test]
+    fn no_globals_analyser_nomatch() {
+        let analyser = NoGlobalsAnalyser {
+            penalty: -1.0,
+            exceptions: vec![],
+        };
+        test_on(&analyser, "int main() {}", &[], false);
+    }
+
+    #[test]
+    fn no_globals_analyser_except() {
+        let analyser = NoGlobalsAnalyser {
+            penalty: -1.0,
+            exceptions: vec!["x.*".to_string()],
+        };
+        test_on_default(&analyser, false);
+    }
+
+    fn recursion_findings(src: &str, exceptions: &[&str]) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let exceptions = exceptions.iter().map(|e| e.to_string()).collect();
+        let res = NoRecursionAnalyser::new(-1.0, exceptions).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn no_recursion_analyser_match() {
+        let src = r#"int fact(int n) {
+    return n <= 1 ? 1 : n * fact(n - 1);
+}
+int is_odd(int n);
+int is_even(int n) {
+    return n == 0 ? 1 : is_odd(n - 1);
+}
+int is_odd(int n) {
+    return n == 0 ? 0 : is_even(n - 1);
+}
+int main() {
+    return fact(3) + is_even(4);
+}"#;
+        let findings = recursion_findings(src, &[]);
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![Some(1), Some(5), Some(8)]);
+        assert_eq!(findings[0].message, "recursive function 'fact'");
+        assert_eq!(
+            findings[1].message,
+            "recursive function 'is_even' (through 'is_odd')"
+        );
+
+        // Exceptions do not hide other functions of the same cycle
+        let findings = recursion_findings(src, &["fact", "is_even"]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "recursive function 'is_odd' (through 'is_even')"
+        );
+    }
+
+    #[test]
+    fn no_recursion_analyser_nomatch() {
+        let findings = recursion_findings(
+            r#"#include <stdio.h>
+int sum(int *a, int n) {
+    int s = 0;
+    for (int i = 0; i < n; i++)
+        s += a[i];
+    return s;
+}
+int main() {
+    int a[] = { 1, 2, 3 };
+    printf("main() sum(a, 3) = %d\n", sum(a, 3));
+    return 0;
+}"#,
+            &[],
+        );
+        assert!(findings.is_empty());
+    }
+
+    fn function_length_findings(src: &str, limit: u64) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let res = MaxFunctionLengthAnalyser::new(limit, -1.0, false).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn max_function_length_analyser() {
+        let long_body = "    x++;\n".repeat(98);
+        let src = format!(
+            "int x;
+void long_fun() {{
+{}}}
+int short_fun(int a) {{
+    int b = a * 2;
+    b += x;
+    return b;
+}}
+int one_line() {{ return 1; }}
+int main() {{ long_fun(); return short_fun(one_line()); }}",
+            long_body
+        );
+        let findings = function_length_findings(&src, 5);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, Some(2));
+        assert_eq!(
+            findings[0].message,
+            "function 'long_fun' has 100 lines (limit 5)"
+        );
+        // The limit is inclusive, one-line functions have a single line
+        assert_eq!(function_length_findings(&src, 100).len(), 0);
+        assert_eq!(function_length_findings(&src, 4).len(), 2);
+        assert_eq!(function_length_findings(&src, 1).len(), 2);
+    }
+
+    #[test]
+    fn max_function_length_analyser_directives() {
+        // Lines of directives and of the excluded code are counted
+        let src = "int f(int a) {
+#ifdef DEBUG
+    if (a > 0) {
+        a--;
+#else
+    if (a < 0) {
+#endif
+        a++;
+    }
+    return a;
+}
+int main() { return f(1); }";
+        let findings = function_length_findings(src, 10);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].message, "function 'f' has 11 lines (limit 10)");
+        assert!(function_length_findings(src, 11).is_empty());
+    }
+
+    #[test]
+    fn max_function_length_analyser_penalty() {
+        let finding = Finding::new(None, String::new());
+        let once = MaxFunctionLengthAnalyser::new(10, -0.5, false);
+        let each = MaxFunctionLengthAnalyser::new(10, -0.5, true);
+        assert_eq!(once.penalty_for(&[]), 0.0);
+        assert_eq!(each.penalty_for(&[]), 0.0);
+        assert_eq!(once.penalty_for(&vec![finding.clone(); 3]), -0.5);
+        assert_eq!(each.penalty_for(&vec![finding; 3]), -1.5);
+    }
+
+    fn float_eq_findings(src: &str) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let res = NoFloatEqAnalyser::new(-1.0, None).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn no_float_eq_analyser_match() {
+        let findings = float_eq_findings(
+            r#"#include <stdio.h>
+int f(double x, int n) {
+    double y = 0.5, *p = &y;
+    float z;
+    if (x == y) return 1;
+    if (n != 1.0) return 2;
+    if (z==0) return 3;
+    return 0;
+}
+int main() {
+    return 1e-3 == 0.001;
+}"#,
+        );
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![Some(5), Some(6), Some(7), Some(11)]);
+        assert_eq!(findings[0].message, "floating-point comparison 'x == y'");
+    }
+
+    #[test]
+    fn no_float_eq_analyser_nomatch() {
+        let findings = float_eq_findings(
+            r#"#include <stdio.h>
+#include <stdlib.h>
+int main(int argc, char **argv) {
+    double *p = NULL, *q = malloc(sizeof(double));
+    int i = 0;
+    double x = atof(argv[1]);
+    if (p == NULL || p != q || i == 0 || argc != 2) return 1;
+    printf("x == 1.0\n");
+    return i == atoi("1") ? '=' : (int)x;
+}"#,
+        );
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn no_float_eq_analyser_penalty() {
+        let finding = Finding::new(None, String::new());
+        let analyser = NoFloatEqAnalyser::new(-0.5, Some(-1.0));
+        assert_eq!(analyser.penalty_for(&[]), 0.0);
+        assert_eq!(analyser.penalty_for(std::slice::from_ref(&finding)), -0.5);
+        assert_eq!(analyser.penalty_for(&vec![finding; 3]), -1.0);
+    }
+
+    fn goto_findings(src: &str, max_allowed: usize) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let res = NoGotoAnalyser::new(-1.0, max_allowed).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn no_goto_analyser() {
+        let src = r#"#include <stdio.h>
+int main() {
+    int i = 0;
+    /* goto end; */
+    puts("goto end;"); // goto end;
+again:
+    if (++i < 3) goto again;
+    if (i == 3)
+        goto end;
+end:
+    return 0;
+}"#;
+        let findings = goto_findings(src, 1);
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![Some(7), Some(9)]);
+        assert_eq!(
+            findings[0].message,
+            "'goto again' (2 gotos, at most 1 allowed)"
+        );
+        assert!(goto_findings(src, 2).is_empty());
+
+        let analyser = NoGotoAnalyser::new(-1.0, 1);
+        assert_eq!(analyser.penalty_for(&findings), -1.0);
+    }
+
+    fn check_alloc_findings(src: &str) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let funs = vec![
+            "malloc".to_string(),
+            "calloc".to_string(),
+            "realloc".to_string(),
+        ];
+        let res = CheckAllocAnalyser::new(funs, Severity::Report).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn check_alloc_analyser_unchecked() {
+        let findings = check_alloc_findings(
+            r#"#include <stdlib.h>
+int *make(int n) {
+    int *p = (int *)malloc(n * sizeof(int));
+    return p;
+}
+int main() {
+    int *arr = calloc(10, sizeof *arr);
+    arr[0] = 1;
+    char *s;
+    s = malloc(2);
+    *s = 'a';
+    if (s == NULL) return 1;
+    return 0;
+}"#,
+        );
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![Some(3), Some(7), Some(10)]);
+        assert_eq!(
+            findings[1].message,
+            "result of 'calloc' assigned to 'arr' is used at line 8 without a NULL check"
+        );
+    }
+
+    #[test]
+    fn check_alloc_analyser_checked() {
+        let findings = check_alloc_findings(
+            r#"#include <stdlib.h>
+int *make(int n) {
+    int *p = malloc(n * sizeof(int));
+    if (!p)
+        return NULL;
+    p[0] = n;
+    return p;
+}
+int main() {
+    int *arr = calloc(10, sizeof(int)), x = 2;
+    if (arr == NULL) return 1;
+    arr[0] = x * 2;
+    int *tmp = realloc(arr, 20 * sizeof(int));
+    if (tmp)
+        arr = tmp;
+    *arr = 3;
+    char *s = malloc(2);
+    if (NULL != s) *s = 'a';
+    free(arr);
+    return 0;
+}"#,
+        );
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn check_alloc_analyser_return_deref() {
+        let findings = check_alloc_findings(
+            r#"#include <stdlib.h>
+int main() {
+    int *p = malloc(sizeof(int));
+    return *p;
+}"#,
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, Some(3));
+    }
+
+    #[test]
+    fn check_alloc_analyser_severity() {
+        let report = CheckAllocAnalyser::new(vec![], Severity::Report);
+        let penalty = CheckAllocAnalyser::new(vec![], Severity::Penalty(-1.0));
+        let finding = Finding::new(None, String::new());
+        assert_eq!(report.penalty_for(std::slice::from_ref(&finding)), 0.0);
+        assert_eq!(penalty.penalty_for(std::slice::from_ref(&finding)), -1.0);
+    }
+
+    fn unsafe_input_findings(src: &str) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let res = UnsafeInputAnalyser::new(UnsafeInputRule::defaults(), -1.0).analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn unsafe_input_analyser_match() {
+        let findings = unsafe_input_findings(
+            r#"#include <stdio.h>
+#include <string.h>
+int main() {
+    char buf[20], name[20];
+    gets(buf);
+    scanf("%s", buf);
+    scanf("%d %[^\n]", &x, buf);
+    fscanf(stdin, "%5s " "%s", name, buf);
+    fscanf(open(names[0], "r, w"), "%ls", (wchar_t *)buf);
+    strcpy(name, buf);
+    return 0;
+}"#,
+        );
+        let lines: Vec<Option<usize>> = findings.iter().map(|f| f.line).collect();
+        assert_eq!(
+            lines,
+            vec![Some(5), Some(6), Some(7), Some(8), Some(9), Some(10)]
+        );
+        assert_eq!(
+            findings[0].message,
+            "gets: 'gets' cannot limit the length of the input (use 'fgets')"
+        );
+        assert!(findings[1].message.starts_with("scanf-string: "));
+        assert!(findings[5].message.starts_with("strcpy: "));
+    }
+
+    #[test]
+    fn unsafe_input_analyser_nomatch() {
+        let findings = unsafe_input_findings(
+            r#"#include <stdio.h>
+int main(int argc, char **argv) {
+    char buf[20], *line;
+    // gets(buf);
+    fgets(buf, 20, stdin);
+    printf("gets(buf); %s\n", buf);
+    scanf("%19s %d%%s", buf, &x);
+    scanf("%*s %ms", &line);
+    fscanf(stdin, "%19[^\n]", buf);
+    fscanf(stdin, argv[1], buf);
+    scanf(argv[1]);
+    return 0;
+}"#,
+        );
+        assert_eq!(findings, vec![]);
+    }
+
+    #[test]
+    fn unsafe_input_analyser_penalty() {
+        let mut rules = UnsafeInputRule::defaults();
+        let analyser = UnsafeInputAnalyser::new(rules.clone(), -1.0);
+        let finding = |rule: &str| Finding::new(None, format!("{}: message", rule));
+        assert_eq!(analyser.penalty_for(&[finding("strcpy")]), 0.0);
+        assert_eq!(
+            analyser.penalty_for(&[finding("strcpy"), finding("gets")]),
+            -1.0
+        );
+        assert_eq!(analyser.severity(), Severity::Penalty(-1.0));
+
+        rules.retain(|r| r.report);
+        let analyser = UnsafeInputAnalyser::new(rules, 0.0);
+        assert_eq!(analyser.severity(), Severity::Report);
+    }
+
+    #[test]
+    fn unbounded_conversions() {
+        assert!(has_unbounded_conversion("%s"));
+        assert!(has_unbounded_conversion("%d,%[a-z]"));
+        assert!(has_unbounded_conversion("%lls"));
+        assert!(!has_unbounded_conversion("%10s %*s %ms %%s"));
+        assert!(!has_unbounded_conversion("%d %c"));
+        assert_eq!(
+            string_literal(r#" "a,\"%s" "b" , x"#),
+            Some(r#"a,\"%sb"#.to_string())
+        );
+        assert_eq!(string_literal("fmt"), None);
+        let masked = mask_literals(r#"f(a(1, 2), "x, y", z)"#);
+        assert_eq!(call_argument(&masked, 1, 0), Some(2));
+        assert_eq!(call_argument(&masked, 1, 1), Some(10));
+        assert_eq!(call_argument(&masked, 1, 2), Some(18));
+        assert_eq!(call_argument(&masked, 1, 3), None);
+    }
+
+    #[test]
+    fn string_literals_concatenated() {
+        let source = r#"printf("a\tb\n" "c\"d\n"
+                              "e");
+                       char c = '"', *s = "\\";
+                       puts("");"#;
+        let literals: Vec<String> = string_literals(source)
+            .into_iter()
+            .map(|(_, literal)| literal)
+            .collect();
+        assert_eq!(literals, vec!["a\tb\nc\"d\ne", "\\", ""]);
+        assert_eq!(string_literals(source)[0].0, 7);
+    }
+
+    fn hardcoded_output_findings(src: &str, outputs: &[&str], threshold: f64) -> Vec<Finding> {
+        let mut solution = get_solution(src, false);
+        let res = Parser {}.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let tests: Vec<Test> = outputs
+            .iter()
+            .enumerate()
+            .map(|(i, stdout)| Test {
+                name: format!("test{}", i / 2),
+                test_cases: vec![crate::TestCase {
+                    stdout: Some(stdout.to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .collect();
+        let res = HardcodedOutputAnalyser::new(&tests, 10, threshold, Severity::Report)
+            .analyse(&solution);
+        assert!(res.is_ok());
+        res.unwrap()
+    }
+
+    #[test]
+    fn hardcoded_output_analyser() {
+        let src = r#"#include <stdio.h>
+int main(int argc, char **argv) {
+    if (argc > 2) {
+        printf("Sum: 10  \n"
+               "Average: 2.5\n");
+        return 0;
+    }
+    printf("Sum: %d\n", argc);
+    puts("Error: no input");
+}"#;
+        let outputs = [
+            "Sum: 10\nAverage: 2.5\n",
+            "Error: no input\n",
+            "Sum: 5\nAverage: 1.25\n",
+            "short",
+        ];
+        let findings = hardcoded_output_findings(src, &outputs, 0.5);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, Some(4));
+        assert_eq!(
+            findings[0].message,
+            "expected outputs of 2 of 3 test cases are in string literals (tests 'test0')"
+        );
+        assert!(hardcoded_output_findings(src, &outputs, 0.8).is_empty());
+        assert!(hardcoded_output_findings(src, &outputs[2..], 0.1).is_empty());
+        assert!(hardcoded_output_findings(src, &outputs[3..], 0.0).is_empty());
+    }
+
+    #[test]
+    fn pair_balance_analyser() {
+        let solution = get_solution(
+            r#"#include <stdio.h>
+#include <stdlib.h>
+int main() {
+    int *p = malloc(4), *q = malloc(4);
+    FILE *f = fopen("f", "r");
+    free(p);
+    printf("fopen(x) malloc(y)\n");
+    fclose(f);
+    return my_malloc(1);
+}"#,
+            false,
+        );
+        let pairs = vec![
+            ("malloc".to_string(), "free".to_string()),
+            ("fopen".to_string(), "fclose".to_string()),
+        ];
+        let analyser = PairBalanceAnalyser::new(pairs.clone(), 0, Severity::Report);
+        let res = analyser.analyse(&solution);
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            vec![Finding::new(
+                None,
+                "'malloc' called 2 times, 'free' called 1 times".to_string()
+            )]
+        );
+        let analyser = PairBalanceAnalyser::new(pairs, 1, Severity::Report);
+        assert_eq!(analyser.analyse(&solution).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn source_size_analyser() {
+        let template = "#include <stdio.h>\n\nint main() {\n    // TODO\n    return 0;\n}\n";
+        let analyser = SourceSizeAnalyser::new(
+            Some(4),
+            Some(6),
+            Some(("skeleton.c".to_string(), template.to_string())),
+            Severity::Penalty(-1.0),
+        );
+
+        let solution = get_solution(
+            "int main() {\n  /* nothing\n here */\n  return 0;\n}",
+            false,
+        );
+        assert_eq!(
+            analyser.analyse(&solution).unwrap(),
+            vec![
+                Finding::new(None, "source has 3 lines, expected at least 4".to_string()),
+                Finding::new(None, "source is 100% identical to skeleton.c".to_string()),
+            ]
+        );
+
+        let solution = get_solution(
+            "int main() {\n  int x = 1;\n  x++;\n  x--;\n  x++;\n  x--;\n  return x;\n}",
+            false,
+        );
+        assert_eq!(
+            analyser.analyse(&solution).unwrap(),
+            vec![Finding::new(
+                None,
+                "source has 8 lines, expected at most 6".to_string()
+            )]
+        );
+
+        let solution = get_solution("int main() {\n  int x = 1;\n  x++;\n  return x;\n}", false);
+        assert_eq!(analyser.analyse(&solution).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn template_similarity_analyser() {
+        let template = "int main() {\n    // TODO\n    return 0;\n}\n";
+        let analyser = TemplateSimilarityAnalyser::new(
+            ("skeleton.c".to_string(), template.to_string()),
+            0.7,
+            Severity::Report,
+        );
+        let solution = get_solution("int main()  {\n  int x;\n  return 0;\n}", false);
+        assert_eq!(
+            analyser.analyse(&solution).unwrap(),
+            vec![Finding::new(
+                None,
+                "similarity to skeleton.c is 0.86".to_string()
+            )]
+        );
+        let solution = get_solution("int main() {\n  int x;\n  x++;\n  return x;\n}", false);
+        assert_eq!(analyser.analyse(&solution).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn strip_comments_ok() {
+        assert_eq!(
+            strip_comments("a; // c\nb /* c\nc */ d;\n\"/* s */\" '/'"),
+            "a; \nb \n  d;\n\"/* s */\" '/'"
+        );
+    }
+
+    #[test]
+    fn source_metrics_ok() {
+        let source = r#"#include <stdio.h>
+#include "list.h"
+#define MAX(a, b) { (a) > (b) ? (a) : (b) }
+
+/* Prints "{"
+   and returns */
+int brace(void) {
+    return puts("{"); // the brace
+}
+
+int main(void)
+{
+    brace();
+
+    return 0;
+}
+"#;
+        assert_eq!(
+            source_metrics(source).unwrap(),
+            SourceMetrics {
+                lines: 11,
+                functions: 2,
+                includes: 2,
+                longest_function: 5,
+                comment_ratio: 0.23,
+            }
+        );
+        assert_eq!(source_metrics("").unwrap(), SourceMetrics::default());
+    }
+
+    #[test]
+    fn similarity_ok() {
+        let lines = |s: &str| s.split(' ').map(String::from).collect::<Vec<String>>();
+        assert_eq!(similarity(&lines("a b c d"), &lines("a b c d")), 1.0);
+        assert_eq!(similarity(&lines("a b c d"), &lines("e f")), 0.0);
+        assert_eq!(similarity(&lines("a b c d"), &lines("a c e f")), 0.5);
+        assert_eq!(similarity(&lines("a b"), &lines("b a b")), 0.8);
+        assert_eq!(similarity(&[], &[]), 1.0);
+        assert_eq!(similarity(&lines("a"), &[]), 0.0);
+        assert_eq!(
+            similarity(&lines("a b c d e f g h i j"), &lines("a b c d e f g h i x")),
+            0.9
+        );
+        assert_eq!(
+            normalized_lines("#include <x>\n  int   x;  // c\n\n"),
+            vec!["int x;"]
+        );
     }
 }
@@ -0,0 +1,330 @@
+//! Audit log of the evaluation (evidence of what exactly was run for each solution)
+//!
+//! The log is a file with one JSON object per line, new runs are appended to it. Each
+//! object has the following fields:
+//!   - `version`: version of the format (`AUDIT_LOG_VERSION`),
+//!   - `time`: Unix time (in seconds, with a fractional part) of the event,
+//!   - `solution`: name of the solution,
+//!   - `event`: kind of the event, one of:
+//!     - `execution`: a program was run (the compiler, the linker, or a test case), with
+//!       the fields `kind`, `command` (list of strings), `cwd`, `env-hash` (of the
+//!       variables set in addition to the inherited ones), `input-hash`, `stdout-hash`,
+//!       `stderr-hash`, `exit-code`, and `result` (hashes are SHA-256 digests, `null`
+//!       if the data were not available, e.g. for outputs compared as a stream),
+//!     - `finding`: an analyser finding, with the fields `analyser`, `line`, `message`,
+//!       and `penalty`,
+//!     - `score`: the score of the solution was changed, with the fields `source` (the
+//!       module which changed it, "gate", or "clamp"), `before`, and `after`.
+
+use crate::analyses::AnalyserFinding;
+use crate::digest::Sha256;
+use crate::results::json_string;
+use crate::AtstError;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the audit log format (increased on incompatible changes)
+pub const AUDIT_LOG_VERSION: u32 = 1;
+
+/// Program run during the evaluation of a solution
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    /// Purpose of the run ("compile", "link", or "test")
+    pub kind: String,
+    /// Program and its arguments
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    /// SHA-256 digest of the environment variables set for the program
+    pub env_hash: String,
+    /// SHA-256 digests of the data passed to the program and of its outputs
+    pub input_hash: Option<String>,
+    pub stdout_hash: Option<String>,
+    pub stderr_hash: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Outcome of the run (e.g. "success" for the compiler, "passed" for a test case)
+    pub result: String,
+    pub time: SystemTime,
+}
+
+impl Execution {
+    /// Execution of `cmd` (its outputs and result are to be filled when it finishes)
+    pub fn new(kind: &str, cmd: &Command) -> Self {
+        let mut env: Vec<String> = cmd
+            .get_envs()
+            .map(|(var, value)| {
+                format!(
+                    "{}={}\n",
+                    var.to_string_lossy(),
+                    value.map_or(String::new(), |v| v.to_string_lossy().to_string())
+                )
+            })
+            .collect();
+        env.sort();
+        Self {
+            kind: kind.to_string(),
+            command: std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            cwd: cmd
+                .get_current_dir()
+                .map(Path::to_path_buf)
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default(),
+            env_hash: sha256(env.concat().as_bytes()),
+            input_hash: None,
+            stdout_hash: None,
+            stderr_hash: None,
+            exit_code: None,
+            result: String::new(),
+            time: SystemTime::now(),
+        }
+    }
+}
+
+/// SHA-256 digest of `data`
+pub fn sha256(data: &[u8]) -> String {
+    let mut digest = Sha256::default();
+    digest.update(data);
+    digest.finish()
+}
+
+/// SHA-256 digest of the contents of `file` (None if it cannot be read)
+pub fn sha256_file(file: &Path) -> Option<String> {
+    let mut file = File::open(file).ok()?;
+    let mut digest = Sha256::default();
+    let mut buf = [0u8; 1 << 16];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => digest.update(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+    }
+    Some(digest.finish())
+}
+
+/// Observer of the evaluation of solutions
+/// Events of a solution are passed to the observer after each module run on it, the
+/// observer is shared by solutions evaluated in parallel.
+pub trait EvaluationObserver: Sync {
+    /// A program was run for `solution`
+    fn execution(&self, _solution: &str, _execution: &Execution) {}
+
+    /// An analyser found a problem in `solution`
+    fn finding(&self, _solution: &str, _finding: &AnalyserFinding) {}
+
+    /// The score of `solution` was changed by `source` (a module, "gate", or "clamp")
+    fn score_changed(&self, _solution: &str, _source: &str, _before: f64, _after: f64) {}
+}
+
+/// Observer writing all events to an append-only audit log (see the module docs)
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+    // The first error while writing the log (the evaluation is not interrupted by it)
+    error: Mutex<Option<std::io::Error>>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self, AtstError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AtstError::OutputError {
+                path: path.display().to_string(),
+                source: e,
+            })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Report the first error which occurred while writing the log
+    pub fn finish(self) -> Result<(), AtstError> {
+        match self.error.into_inner().unwrap() {
+            Some(e) => Err(AtstError::OutputError {
+                path: self.path.display().to_string(),
+                source: e,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&self, time: SystemTime, solution: &str, event: &str, fields: &[(&str, String)]) {
+        let time = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let line = format!(
+            "{{\"version\": {}, \"time\": {}.{:06}, \"solution\": {}, \"event\": {}{}}}\n",
+            AUDIT_LOG_VERSION,
+            time.as_secs(),
+            time.subsec_micros(),
+            json_string(solution),
+            json_string(event),
+            fields
+                .iter()
+                .map(|(field, value)| format!(", {}: {}", json_string(field), value))
+                .collect::<String>()
+        );
+        // Each event is written at once so that events of parallel evaluations do not mix
+        if let Err(e) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            self.error.lock().unwrap().get_or_insert(e);
+        }
+    }
+}
+
+/// JSON representation of an optional string
+fn json_option(s: Option<&str>) -> String {
+    s.map_or("null".to_string(), json_string)
+}
+
+impl EvaluationObserver for AuditLog {
+    fn execution(&self, solution: &str, execution: &Execution) {
+        let command = execution
+            .command
+            .iter()
+            .map(|arg| json_string(arg))
+            .collect::<Vec<String>>();
+        self.write(
+            execution.time,
+            solution,
+            "execution",
+            &[
+                ("kind", json_string(&execution.kind)),
+                ("command", format!("[{}]", command.join(", "))),
+                ("cwd", json_string(&execution.cwd.to_string_lossy())),
+                ("env-hash", json_string(&execution.env_hash)),
+                ("input-hash", json_option(execution.input_hash.as_deref())),
+                ("stdout-hash", json_option(execution.stdout_hash.as_deref())),
+                ("stderr-hash", json_option(execution.stderr_hash.as_deref())),
+                (
+                    "exit-code",
+                    execution
+                        .exit_code
+                        .map_or("null".to_string(), |c| c.to_string()),
+                ),
+                ("result", json_string(&execution.result)),
+            ],
+        );
+    }
+
+    fn finding(&self, solution: &str, finding: &AnalyserFinding) {
+        self.write(
+            SystemTime::now(),
+            solution,
+            "finding",
+            &[
+                ("analyser", json_string(&finding.analyser)),
+                (
+                    "line",
+                    finding
+                        .finding
+                        .line
+                        .map_or("null".to_string(), |l| l.to_string()),
+                ),
+                ("message", json_string(&finding.finding.message)),
+                ("penalty", finding.severity.penalty().to_string()),
+            ],
+        );
+    }
+
+    fn score_changed(&self, solution: &str, source: &str, before: f64, after: f64) {
+        self.write(
+            SystemTime::now(),
+            solution,
+            "score",
+            &[
+                ("source", json_string(source)),
+                ("before", before.to_string()),
+                ("after", after.to_string()),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyses::{Finding, Level, Severity};
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn execution_of_command() {
+        let mut cmd = Command::new("gcc");
+        cmd.args(["-c", "main.c"])
+            .current_dir("/tmp")
+            .env("B", "2")
+            .env("A", "1");
+        let execution = Execution::new("compile", &cmd);
+        assert_eq!(execution.command, vec!["gcc", "-c", "main.c"]);
+        assert_eq!(execution.cwd, PathBuf::from("/tmp"));
+        // The order of setting the variables does not matter
+        assert_eq!(execution.env_hash, sha256(b"A=1\nB=2\n"));
+        assert_eq!(
+            Execution::new("test", &Command::new("gcc")).env_hash,
+            sha256(b"")
+        );
+    }
+
+    #[test]
+    fn audit_log_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        std::fs::write(&path, "{}\n").unwrap();
+
+        let log = AuditLog::open(&path).unwrap();
+        let mut execution = Execution::new("test", Command::new("./proj").arg("a\"b"));
+        execution.input_hash = Some(sha256(b"input"));
+        execution.exit_code = Some(0);
+        execution.result = "passed".to_string();
+        log.execution("xlogin00", &execution);
+        log.finding(
+            "xlogin00",
+            &AnalyserFinding {
+                analyser: "no-goto".to_string(),
+                severity: Severity::Penalty(-0.5),
+                level: Level::Error,
+                finding: Finding::new(Some(3), "'goto end'".to_string()),
+            },
+        );
+        log.score_changed("xlogin00", "tests", 0.0, 1.5);
+        assert!(log.finish().is_ok());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        // The log is appended to
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "{}");
+        let events: Vec<_> = lines[1..]
+            .iter()
+            .map(|l| YamlLoader::load_from_str(l).unwrap().remove(0))
+            .collect();
+        for event in &events {
+            assert_eq!(event["version"].as_i64(), Some(AUDIT_LOG_VERSION as i64));
+            assert_eq!(event["solution"].as_str(), Some("xlogin00"));
+            assert!(event["time"].as_f64().is_some());
+        }
+        assert_eq!(events[0]["event"].as_str(), Some("execution"));
+        assert_eq!(events[0]["command"][1].as_str(), Some("a\"b"));
+        assert_eq!(
+            events[0]["input-hash"].as_str(),
+            Some(sha256(b"input").as_str())
+        );
+        assert!(events[0]["stdout-hash"].is_null());
+        assert_eq!(events[0]["exit-code"].as_i64(), Some(0));
+        assert_eq!(events[0]["result"].as_str(), Some("passed"));
+        assert_eq!(events[1]["event"].as_str(), Some("finding"));
+        assert_eq!(events[1]["line"].as_i64(), Some(3));
+        assert_eq!(events[1]["penalty"].as_f64(), Some(-0.5));
+        assert_eq!(events[2]["event"].as_str(), Some("score"));
+        assert_eq!(events[2]["source"].as_str(), Some("tests"));
+        assert_eq!(events[2]["after"].as_f64(), Some(1.5));
+    }
+}
@@ -0,0 +1,142 @@
+//! Unicode-aware text normalizations used when comparing outputs of solutions
+
+use std::io::{BufRead, BufReader, Read};
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalize `s` to the Unicode NFC (canonical composition)
+/// Letters followed by combining marks (e.g. "C\u{30c}") are replaced by their
+/// precomposed forms ("Č"), so outputs differing only in the representation of
+/// characters compare equal.
+pub fn compose(s: &str) -> String {
+    s.nfc().collect()
+}
+
+/// Fold case of `s` using the Unicode simple case folding
+/// Each character is mapped to a single character regardless of its context and of
+/// the locale, so unlike `str::to_lowercase`, final sigma is folded the same way as
+/// other sigmas, 'İ' is kept (instead of expanding to "i̇"), and 'ß' is not expanded
+/// to "ss" (but 'ẞ' is folded to 'ß').
+pub fn fold_case(s: &str) -> String {
+    s.chars().map(fold_char).collect()
+}
+
+fn fold_char(c: char) -> char {
+    // Characters whose folding differs from their lowercase mapping
+    match c {
+        'µ' => 'μ',
+        'ſ' => 's',
+        '\u{345}' | '\u{1fbe}' => 'ι',
+        'ς' => 'σ',
+        'ϐ' => 'β',
+        'ϑ' => 'θ',
+        'ϕ' => 'φ',
+        'ϖ' => 'π',
+        'ϰ' => 'κ',
+        'ϱ' => 'ρ',
+        'ϵ' => 'ε',
+        'ẛ' => 'ṡ',
+        c => {
+            let mut lower = c.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(l), None) => l,
+                _ => c,
+            }
+        }
+    }
+}
+
+/// Compare two streams ignoring leading and trailing whitespace
+/// This is equivalent to comparing trimmed contents of the streams but only a buffer
+/// of each stream is kept in memory.
+pub fn streams_equal_trimmed(a: impl Read, b: impl Read) -> std::io::Result<bool> {
+    let mut a = BufReader::new(a);
+    let mut b = BufReader::new(b);
+    skip_whitespace(&mut a)?;
+    skip_whitespace(&mut b)?;
+    loop {
+        let (len_a, len_b) = (a.fill_buf()?.len(), b.fill_buf()?.len());
+        let len = len_a.min(len_b);
+        let common = (a.buffer().iter().zip(b.buffer()))
+            .take(len)
+            .take_while(|(x, y)| x == y)
+            .count();
+        a.consume(common);
+        b.consume(common);
+        if common < len || len == 0 {
+            break;
+        }
+    }
+    // The streams differ from here, so they are equal only if both remainders are
+    // whitespace (which is trimmed)
+    Ok(only_whitespace(&mut a)? && only_whitespace(&mut b)?)
+}
+
+fn skip_whitespace(reader: &mut impl BufRead) -> std::io::Result<()> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let whitespace = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let end = whitespace < buf.len();
+        reader.consume(whitespace);
+        if end {
+            return Ok(());
+        }
+    }
+}
+
+fn only_whitespace(reader: &mut impl BufRead) -> std::io::Result<bool> {
+    skip_whitespace(reader)?;
+    Ok(reader.fill_buf()?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose_nfc() {
+        assert_eq!(compose("C\u{30c}erve\u{301}"), "Červé");
+        assert_eq!(compose("u\u{30a}l"), "ůl");
+        // Already composed text is kept
+        assert_eq!(compose("Žluťoučký kůň"), "Žluťoučký kůň");
+        // Vietnamese letters with more marks and Hangul syllables
+        assert_eq!(compose("Vie\u{323}\u{302}t"), "Việt");
+        assert_eq!(compose("\u{1112}\u{1161}\u{11ab}"), "한");
+        // Sequences without a precomposed form are kept
+        assert_eq!(compose("q\u{30c}"), "q\u{30c}");
+        assert_eq!(compose("\u{30c}a"), "\u{30c}a");
+    }
+
+    #[test]
+    fn fold_case_unicode() {
+        assert_eq!(fold_case("ČEŘVENÁ"), "čeřvená");
+        assert_eq!(fold_case("Čč"), "čč");
+        assert_eq!(fold_case("STRAẞE"), "straße");
+        assert_eq!(fold_case("straße"), "straße");
+        assert_eq!(fold_case("ΟΔΟΣ"), fold_case("οδος"));
+        assert_eq!(fold_case("İI"), "İi");
+        // Combining characters are only folded after composition
+        assert_eq!(fold_case("C\u{30c}"), "c\u{30c}");
+        assert_eq!(fold_case(&compose("C\u{30c}")), fold_case("č"));
+    }
+
+    #[test]
+    fn streams_trimmed() {
+        let equal = |a: &str, b: &str| streams_equal_trimmed(a.as_bytes(), b.as_bytes()).unwrap();
+        assert!(equal("1 2 3\n", "1 2 3"));
+        assert!(equal("\n  1 2 3 \n\n", "1 2 3"));
+        assert!(equal("", " \n"));
+        assert!(!equal("1 2 3", "1 2 4"));
+        assert!(!equal("1 2 3", "1 2"));
+        assert!(!equal("1 2", "1 2 3"));
+        assert!(!equal("1 2\n3", "1 2 3"));
+        assert!(equal("1 2 \n", "1 2\n"));
+
+        // Streams longer than the buffer which differ only at the end
+        let long = "x".repeat(100_000);
+        assert!(equal(&long, &format!("{}\n", long)));
+        assert!(!equal(&long, &format!("{}y", long)));
+    }
+}
@@ -1,12 +1,27 @@
 extern crate yaml_rust;
 
 use crate::analyses::*;
-use crate::{Test, TestCase, TestCasesRequirement, DEFAULT_TEST_TIMEOUT};
-use log::warn;
-use std::fs::{read_to_string, File};
-use std::io::Read;
+use crate::digest::is_sha256;
+use crate::toml;
+use crate::{
+    parse_timestamp, AnalysesConfig, BinaryLimits, CoverageConfig, LatePenaltyConfig, PenaltyCurve,
+    TimestampSource, WarningsPenalty,
+};
+use crate::{
+    DataFile, Program, Test, TestCase, TestCasesRequirement, TestConfig, TestOrder, Tolerance,
+    Workspace,
+};
+use crate::{ErrorKind, ModuleKind};
+use crate::{SolutionOverride, OVERRIDE_FILE};
+use crate::{DEFAULT_PARTIAL_CREDIT, STREAMED_OUTPUT_BYTES};
+use log::{info, warn};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{read_dir, read_to_string, File};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
 use thiserror::Error;
 use yaml_rust::{Yaml, YamlLoader};
 
@@ -16,6 +31,7 @@ use yaml_rust::{Yaml, YamlLoader};
 ///   - list of test cases to evaluate the solutions on
 ///   - list of source analyses to run on the solutions
 ///   - list of additional scripts to be run on each solution
+///
 /// Typically parsed from a YAML file
 #[derive(Default)]
 pub struct Config {
@@ -25,22 +41,64 @@ pub struct Config {
 
     // Basic information
     pub src_file: String,
+    // Look for the file defining `main` if `src_file` does not exist
+    pub detect_entry_point: bool,
+    // Source files of individual solutions which differ from `src_file`
+    pub entry_points: Vec<(String, String)>,
 
     // Compiler information
     pub compiler: Option<String>,
     pub c_flags: Option<String>,
     pub ld_flags: Option<String>,
+    // Limits of the built executable
+    pub binary_limits: BinaryLimits,
+    // Penalty for compiler warnings
+    pub warnings_penalty: WarningsPenalty,
+    // Additional programs built from the solution
+    pub programs: Vec<Program>,
 
-    // Test execution configuration (ms)
-    pub timeout: u64,
+    // Test execution configuration
+    pub test_config: TestConfig,
+    // Configuration common for all analysers
+    pub analyses_config: AnalysesConfig,
 
     pub tests: Vec<Test>,
     pub analyses: Vec<Box<dyn Analyser>>,
     pub scripts: Vec<PathBuf>,
+
+    // Penalty for late submissions
+    pub late_penalty: Option<LatePenaltyConfig>,
+    // Scoring based on coverage by student's tests
+    pub coverage: Option<CoverageConfig>,
+
+    // Modules to run and their order (the default order if not set)
+    pub pipeline: Option<Vec<ModuleKind>>,
+
+    // Suspicious expected outputs (e.g. with trailing whitespace) found at load
+    pub output_lints: Vec<String>,
+    // Test cases identical to an earlier case of the same test
+    pub duplicate_cases: Vec<String>,
+    // Options which are not supported (and are ignored)
+    pub unknown_options: Vec<UnknownOption>,
+    // Unsupported options make the configuration invalid
+    pub strict: bool,
+    // Negative total scores of solutions are raised to zero
+    pub clamp_score: bool,
+
+    // Seed of the run (generated if not set)
+    pub seed: Option<u64>,
+
+    // Variables substituted for `${name}` in test cases and script paths
+    pub vars: Vec<(String, String)>,
+    // Sources ("<file" or "$(command)") of named inputs shared by test cases
+    pub fixtures: Vec<(String, String)>,
+    // Directory keeping outputs of fixture commands while the configuration is alive
+    pub(crate) fixtures_dir: Option<TempDir>,
 }
 
 /// Configuration errors
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ConfigError {
     #[error("invalid format (should be a YAML dictionary)")]
     InvalidFormat,
@@ -55,15 +113,59 @@ pub enum ConfigError {
         field: String,
         expected_type: String,
     },
-    #[error("command passed to stdin: {msg}")]
+    #[error("invalid command: {msg}")]
     InvalidCommand { msg: String },
+    #[error("undefined fixture '{name}' (defined fixtures: {})", .defined.join(", "))]
+    UndefinedFixture { name: String, defined: Vec<String> },
+    #[error("undefined program '{name}' (defined programs: {})", .defined.join(", "))]
+    UndefinedProgram { name: String, defined: Vec<String> },
+    #[error("undefined variable '{name}' (defined variables: {})", .defined.join(", "))]
+    UndefinedVariable { name: String, defined: Vec<String> },
+    #[error("{location}, field '{field}': {source}")]
+    InTestCase {
+        location: String,
+        field: String,
+        source: Box<ConfigError>,
+    },
+    #[error("{location}: {source}")]
+    InTest {
+        location: String,
+        source: Box<ConfigError>,
+    },
     #[error("'{option}' is missing a mandatory field '{field}'")]
     MissingField { option: String, field: String },
+    #[error("'{option}' cannot combine field '{field}' with '{other}'")]
+    IncompatibleFields {
+        option: String,
+        field: String,
+        other: String,
+    },
+    #[error("files referenced by the configuration cannot be read:{}", .files.iter().map(|f| format!("\n  {}", f)).collect::<String>())]
+    MissingFiles { files: Vec<String> },
+    #[error("circular include of configuration files: {}", .files.join(" -> "))]
+    CircularInclude { files: Vec<String> },
     #[error("{source}")]
     BadFile {
         #[from]
         source: std::io::Error,
     },
+    #[error("suspicious expected outputs:{}", .lints.iter().map(|l| format!("\n  {}", l)).collect::<String>())]
+    SuspiciousOutputs { lints: Vec<String> },
+    #[error("duplicate test cases:{}", .cases.iter().map(|c| format!("\n  {}", c)).collect::<String>())]
+    DuplicateTestCases { cases: Vec<String> },
+    #[error("unsupported options:{}", .options.iter().map(|o| format!("\n  {}", o)).collect::<String>())]
+    UnknownOptions { options: Vec<UnknownOption> },
+    #[error("'{field}' cannot be overridden for a solution (only timeout, source, and exclude-tests can)")]
+    ForbiddenOverride { field: String },
+    #[error("there is no test '{name}' to exclude")]
+    UnknownExcludedTest { name: String },
+    #[error("{} of solution '{solution}': {source}", OVERRIDE_FILE)]
+    InOverride {
+        solution: String,
+        source: Box<ConfigError>,
+    },
+    #[error("TOML parsing error at line {line}: {msg}")]
+    InvalidToml { line: usize, msg: String },
     #[error("parsing error: {source}")]
     InvalidYaml {
         #[from]
@@ -71,6 +173,20 @@ pub enum ConfigError {
     },
 }
 
+impl ConfigError {
+    /// Category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ConfigError::InTestCase { source, .. } => source.kind(),
+            ConfigError::InTest { source, .. } => source.kind(),
+            ConfigError::InOverride { source, .. } => source.kind(),
+            ConfigError::MissingFiles { .. } => ErrorKind::MissingFiles,
+            ConfigError::BadFile { .. } => ErrorKind::Io,
+            _ => ErrorKind::Config,
+        }
+    }
+}
+
 /// Macro for compact error generation
 macro_rules! make_error {
     ( $kind:ident, $( $param:ident: $val: expr ),* ) => {
@@ -82,20 +198,240 @@ macro_rules! make_error {
     };
 }
 
+/// Summary of a configuration checked by `Config::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigReport {
+    pub source: String,
+    pub tests: usize,
+    pub test_cases: usize,
+    /// Sum of the scores of all tests except penalty ones (and of the coverage score)
+    pub max_score: f64,
+    /// Names of the analysers
+    pub analyses: Vec<String>,
+    pub scripts: Vec<PathBuf>,
+    /// Options which are ignored (e.g. misspelled ones)
+    pub unknown_options: Vec<String>,
+    /// Suspicious expected outputs and duplicate test cases
+    pub warnings: Vec<String>,
+}
+
+impl std::fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "source: {}", self.source)?;
+        writeln!(
+            f,
+            "tests: {} ({} test cases), maximal score {}",
+            self.tests, self.test_cases, self.max_score
+        )?;
+        let list = |items: Vec<String>| match items.is_empty() {
+            true => "none".to_string(),
+            false => items.join(", "),
+        };
+        writeln!(f, "analyses: {}", list(self.analyses.clone()))?;
+        let scripts = self.scripts.iter().map(|s| s.display().to_string());
+        writeln!(f, "scripts: {}", list(scripts.collect()))?;
+        for (title, lines) in [
+            ("unsupported options", &self.unknown_options),
+            ("warnings", &self.warnings),
+        ] {
+            if !lines.is_empty() {
+                writeln!(f, "{}:", title)?;
+                for line in lines {
+                    writeln!(f, "  {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Unsupported option of the configuration
+/// `context` is the place where the option appears (e.g. "tests[3]" or "compiler"), it
+/// is empty for top-level options.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownOption {
+    pub option: String,
+    pub context: String,
+}
+
+impl std::fmt::Display for UnknownOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.context.is_empty() {
+            true => write!(f, "unsupported option '{}'", self.option),
+            false => write!(f, "{}: unsupported option '{}'", self.context, self.option),
+        }
+    }
+}
+
+thread_local! {
+    // Unsupported options found by `check_fields` while a configuration is loaded on this
+    // thread (moved to `Config::unknown_options` once it is loaded)
+    static UNKNOWN_OPTIONS: RefCell<Vec<UnknownOption>> = const { RefCell::new(vec![]) };
+    // Locations of the parts of the configuration being parsed (see `in_context`)
+    static OPTIONS_CONTEXT: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+/// Record an unsupported `option` found in `section` of the configuration (it is ignored)
+/// The section is replaced by the current location (see `in_context`), if there is one.
+fn unknown_option(option: &str, section: &str) {
+    let context = OPTIONS_CONTEXT.with(|context| match context.borrow().is_empty() {
+        true => section.to_string(),
+        false => context.borrow().join(", "),
+    });
+    let option = UnknownOption {
+        option: option.to_string(),
+        context,
+    };
+    warn!("{}", option);
+    UNKNOWN_OPTIONS.with(|options| options.borrow_mut().push(option));
+}
+
+/// Run `parse` with `location` added to the location of unsupported options
+fn in_context<T>(location: String, parse: impl FnOnce() -> T) -> T {
+    OPTIONS_CONTEXT.with(|context| context.borrow_mut().push(location));
+    let result = parse();
+    OPTIONS_CONTEXT.with(|context| context.borrow_mut().pop());
+    result
+}
+
 impl Config {
+    /// Load the configuration from `config_file` and check it without evaluating anything
+    /// All the referenced files (including scripts) must exist and commands generating
+    /// the inputs and outputs of test cases must succeed (they are run).
+    pub fn validate(config_file: &Path, project_path: &Path) -> Result<ConfigReport, ConfigError> {
+        let config = Self::from_file(config_file, project_path)?;
+        config.check()?;
+        Ok(config.report())
+    }
+
+    /// Check the parts of the configuration which are only used during the evaluation
+    pub fn check(&self) -> Result<(), ConfigError> {
+        let mut missing: Vec<String> = self
+            .scripts
+            .iter()
+            .filter(|script| !script.is_file())
+            .map(|script| format!("script: {}: not found", script.display()))
+            .collect();
+        if let Some(runner) = self.test_config.exec_prefix.first() {
+            if !program_exists(runner) {
+                missing.push(format!("runner: {}: not found", runner));
+            }
+        }
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::MissingFiles { files: missing }),
+        }
+    }
+
+    /// Summary of the configuration
+    pub fn report(&self) -> ConfigReport {
+        ConfigReport {
+            source: self.src_file.clone(),
+            tests: self.tests.len(),
+            test_cases: self.tests.iter().map(|t| t.test_cases.len()).sum(),
+            max_score: self.tests.iter().map(|t| t.score.max(0.0)).sum::<f64>()
+                + self.coverage.as_ref().map_or(0.0, |c| c.score),
+            analyses: self.analyses.iter().map(|a| a.name().to_string()).collect(),
+            scripts: self
+                .scripts
+                .iter()
+                .map(|s| {
+                    s.strip_prefix(&self.project_path)
+                        .unwrap_or(s)
+                        .to_path_buf()
+                })
+                .collect(),
+            unknown_options: self.unknown_options.iter().map(|o| o.to_string()).collect(),
+            warnings: self
+                .output_lints
+                .iter()
+                .map(|lint| format!("suspicious expected output: {}", lint))
+                .chain(
+                    self.duplicate_cases
+                        .iter()
+                        .map(|case| format!("duplicate test case: {}", case)),
+                )
+                .collect(),
+        }
+    }
+
+    /// Load exceptions granted to the solution in `solution_path` by its `OVERRIDE_FILE`
+    /// (None if there is no such file). Only the timeout (`timeout`), the source file
+    /// (`source`), and tests which are not run (`exclude-tests`) can be overridden.
+    pub fn solution_override(
+        &self,
+        solution_path: &Path,
+    ) -> Result<Option<SolutionOverride>, ConfigError> {
+        let path = solution_path.join(OVERRIDE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let yaml = YamlLoader::load_from_str(&read_to_string(path)?)?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Hash(Default::default()));
+        let yaml = resolve_merge_keys(yaml)?;
+        for field in yaml.as_hash().ok_or(ConfigError::InvalidFormat)?.keys() {
+            match field.as_str() {
+                Some("timeout" | "source" | "exclude-tests") => {}
+                _ => {
+                    return Err(ConfigError::ForbiddenOverride {
+                        field: field.as_str().map_or(format!("{:?}", field), String::from),
+                    })
+                }
+            }
+        }
+        let excluded_tests =
+            optional_field_vec_str(&yaml, OVERRIDE_FILE, "exclude-tests")?.unwrap_or_default();
+        if let Some(name) = excluded_tests
+            .iter()
+            .find(|name| !self.tests.iter().any(|t| t.name == **name))
+        {
+            return Err(make_error!(UnknownExcludedTest, name: name));
+        }
+        Ok(Some(SolutionOverride {
+            timeout: optional_field_u64(&yaml, OVERRIDE_FILE, "timeout")?,
+            src_file: optional_field_str(&yaml, OVERRIDE_FILE, "source")?,
+            excluded_tests,
+        }))
+    }
+
+    /// Load the configuration from a YAML file (same as `from_file`)
     pub fn from_yaml(yaml_file: &Path, project_path: &Path) -> Result<Self, ConfigError> {
-        let mut yaml_str = String::new();
-        File::open(project_path.join(yaml_file))?.read_to_string(&mut yaml_str)?;
-        let yaml = YamlLoader::load_from_str(&yaml_str)?;
+        Self::from_file(yaml_file, project_path)
+    }
+
+    /// Load the configuration from a YAML file or from a TOML file (with the .toml extension)
+    /// Both formats have the same options, see `toml`.
+    pub fn from_file(config_file: &Path, project_path: &Path) -> Result<Self, ConfigError> {
+        Self::load(config_file, project_path, None)
+    }
+
+    /// Load the configuration like `from_file`, generated fixtures are stored in the
+    /// working directory of the run given by `workspace` (see `Workspace`)
+    pub fn from_file_in(
+        config_file: &Path,
+        project_path: &Path,
+        workspace: &Workspace,
+    ) -> Result<Self, ConfigError> {
+        Self::load(config_file, project_path, Some(workspace))
+    }
 
-        let config_options = yaml[0].as_hash().ok_or(ConfigError::InvalidFormat)?;
+    fn load(
+        config_file: &Path,
+        project_path: &Path,
+        workspace: Option<&Workspace>,
+    ) -> Result<Self, ConfigError> {
+        UNKNOWN_OPTIONS.with(|options| options.borrow_mut().clear());
+        OPTIONS_CONTEXT.with(|context| context.borrow_mut().clear());
+        let yaml = Yaml::Hash(load_yaml(config_file, project_path, &mut vec![])?);
+        let config_options = yaml.as_hash().ok_or(ConfigError::InvalidFormat)?;
 
         let mut result = Config {
             project_path: project_path.to_path_buf(),
             // Set mandatory fields here
-            src_file: mandatory_field_str(&yaml[0], "config", "source")?,
-            // Set default values here
-            timeout: DEFAULT_TEST_TIMEOUT,
+            src_file: mandatory_field_str(&yaml, "config", "source")?,
+            // Default values are set by Default implementations
             ..Default::default()
         };
 
@@ -103,112 +439,719 @@ impl Config {
             match key.as_str() {
                 // Optional fields
                 Some("solutions") => {
-                    check_fields(val, "solutions", &vec!["exclude-dirs"])?;
+                    check_fields(val, "solutions", &["exclude-dirs"])?;
                     result.excluded_dirs =
                         optional_field_vec_str(val, "solutions", "exclude-dirs")?.unwrap_or(vec![])
                 }
                 Some("compiler") => {
-                    check_fields(val, "compiler", &vec!["CC", "CFLAGS", "LDFLAGS"])?;
+                    check_fields(
+                        val,
+                        "compiler",
+                        &[
+                            "CC",
+                            "CFLAGS",
+                            "LDFLAGS",
+                            "max-binary-bytes",
+                            "require-static",
+                            "binary-penalty",
+                            "warnings-penalty",
+                            "per-warning",
+                        ],
+                    )?;
                     result.compiler = optional_field_str(val, "compiler", "CC")?;
                     result.c_flags = optional_field_str(val, "compiler", "CFLAGS")?;
                     result.ld_flags = optional_field_str(val, "compiler", "LDFLAGS")?;
+                    result.binary_limits = BinaryLimits {
+                        max_bytes: optional_field_u64(val, "compiler", "max-binary-bytes")?,
+                        require_static: field_bool(val, "compiler", "require-static")?,
+                        penalty: optional_field_f64(val, "compiler", "binary-penalty")?,
+                    };
+                    result.warnings_penalty = WarningsPenalty {
+                        penalty: optional_field_f64(val, "compiler", "warnings-penalty")?
+                            .unwrap_or(WarningsPenalty::default().penalty),
+                        per_warning: field_bool(val, "compiler", "per-warning")?,
+                    };
                 }
+                Some("programs") => result.programs = programs_from_yaml(val)?,
                 Some("test-config") => {
-                    check_fields(val, "test-config", &vec!["timeout"])?;
+                    check_fields(
+                        val,
+                        "test-config",
+                        &[
+                            "timeout",
+                            "order",
+                            "exec-prefix",
+                            "runner",
+                            "max-diff-lines",
+                            "max-workdir-bytes",
+                            "equivalences",
+                            "expected-output-warn-bytes",
+                        ],
+                    )?;
                     if let Some(timeout) = optional_field_u64(val, "test-config", "timeout")? {
-                        result.timeout = timeout;
+                        result.test_config.timeout = timeout;
+                    }
+                    result.test_config.order =
+                        match optional_field_str(val, "test-config", "order")?.as_deref() {
+                            Some("config") | None => TestOrder::Config,
+                            Some("by-cost") => TestOrder::ByCost,
+                            Some(_) => Err(make_error!(
+                                InvalidField,
+                                option: "test-config",
+                                field: "order",
+                                expected_type: "\"config\" or \"by-cost\""
+                            ))?,
+                        };
+                    // The runner is another name of the execution prefix
+                    let prefix = match (
+                        optional_field_str(val, "test-config", "exec-prefix")?,
+                        optional_field_str(val, "test-config", "runner")?,
+                    ) {
+                        (Some(_), Some(_)) => Err(make_error!(
+                            IncompatibleFields,
+                            option: "test-config",
+                            field: "runner",
+                            other: "exec-prefix"
+                        ))?,
+                        (Some(prefix), None) => Some(("exec-prefix", prefix)),
+                        (None, Some(runner)) => Some(("runner", runner)),
+                        (None, None) => None,
+                    };
+                    if let Some((field, prefix)) = prefix {
+                        result.test_config.exec_prefix = split_words(&prefix).map_err(|_| {
+                            make_error!(
+                                InvalidField,
+                                option: "test-config",
+                                field: field,
+                                expected_type: "properly quoted arguments"
+                            )
+                        })?;
+                    }
+                    if let Some(lines) = optional_field_u64(val, "test-config", "max-diff-lines")? {
+                        result.test_config.max_diff_lines = lines as usize;
+                    }
+                    result.test_config.max_workdir_bytes =
+                        optional_field_u64(val, "test-config", "max-workdir-bytes")?;
+                    if let Some(bytes) =
+                        optional_field_u64(val, "test-config", "expected-output-warn-bytes")?
+                    {
+                        result.test_config.expected_output_warn_bytes = bytes;
                     }
+                    result.test_config.equivalences =
+                        optional_field_token_groups(val, "test-config", "equivalences")?
+                            .unwrap_or_default();
+                }
+                Some("analyses-config") => {
+                    check_fields(val, "analyses-config", &["max-source-bytes", "penalty"])?;
+                    if let Some(bytes) =
+                        optional_field_u64(val, "analyses-config", "max-source-bytes")?
+                    {
+                        result.analyses_config.max_source_bytes = bytes;
+                    }
+                    result.analyses_config.oversize_penalty =
+                        optional_field_f64(val, "analyses-config", "penalty")?;
+                }
+                // Parsed after the tests are processed (some analysers use them)
+                Some("analyses") => {}
+                // Applied to the test cases when parsing the tests
+                Some("test-defaults") => {}
+                Some("tests") => {
+                    result.tests = tests_from_yaml(val, &yaml["test-defaults"], project_path)?;
+                    let (duplicates, across_tests) = duplicate_test_cases(&result.tests);
+                    for duplicate in across_tests {
+                        info!("{}", duplicate);
+                    }
+                    result.duplicate_cases = duplicates;
                 }
-                Some("analyses") => result.analyses = analyses_from_yaml(val)?,
-                Some("tests") => result.tests = tests_from_yaml(val)?,
                 Some("scripts") => {
-                    result.scripts = optional_field_vec_str(&yaml[0], "config", "scripts")?
+                    result.scripts = optional_field_vec_str(&yaml, "config", "scripts")?
                         .unwrap_or(vec![])
                         .iter()
                         .map(|s| project_path.join(s))
                         .collect();
                 }
-                // Mandatory fields (already set)
-                Some("source") => {}
-                Some(k) => {
-                    warn!("Unsupported config option: {}", k);
+                Some("entry-point") => {
+                    check_fields(val, "entry-point", &["detect", "overrides"])?;
+                    result.detect_entry_point = field_bool(val, "entry-point", "detect")?;
+                    result.entry_points =
+                        optional_field_dict_str(val, "entry-point", "overrides")?.unwrap_or(vec![]);
                 }
-                None => {
-                    warn!("Invalid config option: {:?}", key);
+                Some("pipeline") => {
+                    let pipeline = optional_field_vec_str(&yaml, "config", "pipeline")?
+                        .unwrap_or(vec![])
+                        .iter()
+                        .map(|m| m.parse::<ModuleKind>())
+                        .collect::<Result<Vec<ModuleKind>, String>>()
+                        .map_err(|_| {
+                            let modules = ModuleKind::DEFAULT_PIPELINE
+                                .iter()
+                                .map(|m| m.name())
+                                .collect::<Vec<&str>>()
+                                .join(", ");
+                            make_error!(
+                                InvalidField,
+                                option: "config",
+                                field: "pipeline",
+                                expected_type: format!("list of modules ({})", modules)
+                            )
+                        })?;
+                    result.pipeline = Some(pipeline);
+                }
+                Some("fixtures") => {
+                    result.fixtures =
+                        optional_field_dict_str(&yaml, "config", "fixtures")?.unwrap_or_default();
+                    if let Some((name, _)) = result
+                        .fixtures
+                        .iter()
+                        .find(|(_, source)| !source.starts_with('<') && !source.starts_with("$("))
+                    {
+                        return Err(make_error!(
+                            InvalidField,
+                            option: "fixtures",
+                            field: name,
+                            expected_type: "\"<file\" or \"$(command)\""
+                        ));
+                    }
+                    // Generated fixtures are stored in files named after them
+                    if let Some((name, _)) = result.fixtures.iter().find(|(name, _)| {
+                        name.is_empty() || name == "." || name == ".." || name.contains('/')
+                    }) {
+                        return Err(make_error!(
+                            InvalidField,
+                            option: "fixtures",
+                            field: name,
+                            expected_type: "name without '/'"
+                        ));
+                    }
+                }
+                Some("vars") => {
+                    result.vars =
+                        optional_field_dict_str(&yaml, "config", "vars")?.unwrap_or_default()
                 }
+                Some("seed") => {
+                    // Seed 0 is valid (and can be generated, see `generate_seed`)
+                    result.seed = match optional_field_i64(&yaml, "config", "seed")? {
+                        Some(seed) if seed < 0 => {
+                            return Err(make_error!(
+                                InvalidField,
+                                option: "config",
+                                field: "seed",
+                                expected_type: "non-negative int"
+                            ))
+                        }
+                        seed => seed.map(|seed| seed as u64),
+                    }
+                }
+                Some("strict") => result.strict = field_bool(&yaml, "config", "strict")?,
+                Some("clamp-score") => {
+                    result.clamp_score = field_bool(&yaml, "config", "clamp-score")?
+                }
+                Some("late-penalty") => result.late_penalty = Some(late_penalty_from_yaml(val)?),
+                Some("coverage") => {
+                    check_fields(val, "coverage", &["score", "threshold", "tests-dir"])?;
+                    result.coverage = Some(CoverageConfig {
+                        score: mandatory_field_f64(val, "coverage", "score")?,
+                        threshold: optional_field_f64(val, "coverage", "threshold")?
+                            .unwrap_or(100.0),
+                        tests_dir: PathBuf::from(
+                            optional_field_str(val, "coverage", "tests-dir")?
+                                .unwrap_or_else(|| "tests".to_string()),
+                        ),
+                    });
+                }
+                // Mandatory fields (already set)
+                Some("source") => {}
+                Some(k) => unknown_option(k, ""),
+                None => unknown_option(&format!("{:?}", key), ""),
             };
         }
-        result.process()
+        // Programs run by the tests must be defined (in any order of the options)
+        for (i, test) in result.tests.iter().enumerate() {
+            let defined = |name: &String| result.programs.iter().any(|p| p.name == *name);
+            if let Some(name) = test.programs.iter().find(|name| !defined(name)) {
+                return Err(ConfigError::InTest {
+                    location: test_location(i, Some(&test.name)),
+                    source: Box::new(ConfigError::UndefinedProgram {
+                        name: name.clone(),
+                        defined: result.programs.iter().map(|p| p.name.clone()).collect(),
+                    }),
+                });
+            }
+        }
+        let mut result = result.process(workspace)?;
+        result.analyses = analyses_from_yaml(&yaml["analyses"], project_path, &result.tests)?;
+        result.unknown_options = UNKNOWN_OPTIONS.with(|options| options.take());
+        if result.strict && !result.unknown_options.is_empty() {
+            return Err(ConfigError::UnknownOptions {
+                options: result.unknown_options,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Load the configuration like `from_file` and fail if it has suspicious expected
+    /// outputs, duplicate test cases, or unsupported options (same as `--strict`)
+    pub fn from_file_strict(config_file: &Path, project_path: &Path) -> Result<Self, ConfigError> {
+        Self::from_file(config_file, project_path)?.check_strict()
+    }
+
+    /// Fail if the configuration has suspicious expected outputs, duplicate test cases,
+    /// or unsupported options
+    pub(crate) fn check_strict(self) -> Result<Self, ConfigError> {
+        if !self.output_lints.is_empty() {
+            return Err(ConfigError::SuspiciousOutputs {
+                lints: self.output_lints,
+            });
+        }
+        if !self.duplicate_cases.is_empty() {
+            return Err(ConfigError::DuplicateTestCases {
+                cases: self.duplicate_cases,
+            });
+        }
+        if !self.unknown_options.is_empty() {
+            return Err(ConfigError::UnknownOptions {
+                options: self.unknown_options,
+            });
+        }
+        Ok(self)
     }
 
-    fn process(mut self) -> Result<Self, ConfigError> {
+    fn process(mut self, workspace: Option<&Workspace>) -> Result<Self, ConfigError> {
+        // Files which cannot be read are collected so that all of them are reported at once
+        let mut missing = vec![];
+        let mut lints = vec![];
+        // Fixtures are loaded once, when they are referenced for the first time
+        let mut loaded: HashMap<String, DataFile> = HashMap::new();
+        let fixtures = &self.fixtures;
+        let fixtures_dir = &mut self.fixtures_dir;
+        let project_path = &self.project_path;
+        let vars = &self.vars;
+        for script in self.scripts.iter_mut() {
+            let relative = script.strip_prefix(project_path).unwrap_or(script);
+            let path = substitute_vars(&relative.to_string_lossy(), vars)?;
+            *script = project_path.join(path);
+        }
+        // Files larger than `stream_above` are not read, they are returned to be streamed
+        let mut expand_file = |string: &mut Option<String>,
+                               location: &str,
+                               field: &str,
+                               stream_above: Option<u64>|
+         -> Option<DataFile> {
+            let s = string.as_ref().filter(|s| s.starts_with('<'))?;
+            let expanded = referenced_file(s, project_path).and_then(|path| match stream_above {
+                Some(limit) if path.metadata()?.len() > limit => Ok(Err(DataFile {
+                    name: s.trim().to_string(),
+                    path,
+                })),
+                _ => expand_string_from_file(s, project_path).map(Ok),
+            });
+            match expanded {
+                Ok(Ok(contents)) => *string = Some(contents),
+                Ok(Err(file)) => {
+                    *string = None;
+                    return Some(file);
+                }
+                Err(e) => missing.push(format!(
+                    "{}, field '{}': {}: {}",
+                    location,
+                    field,
+                    project_path.join(&s.trim()[1..]).display(),
+                    e
+                )),
+            }
+            None
+        };
         for t in &mut self.tests {
-            for tc in &mut t.test_cases {
-                if let Some(stdin) = tc.stdin.as_ref() {
-                    if stdin.starts_with('<') {
-                        // Pass contents of a file to stdin
-                        tc.stdin = Some(expand_string_from_file(&stdin, &self.project_path)?);
-                    } else if stdin.starts_with("$(") {
-                        // Expand a command to stdin
-                        tc.stdin = Some(expand_string_from_command(&stdin)?);
+            let cases = t.test_cases.len();
+            for (i, tc) in t.test_cases.iter_mut().enumerate() {
+                let location = test_case_location(&t.name, i, cases);
+                // Cases without their own equivalences use the defaults from test-config
+                if tc.equivalences.is_none() && !self.test_config.equivalences.is_empty() {
+                    tc.equivalences = Some(self.test_config.equivalences.clone());
+                }
+                let in_test_case = |field: &str, e: ConfigError| ConfigError::InTestCase {
+                    location: location.clone(),
+                    field: field.to_string(),
+                    source: Box::new(e),
+                };
+                // Variables are substituted before files and commands are expanded
+                for arg in tc.args.iter_mut() {
+                    *arg = substitute_vars(arg, vars).map_err(|e| in_test_case("args", e))?;
+                }
+                for (field, string) in [
+                    ("stdin", &mut tc.stdin),
+                    ("stdout", &mut tc.stdout),
+                    ("stderr", &mut tc.stderr),
+                ] {
+                    if let Some(s) = string {
+                        *s = substitute_vars(s, vars).map_err(|e| in_test_case(field, e))?;
                     }
                 }
-                // If stdout should be compared to contents of a file, read the file
-                if let Some(stdout) = tc.stdout.as_ref() {
-                    tc.stdout = Some(expand_string_from_file(&stdout, &self.project_path)?);
+                match tc.stdin.as_ref() {
+                    // Pass a fixture to stdin
+                    Some(stdin) if stdin.starts_with('@') => {
+                        let name = &stdin[1..];
+                        if !loaded.contains_key(name) {
+                            let (_, source) =
+                                fixtures.iter().find(|(n, _)| n == name).ok_or_else(|| {
+                                    in_test_case(
+                                        "stdin",
+                                        ConfigError::UndefinedFixture {
+                                            name: name.to_string(),
+                                            defined: fixtures
+                                                .iter()
+                                                .map(|(n, _)| n.clone())
+                                                .collect(),
+                                        },
+                                    )
+                                })?;
+                            let fixture =
+                                load_fixture(name, source, project_path, fixtures_dir, workspace)
+                                    .map_err(|e| in_test_case("stdin", e))?;
+                            loaded.insert(name.to_string(), fixture);
+                        }
+                        tc.stdin_file = loaded.get(name).cloned();
+                        tc.stdin = None;
+                    }
+                    // Expand a command to stdin
+                    Some(stdin) if stdin.starts_with("$(") => {
+                        tc.stdin = Some(
+                            expand_string_from_command(stdin, project_path)
+                                .map_err(|e| in_test_case("stdin", e))?,
+                        )
+                    }
+                    // Stream contents of a file to stdin
+                    Some(stdin) if stdin.starts_with('<') => {
+                        if let Some(file) = expand_file(&mut tc.stdin, &location, "stdin", Some(0))
+                        {
+                            tc.stdin_file = Some(file);
+                        }
+                    }
+                    _ => {}
+                }
+                // Expected stdout or stderr may be generated by a command, otherwise, if it
+                // should be compared to contents of a file, read the file (large expected
+                // stdout is compared as a stream if it is not normalized and no prefix of it
+                // is needed for partial credit)
+                let expand_command = |output: &Option<String>, field: &str| {
+                    output
+                        .as_ref()
+                        .filter(|s| s.starts_with("$("))
+                        .map(|cmd| {
+                            expand_string_from_command(cmd, project_path)
+                                .map_err(|e| in_test_case(field, e))
+                        })
+                        .transpose()
+                };
+                let stream_above = (tc.streamable() && t.timeout_partial_credit.is_none())
+                    .then_some(STREAMED_OUTPUT_BYTES);
+                match expand_command(&tc.stdout, "stdout")? {
+                    Some(stdout) => tc.stdout = Some(stdout),
+                    None => {
+                        tc.stdout_file =
+                            expand_file(&mut tc.stdout, &location, "stdout", stream_above)
+                    }
+                }
+                match expand_command(&tc.stderr, "stderr")? {
+                    Some(stderr) => tc.stderr = Some(stderr),
+                    None => {
+                        expand_file(&mut tc.stderr, &location, "stderr", None);
+                    }
+                }
+                // Values of environment variables may be generated by a command or read
+                // from a file (trailing newlines are removed from them, as in a shell)
+                for (var, value) in tc.env.iter_mut() {
+                    let field = format!("env {}", var);
+                    let mut expanded =
+                        Some(substitute_vars(value, vars).map_err(|e| in_test_case(&field, e))?);
+                    match expand_command(&expanded, &field)? {
+                        Some(output) => expanded = Some(output),
+                        None if expanded.as_ref().is_some_and(|v| v.starts_with('<')) => {
+                            expand_file(&mut expanded, &location, &field, None);
+                        }
+                        None => {
+                            *value = expanded.unwrap_or_default();
+                            continue;
+                        }
+                    }
+                    *value = expanded
+                        .unwrap_or_default()
+                        .trim_end_matches('\n')
+                        .to_string();
+                }
+
+                let line_oriented = match &tc.stdin_file {
+                    Some(file) => file_ends_with_newline(&file.path),
+                    None => tc.stdin.as_ref().is_some_and(|s| s.ends_with('\n')),
+                };
+                let max_bytes = self.test_config.expected_output_warn_bytes;
+                for (field, output) in [("stdout", &tc.stdout), ("stderr", &tc.stderr)] {
+                    if let Some(output) = output.as_ref().filter(|o| o.as_str() != "*") {
+                        lints.extend(
+                            lint_output(output, line_oriented, max_bytes)
+                                .into_iter()
+                                .map(|lint| format!("{}, field '{}': {}", location, field, lint)),
+                        );
+                    }
+                }
+                // An empty expected output for a real input is most likely a mistake
+                if tc.stdout.as_deref() == Some("")
+                    && tc.stdin.as_ref().is_some_and(|s| !s.trim().is_empty())
+                {
+                    lints.push(format!(
+                        "{}, field 'stdout': empty although stdin is not",
+                        location
+                    ));
                 }
             }
         }
+        if !missing.is_empty() {
+            return Err(ConfigError::MissingFiles { files: missing });
+        }
+        self.output_lints = lints;
         Ok(self)
     }
 }
 
-fn tests_from_yaml(yaml: &Yaml) -> Result<Vec<Test>, ConfigError> {
+/// Substitute values of `vars` for `${name}` in `string`
+/// `$${` can be used to write `${` literally. References to undefined variables are errors.
+fn substitute_vars(string: &str, vars: &[(String, String)]) -> Result<String, ConfigError> {
+    let mut result = String::new();
+    let mut rest = string;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let name = &rest[start + 2..end];
+        let (_, value) =
+            vars.iter()
+                .find(|(n, _)| n == name)
+                .ok_or_else(|| ConfigError::UndefinedVariable {
+                    name: name.to_string(),
+                    defined: vars.iter().map(|(n, _)| n.clone()).collect(),
+                })?;
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Load the YAML configuration from `file` (relative to `project_path`) and merge the files
+/// it includes into it, in order and before its own options. `including` are the files
+/// which (transitively) include `file`, they are used to detect circular includes.
+fn load_yaml(
+    file: &Path,
+    project_path: &Path,
+    including: &mut Vec<PathBuf>,
+) -> Result<yaml_rust::yaml::Hash, ConfigError> {
+    let path = project_path.join(file).canonicalize()?;
+    if including.contains(&path) {
+        let files = including.iter().chain(std::iter::once(&path));
+        return Err(ConfigError::CircularInclude {
+            files: files.map(|f| f.display().to_string()).collect(),
+        });
+    }
+    let mut contents = String::new();
+    File::open(&path)?.read_to_string(&mut contents)?;
+    let yaml = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::load_from_str(&contents)?,
+        _ => YamlLoader::load_from_str(&contents)?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Null),
+    };
+    let yaml = resolve_merge_keys(yaml)?;
+    let includes = optional_field_vec_str(&yaml, "config", "include")?.unwrap_or_default();
+    let mut options = yaml.into_hash().ok_or(ConfigError::InvalidFormat)?;
+    options.remove(&Yaml::String("include".to_string()));
+    if includes.is_empty() {
+        return Ok(options);
+    }
+
+    including.push(path);
+    let mut merged = yaml_rust::yaml::Hash::new();
+    for include in includes {
+        let included = load_yaml(Path::new(&include), project_path, including)?;
+        merge_options(&mut merged, included, &["tests", "analyses", "scripts"]);
+    }
+    including.pop();
+    merge_options(&mut merged, options, &["tests", "analyses", "scripts"]);
+    Ok(merged)
+}
+
+/// Resolve YAML merge keys (`<<: *anchor`), which yaml-rust keeps as ordinary keys
+/// The merge key may refer to a dictionary or to a list of dictionaries. Their keys are
+/// inserted into the dictionary containing the merge key unless it defines them itself
+/// (if multiple dictionaries define a key, the first one is used).
+fn resolve_merge_keys(yaml: Yaml) -> Result<Yaml, ConfigError> {
+    match yaml {
+        Yaml::Array(items) => Ok(Yaml::Array(
+            items
+                .into_iter()
+                .map(resolve_merge_keys)
+                .collect::<Result<_, _>>()?,
+        )),
+        Yaml::Hash(hash) => {
+            let merge_key = Yaml::String("<<".to_string());
+            let own: Vec<Yaml> = hash.keys().filter(|k| **k != merge_key).cloned().collect();
+            let mut result = yaml_rust::yaml::Hash::new();
+            for (key, value) in hash {
+                let value = resolve_merge_keys(value)?;
+                if key != merge_key {
+                    result.insert(key, value);
+                    continue;
+                }
+                let merged = match value {
+                    Yaml::Hash(dict) => Some(vec![dict]),
+                    Yaml::Array(dicts) => dicts.into_iter().map(Yaml::into_hash).collect(),
+                    _ => None,
+                }
+                .ok_or(make_error!(
+                    InvalidOption,
+                    option: "<<",
+                    expected_type: "dictionary or list of dictionaries"
+                ))?;
+                for (k, v) in merged.into_iter().flatten() {
+                    if !own.contains(&k) && !result.contains_key(&k) {
+                        result.insert(k, v);
+                    }
+                }
+            }
+            Ok(Yaml::Hash(result))
+        }
+        yaml => Ok(yaml),
+    }
+}
+
+/// Merge configuration `options` into `base`
+/// Lists of the `concatenated` options are appended to the lists in `base`, dictionaries
+/// are merged recursively, and other values replace those in `base`.
+fn merge_options(
+    base: &mut yaml_rust::yaml::Hash,
+    options: yaml_rust::yaml::Hash,
+    concatenated: &[&str],
+) {
+    for (key, value) in options {
+        let concatenate = key.as_str().is_some_and(|k| concatenated.contains(&k));
+        match (base.get_mut(&key), value) {
+            (Some(Yaml::Array(list)), Yaml::Array(items)) if concatenate => list.extend(items),
+            (Some(Yaml::Hash(dict)), Yaml::Hash(items)) => merge_options(dict, items, &[]),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Find suspicious properties of an expected `output` which are a common source of
+/// unexpected test failures. The final newline is only checked for `line_oriented`
+/// outputs (i.e. when the input of the test case consists of whole lines). Outputs
+/// larger than `max_bytes` or containing NUL bytes are likely taken from a wrong file.
+fn lint_output(output: &str, line_oriented: bool, max_bytes: u64) -> Vec<String> {
+    let mut lints = vec![];
+    if output.len() as u64 > max_bytes {
+        lints.push(format!(
+            "{} bytes long (more than {})",
+            output.len(),
+            max_bytes
+        ));
+    }
+    if output.contains('\0') {
+        lints.push("contains NUL bytes (binary file?)".to_string());
+    }
+    if output
+        .split('\n')
+        .any(|line| line.trim_end_matches('\r').ends_with(' '))
+    {
+        lints.push("trailing whitespace".to_string());
+    }
+    let crlf = output.matches("\r\n").count();
+    if crlf > 0 && crlf < output.matches('\n').count() {
+        lints.push("mixed line endings".to_string());
+    }
+    if output.contains('\t') {
+        lints.push("tab characters".to_string());
+    }
+    if line_oriented && !output.is_empty() && !output.ends_with('\n') {
+        lints.push("missing final newline".to_string());
+    }
+    lints
+}
+
+/// Human-readable location of the `index`-th test case of the test `test` having `cases` cases
+fn test_case_location(test: &str, index: usize, cases: usize) -> String {
+    match cases {
+        1 => format!("test '{}'", test),
+        _ => format!("test '{}', case {}", test, index),
+    }
+}
+
+/// Find test cases identical to an earlier test case (same arguments, inputs, expected
+/// outputs, and comparison options). Returns the duplicates within a single test, which
+/// are most likely copy-paste mistakes, and the duplicates of cases from other tests,
+/// which may be intentional.
+fn duplicate_test_cases(tests: &[Test]) -> (Vec<String>, Vec<String>) {
+    let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    let (mut within, mut across) = (vec![], vec![]);
+    for (t, test) in tests.iter().enumerate() {
+        let cases = test.test_cases.len();
+        for (i, case) in test.test_cases.iter().enumerate() {
+            // The parsed case does not depend on the formatting of the configuration
+            match seen.get(&format!("{:?}", case)) {
+                Some((orig_t, orig_i)) if *orig_t == t => within.push(format!(
+                    "{} is identical to case {}",
+                    test_case_location(&test.name, i, cases),
+                    orig_i
+                )),
+                Some((orig_t, orig_i)) => {
+                    let orig = &tests[*orig_t];
+                    across.push(format!(
+                        "{} is identical to {}",
+                        test_case_location(&test.name, i, cases),
+                        test_case_location(&orig.name, *orig_i, orig.test_cases.len())
+                    ))
+                }
+                None => {
+                    seen.insert(format!("{:?}", case), (t, i));
+                }
+            }
+        }
+    }
+    (within, across)
+}
+
+/// Parse tests, fields of test cases which are not set by the case (or by its test) are
+/// taken from `defaults` (the `test-defaults` option). Errors are located by the index
+/// of the test in the list (and by its name if it has one).
+fn tests_from_yaml(
+    yaml: &Yaml,
+    defaults: &Yaml,
+    project_path: &Path,
+) -> Result<Vec<Test>, ConfigError> {
+    if !defaults.is_badvalue() {
+        test_case_from_yaml(defaults, "test-defaults", true)?;
+    }
     match yaml.as_vec() {
         Some(v) => v
             .iter()
-            .map(|test| {
-                let test_name = optional_field_str(test, "test", "name")?.unwrap_or_default();
-                check_fields(
-                    test,
-                    &test_name,
-                    &vec![
-                        "name",
-                        "score",
-                        "args",
-                        "stdin",
-                        "stdout",
-                        "stderr",
-                        "test-cases",
-                        "require",
-                        "case-insensitive",
-                    ],
-                )?;
-
-                let test_cases = match test["test-cases"].as_vec() {
-                    Some(cases) => cases
-                        .iter()
-                        .map(|case| test_case_from_yaml(case, &test_name, true))
-                        .collect::<Result<Vec<TestCase>, _>>()?,
-                    None => vec![test_case_from_yaml(test, &test_name, false)?],
-                };
-                let requirement = match optional_field_str(test, &test_name, "require")?.as_deref()
-                {
-                    Some("any") => TestCasesRequirement::ANY,
-                    Some("all") => TestCasesRequirement::ALL,
-                    Some(_) => Err(make_error!(
-                        InvalidOption,
-                        option: "expect",
-                        expected_type: "\"all\" or \"any\""
-                    ))?,
-                    _ => TestCasesRequirement::ALL,
-                };
-
-                Ok(Test {
-                    name: test_name.to_string(),
-                    score: mandatory_field_f64(test, &test_name, "score")?,
-                    test_cases,
-                    requirement,
+            .enumerate()
+            .map(|(i, test)| {
+                let location = test_location(i, test["name"].as_str());
+                in_context(location.clone(), || {
+                    test_from_yaml(test, defaults, project_path)
+                })
+                .map_err(|e| ConfigError::InTest {
+                    location,
+                    source: Box::new(e),
                 })
             })
             .collect(),
@@ -216,6 +1159,223 @@ fn tests_from_yaml(yaml: &Yaml) -> Result<Vec<Test>, ConfigError> {
     }
 }
 
+/// Check if `program` exists, either as a path (if it contains a slash) or in `PATH`
+fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Location of the test with the given index (and name) in the list of tests
+fn test_location(index: usize, name: Option<&str>) -> String {
+    match name {
+        Some(name) if !name.is_empty() => format!("tests[{}] (name: '{}')", index, name),
+        _ => format!("tests[{}]", index),
+    }
+}
+
+/// Parse a single test (see `tests_from_yaml`)
+fn test_from_yaml(test: &Yaml, defaults: &Yaml, project_path: &Path) -> Result<Test, ConfigError> {
+    let test_name = optional_field_str(test, "test", "name")?.unwrap_or_default();
+    check_fields(
+        test,
+        &test_name,
+        &[
+            "name",
+            "score",
+            "args",
+            "stdin",
+            "stdout",
+            "stderr",
+            "exit-code",
+            "test-cases",
+            "require",
+            "case-insensitive",
+            "ignore-trailing-whitespace",
+            "normalize-unicode",
+            "sort-fields",
+            "unordered",
+            "columns",
+            "column-tolerance",
+            "tolerance",
+            "equivalences",
+            "compare",
+            "stdout-sha256",
+            "timeout",
+            "env",
+            "timeout-partial-credit",
+            "partial-credit",
+            "defines",
+            "cases-dir",
+            "description",
+            "hint",
+            "cost-hint",
+            "program",
+            "pipeline",
+        ],
+    )?;
+
+    let cases_dir = optional_field_str(test, &test_name, "cases-dir")?;
+    let mut test_cases = match test["test-cases"].as_vec() {
+        Some(cases) => cases
+            .iter()
+            .enumerate()
+            .map(|(i, case)| {
+                let location = format!("test-cases[{}]", i);
+                in_context(location.clone(), || {
+                    test_case_from_yaml(&with_defaults(case, defaults), &test_name, true)
+                })
+                .map_err(|e| ConfigError::InTest {
+                    location,
+                    source: Box::new(e),
+                })
+            })
+            .collect::<Result<Vec<TestCase>, _>>()?,
+        None if cases_dir.is_some() => vec![],
+        None => vec![test_case_from_yaml(
+            &with_defaults(test, defaults),
+            &test_name,
+            false,
+        )?],
+    };
+    if let Some(dir) = cases_dir {
+        // Fields given directly in the test are shared by all the cases
+        let base = test_case_from_yaml(&with_defaults(test, defaults), &test_name, false)?;
+        test_cases.extend(test_cases_from_dir(&dir, project_path, &base)?);
+    }
+    // Timeout of the test applies to the cases which do not set their own
+    let timeout = match optional_field_u64(test, &test_name, "timeout")? {
+        Some(timeout) => Some(timeout),
+        None => optional_field_u64(defaults, "test-defaults", "timeout")?,
+    };
+    if let Some(timeout) = timeout {
+        for case in test_cases.iter_mut().filter(|c| c.timeout.is_none()) {
+            case.timeout = Some(timeout);
+        }
+    }
+    // Environment of the test is shared by the cases, which may override it
+    let env = match optional_field_dict_str(test, &test_name, "env")? {
+        Some(env) => Some(env),
+        None => optional_field_dict_str(defaults, "test-defaults", "env")?,
+    };
+    if let Some(env) = env {
+        for case in test_cases.iter_mut() {
+            let own = std::mem::take(&mut case.env);
+            case.env = env
+                .iter()
+                .filter(|(var, _)| !own.iter().any(|(v, _)| v == var))
+                .cloned()
+                .chain(own.iter().cloned())
+                .collect();
+        }
+    }
+    let programs = match (
+        optional_field_str(test, &test_name, "program")?,
+        optional_field_vec_str(test, &test_name, "pipeline")?,
+    ) {
+        (Some(_), Some(_)) => Err(make_error!(
+            IncompatibleFields,
+            option: test_name,
+            field: "pipeline",
+            other: "program"
+        ))?,
+        (Some(program), None) => vec![program],
+        (None, Some(pipeline)) if pipeline.is_empty() => Err(make_error!(
+            InvalidField,
+            option: test_name,
+            field: "pipeline",
+            expected_type: "non-empty list of programs"
+        ))?,
+        (None, Some(pipeline)) => pipeline,
+        (None, None) => vec![],
+    };
+    let requirement = match optional_field_str(test, &test_name, "require")?.as_deref() {
+        Some("any") => TestCasesRequirement::ANY,
+        Some("all") => TestCasesRequirement::ALL,
+        Some(_) => Err(make_error!(
+            InvalidOption,
+            option: "expect",
+            expected_type: "\"all\" or \"any\""
+        ))?,
+        _ => TestCasesRequirement::ALL,
+    };
+    let timeout_partial_credit =
+        match optional_field_str(test, &test_name, "timeout-partial-credit")?.as_deref() {
+            Some("prefix") => Some(
+                optional_field_f64(test, &test_name, "partial-credit")?
+                    .unwrap_or(DEFAULT_PARTIAL_CREDIT),
+            ),
+            Some(_) => Err(make_error!(
+                InvalidField,
+                option: test_name,
+                field: "timeout-partial-credit",
+                expected_type: "\"prefix\""
+            ))?,
+            None => None,
+        };
+
+    Ok(Test {
+        name: test_name.to_string(),
+        score: mandatory_field_f64(test, &test_name, "score")?,
+        test_cases,
+        requirement,
+        timeout_partial_credit,
+        defines: optional_field_dict_str(test, &test_name, "defines")?.unwrap_or_default(),
+        description: optional_field_str(test, &test_name, "description")?,
+        hint: optional_field_str(test, &test_name, "hint")?,
+        cost_hint: optional_field_non_negative(test, &test_name, "cost-hint")?,
+        programs,
+    })
+}
+
+/// Parse additional programs (a dictionary from program names to their definitions)
+fn programs_from_yaml(yaml: &Yaml) -> Result<Vec<Program>, ConfigError> {
+    let programs = yaml
+        .as_hash()
+        .ok_or(make_error!(InvalidOption, option: "programs", expected_type: "dictionary"))?;
+    programs
+        .iter()
+        .map(|(name, program)| {
+            // Executables are created in the solution directory
+            let name = name
+                .as_str()
+                .filter(|n| !n.is_empty() && !n.contains('/') && *n != "." && *n != "..")
+                .ok_or(make_error!(
+                    InvalidOption,
+                    option: "programs",
+                    expected_type: "file names of programs"
+                ))?;
+            check_fields(program, name, &["sources", "CFLAGS", "LDFLAGS"])?;
+            Ok(Program {
+                name: name.to_string(),
+                sources: mandatory_field_vec_str(program, name, "sources")?,
+                c_flags: optional_field_str(program, name, "CFLAGS")?,
+                ld_flags: optional_field_str(program, name, "LDFLAGS")?,
+            })
+        })
+        .collect()
+}
+
+/// Add fields of `defaults` which are not set in `case` (values are replaced as a whole,
+/// lists are not concatenated). The timeout and the environment are not added since the
+/// ones of the test take precedence over them.
+fn with_defaults(case: &Yaml, defaults: &Yaml) -> Yaml {
+    match (case.as_hash(), defaults.as_hash()) {
+        (Some(case), Some(defaults)) => {
+            let mut result = case.clone();
+            for (field, value) in defaults {
+                if !matches!(field.as_str(), Some("timeout") | Some("env")) {
+                    result.entry(field.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            Yaml::Hash(result)
+        }
+        _ => case.clone(),
+    }
+}
+
 fn test_case_from_yaml(
     yaml: &Yaml,
     test_name: &str,
@@ -225,74 +1385,502 @@ fn test_case_from_yaml(
         check_fields(
             yaml,
             test_name,
-            &vec!["args", "stdin", "stdout", "stderr", "case-insensitive"],
+            &[
+                "args",
+                "stdin",
+                "stdout",
+                "stderr",
+                "exit-code",
+                "case-insensitive",
+                "ignore-trailing-whitespace",
+                "normalize-unicode",
+                "sort-fields",
+                "unordered",
+                "columns",
+                "column-tolerance",
+                "tolerance",
+                "equivalences",
+                "compare",
+                "stdout-sha256",
+                "timeout",
+                "env",
+            ],
         )?;
     }
+    let tolerances = optional_field_vec_f64(yaml, test_name, "column-tolerance")?;
+    let tolerance = optional_field_tolerance(yaml, test_name, "tolerance")?;
+    // Tokens are compared across lines, columns within each line
+    if tolerance.is_some() {
+        let other = ["columns", "column-tolerance"]
+            .iter()
+            .find(|field| !yaml[**field].is_badvalue());
+        if let Some(other) = other {
+            return Err(make_error!(
+                IncompatibleFields,
+                option: test_name,
+                field: "tolerance",
+                other: other
+            ));
+        }
+    }
+    let digest = optional_field_str(yaml, test_name, "stdout-sha256")?;
+    let stdout_sha256 = match optional_field_str(yaml, test_name, "compare")?.as_deref() {
+        Some("sha256") => match digest {
+            Some(d) if is_sha256(&d) => Some(d.to_lowercase()),
+            Some(_) => Err(make_error!(
+                InvalidField,
+                option: test_name,
+                field: "stdout-sha256",
+                expected_type: "SHA-256 digest (64 hexadecimal digits)"
+            ))?,
+            None => Err(make_error!(MissingField, option: test_name, field: "stdout-sha256"))?,
+        },
+        Some(_) => Err(make_error!(
+            InvalidField,
+            option: test_name,
+            field: "compare",
+            expected_type: "\"sha256\""
+        ))?,
+        None if digest.is_some() => {
+            Err(make_error!(MissingField, option: test_name, field: "compare"))?
+        }
+        None => None,
+    };
+    // The digest is computed from the exact output, so it cannot be normalized
+    if stdout_sha256.is_some() {
+        let other = [
+            "stdout",
+            "case-insensitive",
+            "ignore-trailing-whitespace",
+            "normalize-unicode",
+            "sort-fields",
+            "unordered",
+            "columns",
+            "column-tolerance",
+            "tolerance",
+            "equivalences",
+        ]
+        .iter()
+        .find(|field| !yaml[**field].is_badvalue());
+        if let Some(other) = other {
+            return Err(make_error!(
+                IncompatibleFields,
+                option: test_name,
+                field: "compare",
+                other: other
+            ));
+        }
+    }
     Ok(TestCase {
-        args: optional_field_str(yaml, test_name, "args")?
-            .unwrap_or_default()
-            .split_whitespace()
-            .map(String::from)
-            .collect(),
+        args: optional_field_args(yaml, test_name, "args")?,
         stdin: optional_field_str(yaml, test_name, "stdin")?,
         stdout: optional_field_str(yaml, test_name, "stdout")?,
         stderr: optional_field_str(yaml, test_name, "stderr")?,
+        exit_code: optional_field_vec_i64(yaml, test_name, "exit-code")?,
         case_insensitive: field_bool(yaml, test_name, "case-insensitive")?,
+        // Whitespace at the ends of lines and blank lines at the end of the outputs is
+        // removed from both outputs before comparison (leading and trailing whitespace
+        // of the whole outputs is always removed)
+        ignore_trailing_whitespace: field_bool(yaml, test_name, "ignore-trailing-whitespace")?,
+        normalize_unicode: field_bool(yaml, test_name, "normalize-unicode")?,
+        sort_fields: optional_field_str(yaml, test_name, "sort-fields")?,
+        unordered: field_bool(yaml, test_name, "unordered")?,
+        columns: match field_bool(yaml, test_name, "columns")? {
+            true => Some(tolerances.unwrap_or_default()),
+            false => tolerances,
+        },
+        tolerance,
+        equivalences: optional_field_token_groups(yaml, test_name, "equivalences")?,
+        stdin_file: None,
+        stdout_file: None,
+        stdout_sha256,
+        timeout: optional_field_u64(yaml, test_name, "timeout")?,
+        env: optional_field_dict_str(yaml, test_name, "env")?.unwrap_or_default(),
     })
 }
 
-fn analyses_from_yaml(yaml: &Yaml) -> Result<Vec<Box<dyn Analyser>>, ConfigError> {
+/// Generate one test case for each `*.in` file in `dir` (relative to `project_path`).
+/// The file is passed to stdin and the matching `*.out` file is expected on stdout.
+/// Other fields of the cases are copied from `base`. Cases are sorted by the file names.
+fn test_cases_from_dir(
+    dir: &str,
+    project_path: &Path,
+    base: &TestCase,
+) -> Result<Vec<TestCase>, ConfigError> {
+    let mut inputs = read_dir(project_path.join(dir))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<PathBuf>, std::io::Error>>()?
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "in") && path.is_file())
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect::<Vec<String>>();
+    inputs.sort();
+
+    let dir = Path::new(dir);
+    Ok(inputs
+        .iter()
+        .map(|name| TestCase {
+            stdin: Some(format!("<{}", dir.join(format!("{}.in", name)).display())),
+            stdout: Some(format!("<{}", dir.join(format!("{}.out", name)).display())),
+            ..base.clone()
+        })
+        .collect())
+}
+
+/// Parse analysers (`tests` are the processed tests, with outputs loaded from files)
+fn analyses_from_yaml(
+    yaml: &Yaml,
+    project_path: &Path,
+    tests: &[Test],
+) -> Result<Vec<Box<dyn Analyser>>, ConfigError> {
     let mut result = vec![];
     for analysis in yaml.as_vec().unwrap_or(&vec![]) {
         let analysis_name = mandatory_field_str(analysis, "analysis", "analyser")?;
         let kind = AnalyserKind::from(&analysis_name);
         match &kind {
             AnalyserKind::NoCall => {
-                check_analysis_fields(analysis, &analysis_name, &vec!["funs", "penalty"])?;
+                check_analysis_fields(analysis, &analysis_name, &["funs", "penalty"])?;
                 result.push(Box::new(NoCallAnalyser::new(
                     mandatory_field_vec_str(analysis, "no-call analyser", "funs")?,
                     mandatory_field_f64(analysis, "no-call analyser", "penalty")?,
                 )) as Box<dyn Analyser>);
             }
+            AnalyserKind::RequireCall => {
+                check_analysis_fields(analysis, &analysis_name, &["funs", "penalty"])?;
+                result.push(Box::new(RequireCallAnalyser::new(
+                    mandatory_field_vec_str(analysis, "require-call analyser", "funs")?,
+                    mandatory_field_f64(analysis, "require-call analyser", "penalty")?,
+                )) as Box<dyn Analyser>);
+            }
             AnalyserKind::NoHeader => {
-                check_analysis_fields(analysis, &analysis_name, &vec!["header", "penalty"])?;
+                check_analysis_fields(analysis, &analysis_name, &["header", "penalty"])?;
                 result.push(Box::new(NoHeaderAnalyser::new(
                     mandatory_field_str(analysis, "no-header analyser", "header")?,
                     mandatory_field_f64(analysis, "no-header analyser", "penalty")?,
                 )) as Box<dyn Analyser>);
             }
             AnalyserKind::NoGlobals => {
-                check_analysis_fields(analysis, &analysis_name, &vec!["penalty", "except"])?;
+                check_analysis_fields(analysis, &analysis_name, &["penalty", "except"])?;
                 result.push(Box::new(NoGlobalsAnalyser::new(
                     mandatory_field_f64(analysis, "no-globals", "penalty")?,
                     optional_field_vec_str(analysis, "no-globals", "except")?.unwrap_or(vec![]),
                 )) as Box<dyn Analyser>);
             }
-            AnalyserKind::Unsupported => {
-                warn!(
-                    "Configuration contains an unsupported analysis \'{}\'",
-                    analysis_name
-                );
+            AnalyserKind::NoFloatEq => {
+                check_analysis_fields(analysis, &analysis_name, &["penalty", "max-penalty"])?;
+                result.push(Box::new(NoFloatEqAnalyser::new(
+                    mandatory_field_f64(analysis, "no-float-eq", "penalty")?,
+                    optional_field_f64(analysis, "no-float-eq", "max-penalty")?,
+                )) as Box<dyn Analyser>);
             }
-        }
-    }
-    Ok(result)
-}
-
-/// Check if `yaml` is a YAML dictionary (hash) and that it does not contain any keys
-/// except those given in `fields`. If an extra key is found, emits a warning.
-fn check_fields(yaml: &Yaml, name: &str, fields: &Vec<&str>) -> Result<(), ConfigError> {
-    for field in yaml
+            AnalyserKind::NoGoto => {
+                check_analysis_fields(analysis, &analysis_name, &["penalty", "max-allowed"])?;
+                let max_allowed = match optional_field_i64(analysis, "no-goto", "max-allowed")? {
+                    Some(n) if n < 0 => Err(make_error!(
+                        InvalidField,
+                        option: "no-goto",
+                        field: "max-allowed",
+                        expected_type: "non-negative int"
+                    ))?,
+                    n => n.unwrap_or(0) as usize,
+                };
+                result.push(Box::new(NoGotoAnalyser::new(
+                    mandatory_field_f64(analysis, "no-goto", "penalty")?,
+                    max_allowed,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::MaxFunctionLength => {
+                check_analysis_fields(
+                    analysis,
+                    &analysis_name,
+                    &["limit", "penalty", "per-function"],
+                )?;
+                result.push(Box::new(MaxFunctionLengthAnalyser::new(
+                    optional_field_u64(analysis, "max-function-length", "limit")?.ok_or(
+                        make_error!(MissingField, option: "max-function-length", field: "limit"),
+                    )?,
+                    mandatory_field_f64(analysis, "max-function-length", "penalty")?,
+                    field_bool(analysis, "max-function-length", "per-function")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::NoRecursion => {
+                check_analysis_fields(analysis, &analysis_name, &["penalty", "except"])?;
+                result.push(Box::new(NoRecursionAnalyser::new(
+                    mandatory_field_f64(analysis, "no-recursion", "penalty")?,
+                    optional_field_vec_str(analysis, "no-recursion", "except")?.unwrap_or(vec![]),
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::CheckAlloc => {
+                check_analysis_fields(analysis, &analysis_name, &["funs", "severity", "penalty"])?;
+                result.push(Box::new(CheckAllocAnalyser::new(
+                    optional_field_vec_str(analysis, "check-alloc", "funs")?.unwrap_or(vec![
+                        "malloc".to_string(),
+                        "calloc".to_string(),
+                        "realloc".to_string(),
+                    ]),
+                    severity_from_yaml(analysis, "check-alloc", "report")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::UnsafeInput => {
+                check_analysis_fields(analysis, &analysis_name, &["rules", "messages", "penalty"])?;
+                result.push(Box::new(unsafe_input_from_yaml(analysis)?) as Box<dyn Analyser>);
+            }
+            AnalyserKind::PairBalance => {
+                check_analysis_fields(
+                    analysis,
+                    &analysis_name,
+                    &["pairs", "slack", "severity", "penalty"],
+                )?;
+                result.push(Box::new(PairBalanceAnalyser::new(
+                    pairs_from_yaml(analysis, "pair-balance", "pairs")?,
+                    optional_field_i64(analysis, "pair-balance", "slack")?.unwrap_or(0),
+                    severity_from_yaml(analysis, "pair-balance", "penalty")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::SourceSize => {
+                check_analysis_fields(
+                    analysis,
+                    &analysis_name,
+                    &[
+                        "min-lines",
+                        "max-lines",
+                        "max-identical-to-file",
+                        "severity",
+                        "penalty",
+                    ],
+                )?;
+                let template =
+                    match optional_field_str(analysis, "source-size", "max-identical-to-file")? {
+                        Some(file) => Some(read_template(
+                            &file,
+                            project_path,
+                            "source-size",
+                            "max-identical-to-file",
+                        )?),
+                        None => None,
+                    };
+                result.push(Box::new(SourceSizeAnalyser::new(
+                    optional_field_u64(analysis, "source-size", "min-lines")?.map(|n| n as usize),
+                    optional_field_u64(analysis, "source-size", "max-lines")?.map(|n| n as usize),
+                    template,
+                    severity_from_yaml(analysis, "source-size", "penalty")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::TemplateSimilarity => {
+                check_analysis_fields(
+                    analysis,
+                    &analysis_name,
+                    &["template", "threshold", "severity", "penalty"],
+                )?;
+                let file = mandatory_field_str(analysis, "template-similarity", "template")?;
+                result.push(Box::new(TemplateSimilarityAnalyser::new(
+                    read_template(&file, project_path, "template-similarity", "template")?,
+                    optional_field_f64(analysis, "template-similarity", "threshold")?
+                        .unwrap_or(0.9),
+                    severity_from_yaml(analysis, "template-similarity", "penalty")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::HardcodedOutput => {
+                check_analysis_fields(
+                    analysis,
+                    &analysis_name,
+                    &["min-length", "threshold", "severity", "penalty"],
+                )?;
+                result.push(Box::new(HardcodedOutputAnalyser::new(
+                    tests,
+                    optional_field_u64(analysis, "hardcoded-output", "min-length")?.unwrap_or(20)
+                        as usize,
+                    optional_field_f64(analysis, "hardcoded-output", "threshold")?.unwrap_or(0.5),
+                    severity_from_yaml(analysis, "hardcoded-output", "report")?,
+                )) as Box<dyn Analyser>);
+            }
+            AnalyserKind::Custom(constructor) => result.push(constructor(analysis)?),
+            AnalyserKind::Unsupported => unknown_option(&analysis_name, "analyses"),
+        }
+        if matches!(kind, AnalyserKind::Unsupported) {
+            continue;
+        }
+        // Options common to all analyses
+        let gate = match field_bool(analysis, "analysis", "gate")? {
+            true => Some(optional_field_f64(analysis, "analysis", "fail-score")?.unwrap_or(0.0)),
+            false => None,
+        };
+        let level = match optional_field_str(analysis, "analysis", "level")? {
+            Some(level) => Some(Level::from(&level).ok_or_else(|| {
+                make_error!(
+                    InvalidField,
+                    option: analysis_name,
+                    field: "level",
+                    expected_type: "\"warning\" or \"error\""
+                )
+            })?),
+            None => None,
+        };
+        if gate.is_some() || level.is_some() {
+            let analyser = result.pop().unwrap();
+            result.push(Box::new(ConfiguredAnalyser::new(analyser, gate, level)));
+        }
+    }
+    Ok(result)
+}
+
+/// Read a template `file` (relative to `project_path`) used by the analyser `name`
+/// Returns the name of the file and its contents.
+fn read_template(
+    file: &str,
+    project_path: &Path,
+    name: &str,
+    field: &str,
+) -> Result<(String, String), ConfigError> {
+    let path = project_path.join(file);
+    match read_to_string(&path) {
+        Ok(source) => Ok((file.to_string(), source)),
+        Err(e) => Err(ConfigError::MissingFiles {
+            files: vec![format!(
+                "analyser {}, field '{}': {}: {}",
+                name,
+                field,
+                path.display(),
+                e
+            )],
+        }),
+    }
+}
+
+/// Severity of a heuristic analyser (`default` if not specified)
+/// The `penalty` field is mandatory when severity is set to `penalty`.
+fn severity_from_yaml(yaml: &Yaml, name: &str, default: &str) -> Result<Severity, ConfigError> {
+    match optional_field_str(yaml, name, "severity")?
+        .as_deref()
+        .unwrap_or(default)
+    {
+        "report" => Ok(Severity::Report),
+        "penalty" => Ok(Severity::Penalty(mandatory_field_f64(
+            yaml, name, "penalty",
+        )?)),
+        _ => Err(make_error!(
+            InvalidField,
+            option: name,
+            field: "severity",
+            expected_type: "\"report\" or \"penalty\""
+        )),
+    }
+}
+
+/// Parse the unsafe-input analyser
+/// Rules are configured by `rules` (severities "penalty", "report", or "off") and `messages`,
+/// the `penalty` field is mandatory if some enabled rule is penalized.
+fn unsafe_input_from_yaml(yaml: &Yaml) -> Result<UnsafeInputAnalyser, ConfigError> {
+    let name = "unsafe-input";
+    let mut rules = UnsafeInputRule::defaults();
+    let mut enabled = vec![true; rules.len()];
+    let find_rule = |rules: &[UnsafeInputRule], field: &str, id: &str| {
+        rules.iter().position(|r| r.id == id).ok_or_else(|| {
+            make_error!(
+                InvalidField,
+                option: name,
+                field: format!("{}.{}", field, id),
+                expected_type: "gets, scanf-string, or strcpy rule"
+            )
+        })
+    };
+    for (id, severity) in optional_field_dict_str(yaml, name, "rules")?.unwrap_or_default() {
+        let i = find_rule(&rules, "rules", &id)?;
+        match severity.as_str() {
+            "penalty" => rules[i].report = false,
+            "report" => rules[i].report = true,
+            "off" => enabled[i] = false,
+            _ => {
+                return Err(make_error!(
+                    InvalidField,
+                    option: name,
+                    field: format!("rules.{}", id),
+                    expected_type: "\"penalty\", \"report\", or \"off\""
+                ))
+            }
+        }
+    }
+    for (id, message) in optional_field_dict_str(yaml, name, "messages")?.unwrap_or_default() {
+        let i = find_rule(&rules, "messages", &id)?;
+        rules[i].message = message;
+    }
+    let mut enabled = enabled.into_iter();
+    rules.retain(|_| enabled.next().unwrap_or(false));
+
+    let penalty = match rules.iter().all(|r| r.report) {
+        true => optional_field_f64(yaml, name, "penalty")?.unwrap_or(0.0),
+        false => mandatory_field_f64(yaml, name, "penalty")?,
+    };
+    Ok(UnsafeInputAnalyser::new(rules, penalty))
+}
+
+fn late_penalty_from_yaml(yaml: &Yaml) -> Result<LatePenaltyConfig, ConfigError> {
+    let name = "late-penalty";
+    check_fields(
+        yaml,
+        name,
+        &[
+            "deadline",
+            "timestamp-source",
+            "timestamp-file",
+            "curve",
+            "per-day",
+        ],
+    )?;
+
+    let deadline = match &yaml["deadline"] {
+        Yaml::BadValue => Err(make_error!(MissingField, option: name, field: "deadline"))?,
+        Yaml::Integer(ts) => *ts,
+        val => val.as_str().and_then(parse_timestamp).ok_or(make_error!(
+            InvalidField,
+            option: name,
+            field: "deadline",
+            expected_type: "date (YYYY-MM-DD HH:MM:SS) or Unix timestamp"
+        ))?,
+    };
+    let timestamp = match optional_field_str(yaml, name, "timestamp-source")?.as_deref() {
+        Some("file") | None => TimestampSource::File(PathBuf::from(
+            optional_field_str(yaml, name, "timestamp-file")?
+                .unwrap_or_else(|| "submitted-at".to_string()),
+        )),
+        Some("source-mtime") => TimestampSource::SourceMtime,
+        Some(_) => Err(make_error!(
+            InvalidField,
+            option: name,
+            field: "timestamp-source",
+            expected_type: "\"file\" or \"source-mtime\""
+        ))?,
+    };
+    let curve = match optional_field_str(yaml, name, "curve")?.as_deref() {
+        Some("linear") | None => PenaltyCurve::Linear,
+        Some("exponential") => PenaltyCurve::Exponential,
+        Some(_) => Err(make_error!(
+            InvalidField,
+            option: name,
+            field: "curve",
+            expected_type: "\"linear\" or \"exponential\""
+        ))?,
+    };
+
+    Ok(LatePenaltyConfig {
+        deadline,
+        timestamp,
+        curve,
+        per_day: mandatory_field_f64(yaml, name, "per-day")?,
+    })
+}
+
+/// Check if `yaml` is a YAML dictionary (hash) and that it does not contain any keys
+/// except those given in `fields`. If an extra key is found, emits a warning.
+fn check_fields(yaml: &Yaml, name: &str, fields: &[&str]) -> Result<(), ConfigError> {
+    for field in yaml
         .as_hash()
         .ok_or(make_error!(InvalidOption, option: name, expected_type: "dictionary"))?
         .keys()
     {
         let field_name = field.as_str().unwrap_or_default();
         if !fields.contains(&field_name) {
-            warn!(
-                "Configuration of \'{}\' has unsupported option \'{}\'",
-                name, field_name
-            );
+            unknown_option(field_name, name);
         }
     }
     Ok(())
@@ -300,9 +1888,9 @@ fn check_fields(yaml: &Yaml, name: &str, fields: &Vec<&str>) -> Result<(), Confi
 
 /// Same as `check_fields`, only specialized for analysis config, which always contains
 /// a field "analyser".
-fn check_analysis_fields(yaml: &Yaml, name: &str, fields: &Vec<&str>) -> Result<(), ConfigError> {
-    let mut analyser_fields = fields.clone();
-    analyser_fields.push("analyser");
+fn check_analysis_fields(yaml: &Yaml, name: &str, fields: &[&str]) -> Result<(), ConfigError> {
+    let mut analyser_fields = fields.to_vec();
+    analyser_fields.extend(&["analyser", "gate", "fail-score", "level"]);
     let analyser_name = "analyser ".to_string() + name;
     check_fields(yaml, &analyser_name, &analyser_fields)
 }
@@ -319,8 +1907,131 @@ fn optional_field_i64(yaml: &Yaml, name: &str, field: &str) -> Result<Option<i64
     }
 }
 
-/// Parse `field` from `yaml` as a u32 number.
-/// Yields `ConfigError` if the value is not a u32.
+/// Parse `field` from `yaml` as a vector of i64 numbers.
+/// A single number is accepted, too, and parsed as a one-element vector.
+/// Yields `ConfigError` if the value is not a i64 or a vector of i64.
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_vec_i64(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<Vec<i64>>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "integer or list of integers"
+        )
+    };
+    match &yaml[field] {
+        Yaml::BadValue => Ok(None),
+        Yaml::Integer(n) => Ok(Some(vec![*n])),
+        Yaml::Array(v) => Ok(Some(
+            v.iter()
+                .map(|n| n.as_i64().ok_or_else(err))
+                .collect::<Result<Vec<i64>, ConfigError>>()?,
+        )),
+        _ => Err(err()),
+    }
+}
+
+/// Parse `field` from `yaml` as a vector of f64 numbers (integers are accepted, too).
+/// A single number is accepted, too, and parsed as a one-element vector.
+/// Yields `ConfigError` if the value is not a number or a vector of numbers.
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_vec_f64(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<Vec<f64>>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "number or list of numbers"
+        )
+    };
+    let number = |n: &Yaml| yaml_number(n).ok_or_else(err);
+    match &yaml[field] {
+        Yaml::BadValue => Ok(None),
+        Yaml::Array(v) => Ok(Some(
+            v.iter()
+                .map(number)
+                .collect::<Result<Vec<f64>, ConfigError>>()?,
+        )),
+        n => Ok(Some(vec![number(n)?])),
+    }
+}
+
+/// Parse `field` from `yaml` as a tolerance of numbers: a non-negative number (absolute)
+/// or a string with a percentage (relative to the expected number, e.g. "0.1%").
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_tolerance(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<Tolerance>, ConfigError> {
+    let tolerance = match &yaml[field] {
+        Yaml::BadValue => return Ok(None),
+        Yaml::String(s) => s
+            .strip_suffix('%')
+            .and_then(|p| p.trim().parse::<f64>().ok())
+            .map(|p| Tolerance::Relative(p / 100.0)),
+        n => yaml_number(n).map(Tolerance::Absolute),
+    };
+    match tolerance {
+        Some(Tolerance::Absolute(t) | Tolerance::Relative(t)) if t >= 0.0 => Ok(tolerance),
+        _ => Err(make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "non-negative number or percentage"
+        )),
+    }
+}
+
+/// Parse `field` from `yaml` as a list of groups of tokens (lists of scalars).
+/// Scalars other than strings (e.g. `true` or `1`) are taken as they are written.
+/// Yields `ConfigError` if the value is not a list of non-empty lists of scalars.
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_token_groups(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<Vec<Vec<String>>>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "list of lists of tokens"
+        )
+    };
+    let token = |t: &Yaml| match t {
+        Yaml::String(s) | Yaml::Real(s) => Ok(s.clone()),
+        Yaml::Integer(i) => Ok(i.to_string()),
+        Yaml::Boolean(b) => Ok(b.to_string()),
+        _ => Err(err()),
+    };
+    match &yaml[field] {
+        Yaml::BadValue => Ok(None),
+        val => Ok(Some(
+            val.as_vec()
+                .ok_or_else(err)?
+                .iter()
+                .map(|group| match group.as_vec() {
+                    Some(tokens) if !tokens.is_empty() => tokens.iter().map(token).collect(),
+                    _ => Err(err()),
+                })
+                .collect::<Result<Vec<Vec<String>>, ConfigError>>()?,
+        )),
+    }
+}
+
+/// Parse `field` from `yaml` as a positive u64 number.
+/// Yields `ConfigError` if the value is not a positive integer.
 /// Returns None if `yaml` does not contain `field`.
 fn optional_field_u64(yaml: &Yaml, name: &str, field: &str) -> Result<Option<u64>, ConfigError> {
     match optional_field_i64(yaml, name, field)? {
@@ -334,20 +2045,51 @@ fn optional_field_u64(yaml: &Yaml, name: &str, field: &str) -> Result<Option<u64
     }
 }
 
-/// Parse `field` from `yaml` as a f64 number.
-/// Yields `ConfigError` if the value is not a f64.
+/// Parse `field` from `yaml` as a f64 number (integers are accepted, too).
+/// Yields `ConfigError` if the value is not a number.
 /// Returns None if `yaml` does not contain `field`.
 fn optional_field_f64(yaml: &Yaml, name: &str, field: &str) -> Result<Option<f64>, ConfigError> {
     match &yaml[field] {
         Yaml::BadValue => Ok(None),
-        val => Ok(Some(val.as_f64().ok_or(
-            make_error!(InvalidField, option: name, field: field, expected_type: "float number"),
+        val => Ok(Some(yaml_number(val).ok_or(
+            make_error!(InvalidField, option: name, field: field, expected_type: "number"),
         )?)),
     }
 }
 
-/// Parse `field` from `yaml` as a f64 number.
-/// Yields `ConfigError` if `yaml` does not contain `field` or its value is not a f64.
+/// Value of a YAML number (integers are converted), None for other values
+fn yaml_number(yaml: &Yaml) -> Option<f64> {
+    match yaml {
+        Yaml::Integer(n) => Some(*n as f64),
+        n => n.as_f64(),
+    }
+}
+
+/// Parse `field` from `yaml` as a non-negative number (an integer or a float).
+/// Yields `ConfigError` if the value is not a non-negative number.
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_non_negative(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<f64>, ConfigError> {
+    let number = match &yaml[field] {
+        Yaml::BadValue => return Ok(None),
+        n => yaml_number(n),
+    };
+    match number {
+        Some(n) if n >= 0.0 => Ok(Some(n)),
+        _ => Err(make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "non-negative number"
+        )),
+    }
+}
+
+/// Parse `field` from `yaml` as a f64 number (integers are accepted, too).
+/// Yields `ConfigError` if `yaml` does not contain `field` or its value is not a number.
 fn mandatory_field_f64(yaml: &Yaml, name: &str, field: &str) -> Result<f64, ConfigError> {
     optional_field_f64(yaml, name, field)?
         .ok_or_else(|| make_error!(MissingField, option: name, field: field))
@@ -402,6 +2144,121 @@ fn optional_field_vec_str(
     }
 }
 
+/// Parse `field` from `yaml` as program arguments.
+/// A string is split into words as by a shell (see `split_words`), elements of a list of
+/// strings are taken verbatim.
+/// Yields `ConfigError` if the value is neither a string nor a list of strings.
+fn optional_field_args(yaml: &Yaml, name: &str, field: &str) -> Result<Vec<String>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "string or list of strings")
+    };
+    match &yaml[field] {
+        Yaml::BadValue => Ok(vec![]),
+        Yaml::String(args) => split_words(args).map_err(|_| {
+            make_error!(
+                InvalidField,
+                option: name,
+                field: field,
+                expected_type: "properly quoted arguments")
+        }),
+        Yaml::Array(args) => args
+            .iter()
+            .map(|arg| arg.as_str().map(String::from).ok_or_else(err))
+            .collect(),
+        _ => Err(err()),
+    }
+}
+
+/// Split `s` into words like a shell does (without any expansions). Words are separated
+/// by whitespace, which can be made part of a word by quoting:
+///   - characters in single quotes are taken literally,
+///   - in double quotes, a backslash only escapes `"` and `\`,
+///   - outside of quotes, a backslash escapes any character.
+///
+/// Quotes may be adjacent to other parts of a word (`a"b c"` is one word) and empty
+/// quotes give an empty word. Yields an error message for unterminated quotes and
+/// a trailing backslash.
+fn split_words(s: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    // Current word (None between words)
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing backslash".to_string()),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Check if `s` contains an unquoted pipe, redirection, or command separator, i.e. if it
+/// must be run by a shell (quoting is the same as in `split_words`)
+fn needs_shell(s: &str) -> bool {
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '|' | '<' | '>' | ';' | '&' => return true,
+            '\'' => {
+                chars.by_ref().find(|&c| c == '\'');
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '\\' => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
 /// Parse `field` from `yaml` as a vector of string.
 /// Yields `ConfigError` if `yaml` does not contain `field`
 /// or its value is not a vector of strings.
@@ -414,6 +2271,81 @@ fn mandatory_field_vec_str(
         .ok_or_else(|| make_error!(MissingField, option: name, field: field))
 }
 
+/// Parse `field` from `yaml` as a list of pairs of strings (2-element lists).
+/// Yields `ConfigError` if `yaml` does not contain `field` or if it has a wrong type.
+fn pairs_from_yaml(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Vec<(String, String)>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "list of pairs of strings"
+        )
+    };
+    match &yaml[field] {
+        Yaml::BadValue => Err(make_error!(MissingField, option: name, field: field)),
+        Yaml::Array(pairs) => pairs
+            .iter()
+            .map(|pair| match pair.as_vec().map(|p| p.as_slice()) {
+                Some([Yaml::String(first), Yaml::String(second)]) => {
+                    Ok((first.clone(), second.clone()))
+                }
+                _ => Err(err()),
+            })
+            .collect(),
+        _ => Err(err()),
+    }
+}
+
+/// Parse `field` from `yaml` as a dictionary mapping strings to strings.
+/// Numeric values are converted to strings, null values to empty strings.
+/// Yields `ConfigError` if the value is not a dictionary of strings (or numbers), an invalid
+/// value is reported as the field `<field>.<key>`.
+/// Returns None if `yaml` does not contain `field`.
+fn optional_field_dict_str(
+    yaml: &Yaml,
+    name: &str,
+    field: &str,
+) -> Result<Option<Vec<(String, String)>>, ConfigError> {
+    let err = || {
+        make_error!(
+            InvalidField,
+            option: name,
+            field: field,
+            expected_type: "dictionary of strings"
+        )
+    };
+    match &yaml[field] {
+        Yaml::BadValue => Ok(None),
+        Yaml::Hash(h) => Ok(Some(
+            h.iter()
+                .map(|(k, v)| {
+                    let key = k.as_str().ok_or_else(err)?;
+                    let value = match v {
+                        Yaml::String(s) | Yaml::Real(s) => s.clone(),
+                        Yaml::Integer(n) => n.to_string(),
+                        Yaml::Null => String::new(),
+                        _ => {
+                            return Err(make_error!(
+                                InvalidField,
+                                option: name,
+                                field: format!("{}.{}", field, key),
+                                expected_type: "string"
+                            ))
+                        }
+                    };
+                    Ok((key.to_string(), value))
+                })
+                .collect::<Result<Vec<(String, String)>, ConfigError>>()?,
+        )),
+        _ => Err(err()),
+    }
+}
+
 /// Parse `field` from `yaml` as a boolean.
 /// Yields `ConfigError` if the value is not a bool.
 /// Returns false if `yaml` does not contain `field`.
@@ -437,32 +2369,166 @@ fn expand_string_from_file(string: &str, project_path: &Path) -> Result<String,
     Ok(string.to_string())
 }
 
-/// If `string` has form "$(shell command)", execute the command and return its stdout.
-/// Otherwise return the original `string`.
-/// If the command fails to execute, an error is returned.
-fn expand_string_from_command(string: &str) -> Result<String, ConfigError> {
+/// If `string` has form "$(shell command)", execute the command in `project_path` and return
+/// its stdout. Otherwise return the original `string`.
+/// If the command fails to execute or exits with a non-zero code, an error is returned.
+fn expand_string_from_command(string: &str, project_path: &Path) -> Result<String, ConfigError> {
     if !string.starts_with("$(") {
         return Ok(string.to_string());
     }
 
+    let output = command_from_string(string)?
+        .current_dir(project_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(command_failed(string, &output));
+    }
+    String::from_utf8(output.stdout).map_err(|e| make_error!(InvalidCommand, msg: format!("{}", e)))
+}
+
+/// Error of the command given by `string` (see `command_from_string`) which failed
+/// The first line of the error output of the command is included in the message.
+fn command_failed(string: &str, output: &Output) -> ConfigError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    make_error!(
+        InvalidCommand,
+        msg: format!(
+            "'{}' failed ({}){}",
+            &string[2..string.len() - 1],
+            output.status,
+            stderr
+                .lines()
+                .next()
+                .map_or(String::new(), |line| format!(": {}", line))
+        )
+    )
+}
+
+/// Create the command given by `string` of the form "$(shell command)"
+/// The command is split into the program and its arguments by `split_words`. Commands
+/// with pipes or redirections (see `needs_shell`) are run by `sh -c` instead.
+fn command_from_string(string: &str) -> Result<Command, ConfigError> {
     if !string.ends_with(')') {
         return Err(make_error!(InvalidCommand, msg: "missing trailing \')\'"));
     }
 
-    let cmd: Vec<&str> = string[2..string.len() - 1].split(' ').collect();
-    if cmd.len() == 0 || cmd.len() == 1 && cmd[0].trim().is_empty() {
-        return Err(make_error!(InvalidCommand, msg: "empty command"));
+    let string = &string[2..string.len() - 1];
+    let cmd = split_words(string).map_err(|e| make_error!(InvalidCommand, msg: e))?;
+    if !cmd.is_empty() && needs_shell(string) {
+        let mut command = Command::new("sh");
+        command.args(["-c", string]);
+        return Ok(command);
+    }
+    match cmd.split_first() {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            Ok(command)
+        }
+        None => Err(make_error!(InvalidCommand, msg: "empty command")),
+    }
+}
+
+/// Load the fixture `name` from its `source`
+/// A file ("<file") is used directly, a command ("$(command)") is run once and its
+/// output is stored in a file in the `workspace` of the run, or in `dir` (which is
+/// created if needed) if there is no workspace.
+fn load_fixture(
+    name: &str,
+    source: &str,
+    project_path: &Path,
+    dir: &mut Option<TempDir>,
+    workspace: Option<&Workspace>,
+) -> Result<DataFile, ConfigError> {
+    let path = match source.strip_prefix('<') {
+        Some(file) => {
+            let path = project_path.join(file.trim());
+            if let Err(e) = std::fs::metadata(&path) {
+                return Err(ConfigError::MissingFiles {
+                    files: vec![format!("fixture '{}': {}: {}", name, path.display(), e)],
+                });
+            }
+            path
+        }
+        None => {
+            let path = match workspace {
+                Some(workspace) => {
+                    let fixtures = workspace.path().join("fixtures");
+                    std::fs::create_dir_all(&fixtures)?;
+                    fixtures.join(name)
+                }
+                None => {
+                    if dir.is_none() {
+                        *dir = Some(
+                            tempfile::Builder::new()
+                                .prefix("atst-fixtures-")
+                                .tempdir()?,
+                        );
+                    }
+                    dir.as_ref().unwrap().path().join(name)
+                }
+            };
+            info!("Generating fixture '{}'", name);
+            // Commands are run in the project directory, as in expected outputs
+            let output = command_from_string(source)?
+                .current_dir(project_path)
+                .stdout(File::create(&path)?)
+                .stderr(Stdio::piped())
+                .output()?;
+            // A partial output of a failed command must not be used as an input
+            if !output.status.success() {
+                return Err(command_failed(source, &output));
+            }
+            path
+        }
+    };
+    Ok(DataFile {
+        name: format!("@{}", name),
+        path,
+    })
+}
+
+/// Path of the file referenced by `string` ("<file") relative to `project_path`
+/// Fails if the file cannot be read.
+fn referenced_file(string: &str, project_path: &Path) -> Result<PathBuf, std::io::Error> {
+    let path = project_path.join(&string.trim()[1..]);
+    File::open(&path)?;
+    if !path.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "not a file",
+        ));
     }
+    Ok(path)
+}
 
-    let output = Command::new(&cmd[0]).args(&cmd[1..]).output()?;
-    Ok(String::from_utf8(output.stdout)
-        .map_err(|e| make_error!(InvalidCommand, msg: format!("{}", e)))?)
+/// Check if the file at `path` ends with a newline
+fn file_ends_with_newline(path: &Path) -> bool {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))
+        .and_then(|_| file.read_exact(&mut last))
+        .is_ok_and(|_| last[0] == b'\n')
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    /// Location of an error in the tests (see `ConfigError::InTest`) and the error itself
+    fn located(err: ConfigError) -> (String, ConfigError) {
+        match err {
+            ConfigError::InTest { location, source } => match located(*source) {
+                (inner, err) if inner.is_empty() => (location, err),
+                (inner, err) => (format!("{}: {}", location, inner), err),
+            },
+            err => (String::new(), err),
+        }
+    }
+
     #[test]
     fn parse_mandatory_str_ok() {
         let yaml = YamlLoader::load_from_str("option: { field: value }").unwrap();
@@ -536,6 +2602,25 @@ mod test {
         assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
     }
 
+    #[test]
+    fn parse_mandatory_f64_int_ok() {
+        let yaml = YamlLoader::load_from_str("option: { score: 2, penalty: -1 }").unwrap();
+        let f = mandatory_field_f64(&yaml[0]["option"], "option", "score");
+        assert_eq!(f.unwrap(), 2.0);
+        let f = mandatory_field_f64(&yaml[0]["option"], "option", "penalty");
+        assert_eq!(f.unwrap(), -1.0);
+    }
+
+    #[test]
+    fn parse_mandatory_f64_int_invalid() {
+        for value in ["true", "'1'", "[ 1 ]"] {
+            let yaml =
+                YamlLoader::load_from_str(&format!("option: {{ field: {} }}", value)).unwrap();
+            let err = mandatory_field_f64(&yaml[0]["option"], "option", "field");
+            assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
+        }
+    }
+
     #[test]
     fn parse_optional_i64_ok() {
         let yaml = YamlLoader::load_from_str("option: { field: 1 }").unwrap();
@@ -561,6 +2646,38 @@ mod test {
         assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
     }
 
+    #[test]
+    fn parse_optional_vec_i64_ok() {
+        let yaml = YamlLoader::load_from_str("option: { field: [ 0, 2 ] }").unwrap();
+        let f = optional_field_vec_i64(&yaml[0]["option"], "option", "field");
+        assert!(f.is_ok());
+        assert_eq!(f.unwrap().unwrap(), vec![0, 2]);
+    }
+
+    #[test]
+    fn parse_optional_vec_i64_single() {
+        let yaml = YamlLoader::load_from_str("option: { field: 1 }").unwrap();
+        let f = optional_field_vec_i64(&yaml[0]["option"], "option", "field");
+        assert!(f.is_ok());
+        assert_eq!(f.unwrap().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn parse_optional_vec_i64_missing() {
+        let yaml = YamlLoader::load_from_str("option: { field: 1 }").unwrap();
+        let f = optional_field_vec_i64(&yaml[0]["option"], "option", "other_field");
+        assert!(f.is_ok());
+        assert!(f.unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_optional_vec_i64_invalid() {
+        let yaml = YamlLoader::load_from_str("option: { field: [ 0, x ] }").unwrap();
+        let err = optional_field_vec_i64(&yaml[0]["option"], "option", "field");
+        assert!(err.is_err());
+        assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
+    }
+
     #[test]
     fn parse_optional_u64_ok() {
         let yaml = YamlLoader::load_from_str("option: { field: 1 }").unwrap();
@@ -595,6 +2712,13 @@ mod test {
         assert_eq!(f.unwrap().unwrap(), 1.0);
     }
 
+    #[test]
+    fn parse_optional_f64_int_ok() {
+        let yaml = YamlLoader::load_from_str("option: { field: 1 }").unwrap();
+        let f = optional_field_f64(&yaml[0]["option"], "option", "field");
+        assert_eq!(f.unwrap(), Some(1.0));
+    }
+
     #[test]
     fn parse_optional_f64_missing() {
         let yaml = YamlLoader::load_from_str("option: { field: 1.0 }").unwrap();
@@ -661,24 +2785,57 @@ mod test {
     }
 
     #[test]
-    fn parse_bool_ok() {
-        let yaml = YamlLoader::load_from_str("option: { field: true }").unwrap();
-        let f = field_bool(&yaml[0]["option"], "option", "field");
+    fn parse_optional_dict_str_ok() {
+        let yaml =
+            YamlLoader::load_from_str("option: { field: { A: x, B: 1, C: 1.5, D: } }").unwrap();
+        let f = optional_field_dict_str(&yaml[0]["option"], "option", "field");
         assert!(f.is_ok());
-        assert_eq!(f.unwrap(), true);
+        assert_eq!(
+            f.unwrap().unwrap(),
+            vec![
+                ("A".to_string(), "x".to_string()),
+                ("B".to_string(), "1".to_string()),
+                ("C".to_string(), "1.5".to_string()),
+                ("D".to_string(), "".to_string()),
+            ]
+        );
     }
 
     #[test]
-    fn parse_bool_missing() {
-        let yaml = YamlLoader::load_from_str("option: { field: true }").unwrap();
-        let f = field_bool(&yaml[0]["option"], "option", "other_field");
+    fn parse_optional_dict_str_missing() {
+        let yaml = YamlLoader::load_from_str("option: { field: { A: x } }").unwrap();
+        let f = optional_field_dict_str(&yaml[0]["option"], "option", "other_field");
         assert!(f.is_ok());
-        assert_eq!(f.unwrap(), false);
+        assert!(f.unwrap().is_none());
     }
 
     #[test]
-    fn parse_bool_err() {
-        let yaml = YamlLoader::load_from_str("option: { field: \"true\"}").unwrap();
+    fn parse_optional_dict_str_invalid() {
+        let yaml = YamlLoader::load_from_str("option: { field: { A: [ x ] } }").unwrap();
+        let err = optional_field_dict_str(&yaml[0]["option"], "option", "field");
+        assert!(err.is_err());
+        assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
+    }
+
+    #[test]
+    fn parse_bool_ok() {
+        let yaml = YamlLoader::load_from_str("option: { field: true }").unwrap();
+        let f = field_bool(&yaml[0]["option"], "option", "field");
+        assert!(f.is_ok());
+        assert!(f.unwrap());
+    }
+
+    #[test]
+    fn parse_bool_missing() {
+        let yaml = YamlLoader::load_from_str("option: { field: true }").unwrap();
+        let f = field_bool(&yaml[0]["option"], "option", "other_field");
+        assert!(f.is_ok());
+        assert!(!f.unwrap());
+    }
+
+    #[test]
+    fn parse_bool_err() {
+        let yaml = YamlLoader::load_from_str("option: { field: \"true\"}").unwrap();
         let err = field_bool(&yaml[0]["option"], "option", "field");
         assert!(err.is_err());
         assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
@@ -687,20 +2844,87 @@ mod test {
     #[test]
     fn check_fields_ok() {
         let yaml = YamlLoader::load_from_str("{ field1: val1, field2: val2 }").unwrap();
-        let res = check_fields(&yaml[0], "", &vec!["field1", "field2"]);
+        let res = check_fields(&yaml[0], "", &["field1", "field2"]);
         assert!(res.is_ok());
     }
 
     #[test]
     fn expand_string_from_command_ok() {
-        let res = expand_string_from_command("$(echo hello)");
+        let res = expand_string_from_command("$(echo hello)", Path::new("."));
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), "hello\n");
     }
 
+    #[test]
+    fn expand_string_from_command_quoted() {
+        let res = expand_string_from_command("$(printf '%s|' \"a  b\" '')", Path::new("."));
+        assert_eq!(res.unwrap(), "a  b||");
+        let res = expand_string_from_command("$(echo 'a)", Path::new("."));
+        assert!(matches!(
+            res.unwrap_err(),
+            ConfigError::InvalidCommand { .. }
+        ));
+    }
+
+    #[test]
+    fn expand_string_from_command_shell() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("words.txt"), "hello world\nbye\n").unwrap();
+        let res = expand_string_from_command(
+            "$(grep \"hello world\" words.txt | tr a-z A-Z)",
+            project.path(),
+        );
+        assert_eq!(res.unwrap(), "HELLO WORLD\n");
+        let res = expand_string_from_command("$(echo a > out.txt; cat out.txt)", project.path());
+        assert_eq!(res.unwrap(), "a\n");
+        // Quoted operators are passed to the program
+        let res = expand_string_from_command("$(echo 'a|b' \"<c>\" \\;)", project.path());
+        assert_eq!(res.unwrap(), "a|b <c> ;\n");
+
+        let res = expand_string_from_command("$(echo a | false)", project.path());
+        assert!(matches!(
+            res.unwrap_err(),
+            ConfigError::InvalidCommand { .. }
+        ));
+    }
+
+    #[test]
+    fn needs_shell_operators() {
+        assert!(needs_shell("cat a | wc -l"));
+        assert!(needs_shell("gen > out"));
+        assert!(needs_shell("sort <in"));
+        assert!(needs_shell("a; b"));
+        assert!(needs_shell("a && b"));
+        assert!(!needs_shell("echo hello"));
+        assert!(!needs_shell(r#"echo 'a | b' "c > d" \; "\"|""#));
+    }
+
+    #[test]
+    fn split_words_quoting() {
+        let split = |s: &str| split_words(s).unwrap();
+        assert_eq!(split("  -v  --name John "), vec!["-v", "--name", "John"]);
+        assert!(split(" ").is_empty());
+        // Quoted spaces
+        assert_eq!(split(r#"-o "my file.txt""#), vec!["-o", "my file.txt"]);
+        assert_eq!(split("-o 'my file.txt'"), vec!["-o", "my file.txt"]);
+        assert_eq!(split(r"-o my\ file.txt"), vec!["-o", "my file.txt"]);
+        // Adjacent quotes form a single word
+        assert_eq!(split(r#"a"b c"'d e'f"#), vec!["ab cd ef"]);
+        // Empty quoted arguments
+        assert_eq!(split(r#"a "" '' b"#), vec!["a", "", "", "b"]);
+        // Escapes
+        assert_eq!(split(r#""a\"b\\c\d""#), vec![r#"a"b\c\d"#]);
+        assert_eq!(split(r"'a\b' \'"), vec![r"a\b", "'"]);
+        // Unterminated quotes
+        assert!(split_words("-o \"my file").is_err());
+        assert!(split_words("-o 'my file").is_err());
+        assert!(split_words(r#""a\""#).is_err());
+        assert!(split_words(r"a\").is_err());
+    }
+
     #[test]
     fn expand_string_from_command_empty() {
-        let res = expand_string_from_command("$()");
+        let res = expand_string_from_command("$()", Path::new("."));
         assert!(res.is_err());
         assert!(matches!(
             res.unwrap_err(),
@@ -710,7 +2934,7 @@ mod test {
 
     #[test]
     fn expand_string_from_command_fail() {
-        let res = expand_string_from_command("$(nocmd)");
+        let res = expand_string_from_command("$(nocmd)", Path::new("."));
         assert!(res.is_err());
         assert!(matches!(res.unwrap_err(), ConfigError::BadFile { .. }));
     }
@@ -726,7 +2950,7 @@ mod test {
   stdout: output",
         )
         .unwrap();
-        let res = tests_from_yaml(&yaml[0]);
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
         assert!(res.is_ok());
         let tests = res.unwrap();
         assert_eq!(tests.len(), 1);
@@ -741,7 +2965,7 @@ mod test {
     #[test]
     fn tests_from_yaml_single_incomplete() {
         let yaml = YamlLoader::load_from_str("[{ score: 1.0 }]").unwrap();
-        let res = tests_from_yaml(&yaml[0]);
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
         assert!(res.is_ok());
         let tests = res.unwrap();
         assert_eq!(tests.len(), 1);
@@ -753,6 +2977,89 @@ mod test {
         assert!(tests[0].test_cases[0].stdout.is_none());
     }
 
+    #[test]
+    fn tests_from_yaml_defaults() {
+        let defaults = YamlLoader::load_from_str(
+            "
+case-insensitive: true
+args: [ --mode, fast ]
+timeout: 100
+env: { LC_ALL: C }",
+        )
+        .unwrap();
+        let with_defaults = YamlLoader::load_from_str(
+            "
+- name: single
+  score: 1.0
+  stdout: output
+- name: multiple
+  score: 1.0
+  timeout: 500
+  test-cases:
+    - stdout: a
+    - stdout: b
+      args: [ --mode, slow ]
+      case-insensitive: false
+      env: { LANG: C }
+- name: own environment
+  score: 1.0
+  env: { LANG: C }
+  stdout: c",
+        )
+        .unwrap();
+        // Explicit values win, lists are replaced and not concatenated
+        let expanded = YamlLoader::load_from_str(
+            "
+- name: single
+  score: 1.0
+  stdout: output
+  case-insensitive: true
+  args: [ --mode, fast ]
+  timeout: 100
+  env: { LC_ALL: C }
+- name: multiple
+  score: 1.0
+  timeout: 500
+  env: { LC_ALL: C }
+  test-cases:
+    - stdout: a
+      case-insensitive: true
+      args: [ --mode, fast ]
+    - stdout: b
+      args: [ --mode, slow ]
+      case-insensitive: false
+      env: { LANG: C }
+- name: own environment
+  score: 1.0
+  env: { LANG: C }
+  stdout: c
+  case-insensitive: true
+  args: [ --mode, fast ]
+  timeout: 100",
+        )
+        .unwrap();
+        let tests = tests_from_yaml(&with_defaults[0], &defaults[0], Path::new(".")).unwrap();
+        let expected = tests_from_yaml(&expanded[0], &Yaml::BadValue, Path::new(".")).unwrap();
+        assert_eq!(format!("{:?}", tests), format!("{:?}", expected));
+        assert!(tests[1].test_cases[0].case_insensitive);
+        assert_eq!(tests[1].test_cases[0].timeout, Some(500));
+        assert_eq!(tests[1].test_cases[1].args, vec!["--mode", "slow"]);
+        assert_eq!(
+            tests[1].test_cases[1].env,
+            vec![
+                ("LC_ALL".to_string(), "C".to_string()),
+                ("LANG".to_string(), "C".to_string())
+            ]
+        );
+
+        let invalid = YamlLoader::load_from_str("{ timeout: fast }").unwrap();
+        assert!(matches!(
+            tests_from_yaml(&with_defaults[0], &invalid[0], Path::new(".")),
+            Err(ConfigError::InvalidField { option, field, .. })
+                if option == "test-defaults" && field == "timeout"
+        ));
+    }
+
     #[test]
     fn tests_from_yaml_multiple() {
         let yaml = YamlLoader::load_from_str(
@@ -769,7 +3076,7 @@ mod test {
   require: any",
         )
         .unwrap();
-        let res = tests_from_yaml(&yaml[0]);
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
         assert!(res.is_ok());
         let tests = res.unwrap();
         assert_eq!(tests.len(), 1);
@@ -784,41 +3091,2123 @@ mod test {
         assert_eq!(tests[0].test_cases[1].stdout, Some("out".to_string()));
     }
 
+    #[test]
+    fn tests_from_yaml_cases_dir() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir(project.path().join("cases")).unwrap();
+        for file in ["02.in", "02.out", "01.in", "01.out", "notes.txt"] {
+            std::fs::write(project.path().join("cases").join(file), file).unwrap();
+        }
+        let yaml = YamlLoader::load_from_str(
+            "
+- name: io
+  score: 1.0
+  args: -n
+  cases-dir: cases",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, project.path());
+        assert!(res.is_ok());
+        let tests = res.unwrap();
+        assert_eq!(tests[0].test_cases.len(), 2);
+        for (case, name) in tests[0].test_cases.iter().zip(["01", "02"]) {
+            assert_eq!(case.args, vec!["-n"]);
+            assert_eq!(case.stdin, Some(format!("<cases/{}.in", name)));
+            assert_eq!(case.stdout, Some(format!("<cases/{}.out", name)));
+        }
+
+        let yaml = YamlLoader::load_from_str("[{ score: 1.0, cases-dir: nodir }]").unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, project.path());
+        assert!(matches!(
+            located(res.unwrap_err()).1,
+            ConfigError::BadFile { .. }
+        ));
+    }
+
+    #[test]
+    fn tests_from_yaml_partial_credit() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- name: default
+  score: 1.0
+  timeout-partial-credit: prefix
+- name: custom
+  score: 1.0
+  timeout-partial-credit: prefix
+  partial-credit: 0.25
+- name: none
+  score: 1.0",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let tests = res.unwrap();
+        assert_eq!(
+            tests[0].timeout_partial_credit,
+            Some(DEFAULT_PARTIAL_CREDIT)
+        );
+        assert_eq!(tests[1].timeout_partial_credit, Some(0.25));
+        assert_eq!(tests[2].timeout_partial_credit, None);
+    }
+
+    #[test]
+    fn tests_from_yaml_columns() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- score: 1.0
+  columns: true
+- score: 1.0
+  column-tolerance: [ 0, 0.5 ]
+- score: 1.0
+  column-tolerance: 1
+- score: 1.0",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let columns: Vec<Option<Vec<f64>>> = res
+            .unwrap()
+            .iter()
+            .map(|t| t.test_cases[0].columns.clone())
+            .collect();
+        assert_eq!(
+            columns,
+            vec![Some(vec![]), Some(vec![0.0, 0.5]), Some(vec![1.0]), None]
+        );
+    }
+
+    #[test]
+    fn tests_from_yaml_trailing_whitespace() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- score: 1.0
+  ignore-trailing-whitespace: true
+  case-insensitive: true
+- score: 1.0
+  test-cases:
+    - ignore-trailing-whitespace: true
+    - stdout: a",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let tests = res.unwrap();
+        assert!(tests[0].test_cases[0].ignore_trailing_whitespace);
+        assert!(tests[0].test_cases[0].case_insensitive);
+        assert!(tests[1].test_cases[0].ignore_trailing_whitespace);
+        assert!(!tests[1].test_cases[1].ignore_trailing_whitespace);
+    }
+
+    #[test]
+    fn tests_from_yaml_args() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+- score: 1.0
+  args: -v  --name John
+- score: 1.0
+  args: ["--name", "John Smith", ""]
+- score: 1.0"#,
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let args: Vec<Vec<String>> = res
+            .unwrap()
+            .iter()
+            .map(|t| t.test_cases[0].args.clone())
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                vec!["-v", "--name", "John"],
+                vec!["--name", "John Smith", ""],
+                vec![],
+            ]
+        );
+
+        let yaml = YamlLoader::load_from_str(
+            r#"
+- name: quoted
+  score: 1.0
+  args: -o "my file.txt" '' """#,
+        )
+        .unwrap();
+        let tests = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new(".")).unwrap();
+        assert_eq!(
+            tests[0].test_cases[0].args,
+            vec!["-o", "my file.txt", "", ""]
+        );
+        let yaml = YamlLoader::load_from_str(
+            r#"
+- name: unterminated
+  score: 1.0
+  args: -o "my file.txt"#,
+        )
+        .unwrap();
+        let err = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "tests[0] (name: 'unterminated'): 'unterminated' has invalid value of field 'args' \
+             (properly quoted arguments expected)"
+        );
+        assert!(matches!(located(err).1, ConfigError::InvalidField { .. }));
+
+        for args in ["{ a: b }", "[a, [b]]", "[1]"] {
+            let yaml = YamlLoader::load_from_str(&format!("- args: {}", args)).unwrap();
+            match tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new(".")).map_err(located) {
+                Err((
+                    _,
+                    ConfigError::InvalidField {
+                        field,
+                        expected_type,
+                        ..
+                    },
+                )) => {
+                    assert_eq!(field, "args");
+                    assert_eq!(expected_type, "string or list of strings");
+                }
+                _ => panic!("invalid args {} accepted", args),
+            }
+        }
+    }
+
+    #[test]
+    fn tests_from_yaml_tolerance() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- score: 1.0
+  tolerance: 0.001
+- score: 1.0
+  tolerance: 0
+- score: 1.0
+  tolerance: 0.5%
+- score: 1.0",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let tolerances: Vec<Option<Tolerance>> = res
+            .unwrap()
+            .iter()
+            .map(|t| t.test_cases[0].tolerance)
+            .collect();
+        assert_eq!(
+            tolerances,
+            vec![
+                Some(Tolerance::Absolute(0.001)),
+                Some(Tolerance::Absolute(0.0)),
+                Some(Tolerance::Relative(0.005)),
+                None
+            ]
+        );
+
+        for (tolerance, msg) in [
+            ("-0.1", "non-negative number or percentage"),
+            ("small", "non-negative number or percentage"),
+            (
+                "1\n  columns: true",
+                "cannot combine field 'tolerance' with 'columns'",
+            ),
+        ] {
+            let yaml =
+                YamlLoader::load_from_str(&format!("- score: 1.0\n  tolerance: {}", tolerance))
+                    .unwrap();
+            let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+            assert!(res.is_err());
+            assert!(res.unwrap_err().to_string().contains(msg));
+        }
+    }
+
+    #[test]
+    fn config_equivalences() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+test-config:
+  equivalences: [ [ true, 1 ], [ yes, y ] ]
+tests:
+  - score: 1.0
+  - score: 1.0
+    equivalences: [ [ '1.0', 1 ] ]
+",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let equivalences: Vec<Option<Vec<Vec<String>>>> = res
+            .unwrap()
+            .tests
+            .iter()
+            .map(|t| t.test_cases[0].equivalences.clone())
+            .collect();
+        let groups = |groups: &[&[&str]]| {
+            Some(
+                groups
+                    .iter()
+                    .map(|g| g.iter().map(|t| t.to_string()).collect())
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            equivalences,
+            vec![
+                groups(&[&["true", "1"], &["yes", "y"]]),
+                groups(&[&["1.0", "1"]])
+            ]
+        );
+
+        let yaml = YamlLoader::load_from_str("[{ score: 1.0, equivalences: [ [] ] }]").unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        let (location, err) = located(res.unwrap_err());
+        assert_eq!(location, "tests[0]");
+        assert!(matches!(err, ConfigError::InvalidField { .. }));
+    }
+
     #[test]
     fn tests_from_yaml_missing_field() {
         let yaml = YamlLoader::load_from_str("[{ name: test }]").unwrap();
-        let res = tests_from_yaml(&yaml[0]);
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
         assert!(res.is_err());
-        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+        let (location, err) = located(res.unwrap_err());
+        assert_eq!(location, "tests[0] (name: 'test')");
+        assert!(matches!(err, ConfigError::MissingField { .. }));
+
+        // Tests without a name are located by their index only
+        let yaml = YamlLoader::load_from_str(
+            "
+- { name: first, score: 1.0 }
+- { score: 1.0, test-cases: [ { stdin: a }, { stdin: [ b ] } ] }",
+        )
+        .unwrap();
+        let err = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."))
+            .err()
+            .unwrap();
+        assert_eq!(located(err).0, "tests[1]: test-cases[1]");
     }
 
     #[test]
-    fn analyses_from_yaml_ok() {
+    fn config_pipeline() {
+        let project = tempfile::tempdir().unwrap();
+        let config = |pipeline: &str| {
+            std::fs::write(
+                project.path().join("config.yaml"),
+                format!("source: test.c\npipeline: {}", pipeline),
+            )
+            .unwrap();
+            Config::from_yaml(Path::new("config.yaml"), project.path())
+        };
+        let res = config("[ compile, analyses, tests ]");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap().pipeline,
+            Some(vec![
+                ModuleKind::Compile,
+                ModuleKind::Analyses,
+                ModuleKind::Tests
+            ])
+        );
+        let res = config("[ compile, test ]");
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn lint_output_smells() {
+        assert!(lint_output("a\nb\n", true, 1000).is_empty());
+        assert!(lint_output("a\nb", false, 1000).is_empty());
+        assert_eq!(
+            lint_output("a \nb", false, 1000),
+            vec!["trailing whitespace"]
+        );
+        assert_eq!(
+            lint_output("a \r\nb\r\n", false, 1000),
+            vec!["trailing whitespace"]
+        );
+        assert!(lint_output("a\r\nb\r\n", false, 1000).is_empty());
+        assert_eq!(
+            lint_output("a\r\nb\n", false, 1000),
+            vec!["mixed line endings"]
+        );
+        assert_eq!(lint_output("a\tb", false, 1000), vec!["tab characters"]);
+        assert_eq!(
+            lint_output("a\nb", true, 1000),
+            vec!["missing final newline"]
+        );
+        assert_eq!(
+            lint_output("abcde", false, 4),
+            vec!["5 bytes long (more than 4)"]
+        );
+        assert_eq!(
+            lint_output("a\0b", false, 1000),
+            vec!["contains NUL bytes (binary file?)"]
+        );
+    }
+
+    #[test]
+    fn tests_from_yaml_duplicate_cases() {
         let yaml = YamlLoader::load_from_str(
             "
-- analyser: no-call
-  funs: [ f1, f2]
-  penalty: -1.0
-- analyser: no-header
-  header: header.h
-  penalty: -0.5
-- analyser: no-globals
-  penalty: -2.0",
+- name: copied
+  score: 1.0
+  test-cases:
+    - args: \"1\"
+      stdin: \"a\"
+      stdout: \"b\"
+    - args: \"2\"
+      stdin: \"a\"
+      stdout: \"b\"
+    - args: '1'
+      stdin: a
+      stdout: b
+    - args: \"1\"
+      stdin: \"a\"
+      stdout: \"b \"
+    - args: \"1\"
+      stdin: \"a\"
+      stdout: \"b\"
+      case-insensitive: true
+- name: other
+  score: 1.0
+  args: \"2\"
+  stdin: \"a\"
+  stdout: \"b\"
+",
+        )
+        .unwrap();
+        let tests = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new(".")).unwrap();
+        let (within, across) = duplicate_test_cases(&tests);
+        assert_eq!(within, vec!["test 'copied', case 2 is identical to case 0"]);
+        assert_eq!(
+            across,
+            vec!["test 'other' is identical to test 'copied', case 1"]
+        );
+    }
+
+    #[test]
+    fn config_output_lints() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("out"), "a \n").unwrap();
+        std::fs::write(project.path().join("bin"), "\x7fELF\0").unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+tests:
+  - name: file
+    score: 1.0
+    stdout: <out
+  - name: lines
+    score: 1.0
+    test-cases:
+      - stdin: \"1\\n2\\n\"
+        stdout: \"3\"
+      - stdin: \"1\\n2\\n\"
+        stdout: \"*\"
+      - stdin: \"1\\n2\\n\"
+        stdout: \"\"
+  - name: binary
+    score: 1.0
+    stdout: <bin
+",
         )
         .unwrap();
-        let res = analyses_from_yaml(&yaml[0]);
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
         assert!(res.is_ok());
-        let analyses = res.unwrap();
-        assert_eq!(analyses.len(), 3);
-        assert_eq!(analyses[0].penalty(), -1.0);
-        assert_eq!(analyses[1].penalty(), -0.5);
-        assert_eq!(analyses[2].penalty(), -2.0);
+        assert_eq!(
+            res.unwrap().output_lints,
+            vec![
+                "test 'file', field 'stdout': trailing whitespace",
+                "test 'lines', case 0, field 'stdout': missing final newline",
+                "test 'lines', case 2, field 'stdout': empty although stdin is not",
+                "test 'binary', field 'stdout': contains NUL bytes (binary file?)"
+            ]
+        );
+    }
+
+    #[test]
+    fn config_fixtures() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("input"), "1 2 3").unwrap();
+        let config = |stdin: &str| {
+            std::fs::write(
+                project.path().join("config.yaml"),
+                format!(
+                    "
+source: test.c
+fixtures:
+  numbers: <input
+  generated: $(echo generated)
+tests:
+  - score: 1.0
+    test-cases:
+      - stdin: '@generated'
+      - stdin: '@generated'
+      - stdin: '{}'
+",
+                    stdin
+                ),
+            )
+            .unwrap();
+            Config::from_yaml(Path::new("config.yaml"), project.path())
+        };
+
+        let res = config("@numbers");
+        assert!(res.is_ok());
+        let config_ok = res.unwrap();
+        let fixtures: Vec<&DataFile> = config_ok.tests[0]
+            .test_cases
+            .iter()
+            .map(|tc| tc.stdin_file.as_ref().unwrap())
+            .collect();
+        assert!(config_ok.tests[0].test_cases[0].stdin.is_none());
+        // The command is run only once
+        assert_eq!(fixtures[0].path, fixtures[1].path);
+        assert_eq!(read_to_string(&fixtures[0].path).unwrap(), "generated\n");
+        assert_eq!(fixtures[2].path, project.path().join("input"));
+        assert_eq!(fixtures[2].name, "@numbers");
+
+        let res = config("@unknown");
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "test '', case 2, field 'stdin': undefined fixture 'unknown' \
+             (defined fixtures: numbers, generated)"
+        );
+
+        let fixture = |name: &str, source: &str| {
+            std::fs::write(
+                project.path().join("config.yaml"),
+                format!(
+                    "
+source: test.c
+fixtures:
+  {}: {}
+tests:
+  - score: 1.0
+    test-cases:
+      - stdin: '@{}'
+",
+                    name, source, name
+                ),
+            )
+            .unwrap();
+            Config::from_yaml(Path::new("config.yaml"), project.path())
+        };
+
+        // Output of a failed command is not used
+        let res = fixture("failed", "$(echo partial; echo error >&2; false)");
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "test '', field 'stdin': invalid command: \
+             'echo partial; echo error >&2; false' failed (exit status: 1): error"
+        );
+
+        // Generators are run in the project directory
+        std::fs::write(project.path().join("gen.sh"), "#!/bin/sh\necho generated").unwrap();
+        std::fs::set_permissions(
+            project.path().join("gen.sh"),
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+        let res = fixture("script", "$(./gen.sh)");
+        assert!(res.is_ok());
+        assert_eq!(
+            read_to_string(
+                &res.ok().unwrap().tests[0].test_cases[0]
+                    .stdin_file
+                    .as_ref()
+                    .unwrap()
+                    .path
+            )
+            .unwrap(),
+            "generated\n"
+        );
+
+        let res = fixture("../escaped", "$(echo escaped)");
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "'fixtures' has invalid value of field '../escaped' (name without '/' expected)"
+        );
+    }
+
+    #[test]
+    fn tests_from_yaml_env() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- name: locale
+  score: 1.0
+  env: { LC_ALL: C, MAX_ITEMS: 10 }
+  test-cases:
+    - stdin: a
+    - stdin: b
+      env: { MAX_ITEMS: 20, DEBUG: '' }
+- name: single
+  score: 1.0
+  env: { LC_ALL: cs_CZ.UTF-8 }",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let tests = res.unwrap();
+        let env = |test: usize, case: usize| {
+            tests[test].test_cases[case]
+                .env
+                .iter()
+                .map(|(var, value)| format!("{}={}", var, value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(env(0, 0), vec!["LC_ALL=C", "MAX_ITEMS=10"]);
+        assert_eq!(env(0, 1), vec!["LC_ALL=C", "MAX_ITEMS=20", "DEBUG="]);
+        assert_eq!(env(1, 0), vec!["LC_ALL=cs_CZ.UTF-8"]);
+
+        let yaml = YamlLoader::load_from_str(
+            "[{ name: locale, score: 1.0, test-cases: [ { env: { LC_ALL: [ C ] } } ] }]",
+        );
+        let err = tests_from_yaml(&yaml.unwrap()[0], &Yaml::BadValue, Path::new("."));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "tests[0] (name: 'locale'): test-cases[0]: 'locale' has invalid value of field \
+             'env.LC_ALL' (string expected)"
+        );
+        let yaml = YamlLoader::load_from_str("[{ score: 1.0, env: [ LC_ALL ] }]");
+        let err = tests_from_yaml(&yaml.unwrap()[0], &Yaml::BadValue, Path::new("."));
+        assert!(matches!(
+            located(err.unwrap_err()).1,
+            ConfigError::InvalidField { .. }
+        ));
+    }
+
+    #[test]
+    fn tests_from_yaml_timeout() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- name: io
+  score: 1.0
+  timeout: 200
+  test-cases:
+    - stdin: small
+    - stdin: big
+      timeout: 30000
+- name: default
+  score: 1.0
+- name: single
+  score: 1.0
+  timeout: 500",
+        )
+        .unwrap();
+        let res = tests_from_yaml(&yaml[0], &Yaml::BadValue, Path::new("."));
+        assert!(res.is_ok());
+        let tests = res.unwrap();
+        let timeouts: Vec<Option<u64>> = tests
+            .iter()
+            .flat_map(|t| t.test_cases.iter().map(|c| c.timeout))
+            .collect();
+        assert_eq!(timeouts, vec![Some(200), Some(30000), None, Some(500)]);
+
+        for timeout in ["0", "-100", "~", "slow"] {
+            let yaml = YamlLoader::load_from_str(&format!(
+                "[{{ score: 1.0, test-cases: [ {{ timeout: {} }} ] }}]",
+                timeout
+            ));
+            let err = tests_from_yaml(&yaml.unwrap()[0], &Yaml::BadValue, Path::new("."));
+            assert!(matches!(
+                located(err.unwrap_err()).1,
+                ConfigError::InvalidField { .. }
+            ));
+            let yaml =
+                YamlLoader::load_from_str(&format!("[{{ score: 1.0, timeout: {} }}]", timeout));
+            let err = tests_from_yaml(&yaml.unwrap()[0], &Yaml::BadValue, Path::new("."));
+            assert!(matches!(
+                located(err.unwrap_err()).1,
+                ConfigError::InvalidField { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn tests_from_yaml_sha256() {
+        let digest = "5891B5B522D5DF086D0FF0B110FBD9D21BB4FC7163AF34D08286A2E846F6BE03";
+        let parse = |fields: &str| {
+            let yaml = YamlLoader::load_from_str(&format!("[{{ score: 1.0, {} }}]", fields));
+            tests_from_yaml(&yaml.unwrap()[0], &Yaml::BadValue, Path::new("."))
+                .map_err(|e| located(e).1)
+        };
+
+        let res = parse(&format!("compare: sha256, stdout-sha256: {}", digest));
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap()[0].test_cases[0].stdout_sha256,
+            Some(digest.to_lowercase())
+        );
+
+        let err = parse("compare: sha256, stdout-sha256: 5891b5b5");
+        assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
+        let err = parse("compare: md5, stdout-sha256: 5891b5b5");
+        assert!(matches!(err.unwrap_err(), ConfigError::InvalidField { .. }));
+        let err = parse("compare: sha256");
+        assert!(matches!(err.unwrap_err(), ConfigError::MissingField { .. }));
+        let err = parse(&format!("stdout-sha256: {}", digest));
+        assert!(matches!(err.unwrap_err(), ConfigError::MissingField { .. }));
+        let err = parse(&format!(
+            "compare: sha256, stdout-sha256: {}, case-insensitive: true",
+            digest
+        ));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "'' cannot combine field 'compare' with 'case-insensitive'"
+        );
+        let err = parse(&format!(
+            "compare: sha256, stdout-sha256: {}, stdout: hello",
+            digest
+        ));
+        assert!(matches!(
+            err.unwrap_err(),
+            ConfigError::IncompatibleFields { .. }
+        ));
+    }
+
+    #[test]
+    fn config_streamed_files() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("in"), "input\n").unwrap();
+        std::fs::write(project.path().join("small"), "output\n").unwrap();
+        let large = "output\n".repeat(STREAMED_OUTPUT_BYTES as usize);
+        std::fs::write(project.path().join("large"), large).unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+tests:
+  - score: 1.0
+    test-cases:
+      - stdin: <in
+        stdout: <small
+      - stdin: <in
+        stdout: <large
+      - stdin: <in
+        stdout: <large
+        case-insensitive: true",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let config = res.unwrap();
+        let cases = &config.tests[0].test_cases;
+        for case in cases {
+            assert!(case.stdin.is_none());
+            assert_eq!(case.stdin_file.as_ref().unwrap().name, "<in");
+            assert_eq!(
+                case.stdin_file.as_ref().unwrap().path,
+                project.path().join("in")
+            );
+        }
+        assert_eq!(cases[0].stdout.as_deref(), Some("output\n"));
+        assert!(cases[0].stdout_file.is_none());
+        // Only large outputs which are not normalized are streamed
+        assert!(cases[1].stdout.is_none());
+        assert_eq!(
+            cases[1].stdout_file.as_ref().unwrap().path,
+            project.path().join("large")
+        );
+        assert!(cases[2].stdout.is_some());
+        assert!(cases[2].stdout_file.is_none());
+    }
+
+    #[test]
+    fn config_stderr_files() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("err"), "error: invalid input\n").unwrap();
+        let config = |stderr: &str| {
+            std::fs::write(
+                project.path().join("config.yaml"),
+                format!(
+                    "
+source: test.c
+tests:
+  - score: 1.0
+    test-cases:
+      - stderr: {}",
+                    stderr
+                ),
+            )
+            .unwrap();
+            Config::from_yaml(Path::new("config.yaml"), project.path())
+        };
+        let res = config("<err");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap().tests[0].test_cases[0].stderr.as_deref(),
+            Some("error: invalid input\n")
+        );
+        // Only strings starting with '<' are file references
+        let res = config("'usage: prog <file>'");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap().tests[0].test_cases[0].stderr.as_deref(),
+            Some("usage: prog <file>")
+        );
+        let res = config("<missing");
+        assert!(matches!(res, Err(ConfigError::MissingFiles { .. })));
+    }
+
+    #[test]
+    fn substitute_vars_ok() {
+        let vars = vec![
+            ("dir".to_string(), "inputs/2024".to_string()),
+            ("n".to_string(), "5".to_string()),
+        ];
+        let res = substitute_vars("<${dir}/case${n}.txt", &vars);
+        assert_eq!(res.unwrap(), "<inputs/2024/case5.txt");
+        let res = substitute_vars("$${dir} ${n} $n ${", &vars);
+        assert_eq!(res.unwrap(), "${dir} 5 $n ${");
+        let res = substitute_vars("${data}", &vars);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "undefined variable 'data' (defined variables: dir, n)"
+        );
+    }
+
+    #[test]
+    fn config_vars() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir(project.path().join("2024")).unwrap();
+        std::fs::write(project.path().join("2024").join("in"), "input").unwrap();
+        let config = |stdout: &str| {
+            format!(
+                "
+source: test.c
+vars: {{ data: \"2024\", flag: -v }}
+scripts: [ \"${{data}}/check.sh\" ]
+tests:
+  - name: vars
+    score: 1.0
+    args: \"${{flag}} -n\"
+    stdin: <${{data}}/in
+    stdout: {}
+",
+                stdout
+            )
+        };
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(&config_file, config("${flag}")).unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let config_ok = res.unwrap();
+        let case = &config_ok.tests[0].test_cases[0];
+        assert_eq!(case.args, vec!["-v", "-n"]);
+        let stdin = case.stdin_file.as_ref().unwrap();
+        assert_eq!(stdin.name, "<2024/in");
+        assert_eq!(case.stdout.as_deref(), Some("-v"));
+        assert_eq!(
+            config_ok.scripts,
+            vec![project.path().join("2024").join("check.sh")]
+        );
+
+        std::fs::write(&config_file, config("${missing}")).unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "test 'vars', field 'stdout': undefined variable 'missing' \
+             (defined variables: data, flag)"
+        );
+    }
+
+    #[test]
+    fn config_env_expansion() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("seed.txt"), "42\n").unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+vars: { mode: fast }
+tests:
+  - name: env
+    score: 1.0
+    env:
+      SEED: <seed.txt
+      ITEMS: $(echo 10)
+      MODE: ${mode}
+      TEXT: \"a\\n\"",
+        )
+        .unwrap();
+        let config = Config::from_yaml(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(
+            config.tests[0].test_cases[0].env,
+            vec![
+                ("SEED".to_string(), "42".to_string()),
+                ("ITEMS".to_string(), "10".to_string()),
+                ("MODE".to_string(), "fast".to_string()),
+                // Only generated values are trimmed
+                ("TEXT".to_string(), "a\n".to_string()),
+            ]
+        );
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+tests: [ { name: env, score: 1.0, env: { SEED: <missing.txt } } ]",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::from_yaml(Path::new("config.yaml"), project.path()),
+            Err(ConfigError::MissingFiles { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_merge_keys_ok() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- &a { x: 1, y: 2 }
+- &b { y: 3, z: 4 }
+- { <<: *a, x: 5 }
+- { x: 5, <<: [ *b, *a ] }
+- [ { <<: { w: 0 } } ]",
+        )
+        .unwrap();
+        let res = resolve_merge_keys(yaml[0].clone());
+        assert!(res.is_ok());
+        let expected = YamlLoader::load_from_str(
+            "
+- { x: 1, y: 2 }
+- { y: 3, z: 4 }
+- { y: 2, x: 5 }
+- { x: 5, y: 3, z: 4 }
+- [ { w: 0 } ]",
+        )
+        .unwrap();
+        assert_eq!(res.unwrap(), expected[0]);
+
+        let yaml = YamlLoader::load_from_str("{ <<: 1 }").unwrap();
+        let res = resolve_merge_keys(yaml[0].clone());
+        assert!(matches!(res, Err(ConfigError::InvalidOption { .. })));
+    }
+
+    #[test]
+    fn config_merge_keys() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+compiler:
+  <<: &compiler { CC: gcc, CFLAGS: -O2 }
+  CC: clang
+analyses:
+  - &globals { analyser: no-globals, penalty: -1.0 }
+  - { <<: *globals, penalty: -2.0 }
+tests:
+  - name: cases
+    score: 1.0
+    test-cases:
+      - &case { args: -v, stdin: input, stdout: output }
+      - { <<: *case, args: -q }
+  - { <<: *case, name: single, score: 2.0, case-insensitive: true }
+",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let config = res.unwrap();
+        assert_eq!(config.compiler.as_deref(), Some("clang"));
+        assert_eq!(config.c_flags.as_deref(), Some("-O2"));
+        assert_eq!(config.analyses.len(), 2);
+        assert_eq!(config.analyses[0].penalty(), -1.0);
+        assert_eq!(config.analyses[1].name(), "no-globals");
+        assert_eq!(config.analyses[1].penalty(), -2.0);
+
+        let cases = &config.tests[0].test_cases;
+        assert_eq!(cases[1].args, vec!["-q"]);
+        assert_eq!(cases[1].stdin.as_deref(), Some("input"));
+        assert_eq!(cases[1].stdout.as_deref(), Some("output"));
+        let single = &config.tests[1];
+        assert_eq!(single.name, "single");
+        assert_eq!(single.score, 2.0);
+        assert_eq!(single.test_cases[0].args, vec!["-v"]);
+        assert_eq!(single.test_cases[0].stdout.as_deref(), Some("output"));
+        assert!(single.test_cases[0].case_insensitive);
+    }
+
+    #[test]
+    fn config_toml() {
+        let project = tempfile::tempdir().unwrap();
+        let write = |file: &str, contents: &str| {
+            std::fs::write(project.path().join(file), contents).unwrap();
+        };
+        write(
+            "config.toml",
+            r#"
+source = "test.c"
+include = [ "common.yaml" ]
+scripts = [ "check.sh" ]
+
+[compiler]
+CC = "clang"
+
+[[analyses]]
+analyser = "no-goto"
+penalty = -0.5
+
+[[tests]]
+name = "single"
+score = 1.0
+args = "-v"
+stdout = """
+ok
+"""
+
+[[tests]]
+name = "cases"
+score = 2.0
+test-cases = [
+    { stdin = "a", stdout = "b" },
+    { stdin = "c", stdout = "d", exit-code = 1 },
+]
+"#,
+        );
+        write(
+            "common.yaml",
+            "
+tests:
+  - { name: common, score: 1.0, stdout: ok }",
+        );
+        let res = Config::from_file(Path::new("config.toml"), project.path());
+        assert!(res.is_ok());
+        let config = res.unwrap();
+        assert_eq!(config.src_file, "test.c");
+        assert_eq!(config.compiler.as_deref(), Some("clang"));
+        assert_eq!(config.scripts, vec![project.path().join("check.sh")]);
+        assert_eq!(config.analyses.len(), 1);
+        assert_eq!(config.analyses[0].name(), "no-goto");
+        let names: Vec<&str> = config.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["common", "single", "cases"]);
+        assert_eq!(
+            config.tests[1].test_cases[0].stdout.as_deref(),
+            Some("ok\n")
+        );
+        assert_eq!(config.tests[2].test_cases[1].exit_code, Some(vec![1]));
+
+        // Errors carry the same options and fields as for YAML
+        write(
+            "config.toml",
+            "source = \"test.c\"\n[[tests]]\nname = \"a\"",
+        );
+        write("config.yaml", "source: test.c\ntests:\n  - name: a");
+        for file in ["config.toml", "config.yaml"] {
+            let err = Config::from_file(Path::new(file), project.path()).err();
+            let (location, err) = located(err.unwrap());
+            assert_eq!(location, "tests[0] (name: 'a')");
+            assert!(matches!(
+                err,
+                ConfigError::MissingField { option, field } if option == "a" && field == "score"
+            ));
+        }
+
+        write("config.toml", "source = \"test.c\"\nsource = \"main.c\"");
+        let res = Config::from_file(Path::new("config.toml"), project.path());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "TOML parsing error at line 2: duplicate key 'source'"
+        );
+    }
+
+    #[test]
+    fn config_validate() {
+        let project = tempfile::tempdir().unwrap();
+        let write = |file: &str, contents: &str| {
+            std::fs::write(project.path().join(file), contents).unwrap();
+        };
+        write("check.sh", "#!/bin/sh");
+        write("out", "2\n");
+        write(
+            "config.yaml",
+            "
+source: main.c
+tests:
+  - { name: single, score: 1.5, stdout: <out, timout: 10 }
+  - name: cases
+    score: 2.0
+    test-cases: [ { stdin: a }, { stdin: a } ]
+analyses:
+  - { analyser: no-goto, penalty: -1.0 }
+  - { analyser: no-gotos, penalty: -1.0 }
+coverage: { score: 0.5 }
+scripts: [ check.sh ]
+test-conf: { timeout: 10 }",
+        );
+        let res = Config::validate(Path::new("config.yaml"), project.path());
+        assert_eq!(
+            res.unwrap(),
+            ConfigReport {
+                source: "main.c".to_string(),
+                tests: 2,
+                test_cases: 3,
+                max_score: 4.0,
+                analyses: vec!["no-goto".to_string()],
+                scripts: vec![PathBuf::from("check.sh")],
+                unknown_options: vec![
+                    "tests[0] (name: 'single'): unsupported option 'timout'".to_string(),
+                    "unsupported option 'test-conf'".to_string(),
+                    "analyses: unsupported option 'no-gotos'".to_string(),
+                ],
+                warnings: vec!["duplicate test case: test 'cases', case 1 is identical to \
+                                case 0"
+                    .to_string()],
+            }
+        );
+
+        // Nothing is reported for a correct configuration
+        write("config.yaml", "source: main.c");
+        let report = Config::validate(Path::new("config.yaml"), project.path()).unwrap();
+        assert!(report.unknown_options.is_empty());
+
+        write(
+            "config.yaml",
+            "
+source: main.c
+tests: [ { score: 1.0, stdout: <missing } ]
+scripts: [ check.sh, missing.sh ]",
+        );
+        let res = Config::validate(Path::new("config.yaml"), project.path());
+        assert!(matches!(res, Err(ConfigError::MissingFiles { .. })));
+        write(
+            "config.yaml",
+            "
+source: main.c
+test-config: { runner: qemu-atst-missing -L /usr/arm-linux-gnueabi }",
+        );
+        let res = Config::validate(Path::new("config.yaml"), project.path());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "files referenced by the configuration cannot be read:\n  runner: \
+             qemu-atst-missing: not found"
+        );
+        write(
+            "config.yaml",
+            "source: main.c\ntest-config: { runner: env }",
+        );
+        assert!(Config::validate(Path::new("config.yaml"), project.path()).is_ok());
+        write(
+            "config.yaml",
+            "
+source: main.c
+scripts: [ check.sh, missing.sh ]",
+        );
+        let res = Config::validate(Path::new("config.yaml"), project.path());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!(
+                "files referenced by the configuration cannot be read:\n  script: {}: not found",
+                project.path().join("missing.sh").display()
+            )
+        );
+    }
+
+    #[test]
+    fn config_solution_override() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: main.c
+tests: [ { name: a, score: 1.0 }, { name: b, score: 1.0 } ]",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        let solution = project.path().join("xlogin00");
+        std::fs::create_dir(&solution).unwrap();
+        assert_eq!(config.solution_override(&solution).unwrap(), None);
+
+        let write = |contents: &str| {
+            std::fs::write(solution.join(OVERRIDE_FILE), contents).unwrap();
+            config.solution_override(&solution)
+        };
+        assert_eq!(
+            write("{ timeout: 10000, source: alt.c, exclude-tests: [ b ] }").unwrap(),
+            Some(SolutionOverride {
+                timeout: Some(10000),
+                src_file: Some("alt.c".to_string()),
+                excluded_tests: vec!["b".to_string()],
+            })
+        );
+        assert_eq!(write("").unwrap(), Some(SolutionOverride::default()));
+        for field in [
+            "scripts: [ bonus.sh ]",
+            "coverage: { score: 5.0 }",
+            "score: 1.0",
+        ] {
+            assert!(matches!(
+                write(&format!("timeout: 100\n{}", field)),
+                Err(ConfigError::ForbiddenOverride { .. })
+            ));
+        }
+        assert_eq!(
+            write("exclude-tests: [ c ]").err().unwrap().to_string(),
+            "there is no test 'c' to exclude"
+        );
+    }
+
+    #[test]
+    fn config_strict() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        let yaml = "
+source: test.c
+sed: 42
+tests:
+  - name: typo
+    score: 1.0
+    stdou: output";
+        std::fs::write(&config_file, yaml).unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.unknown_options.len(), 2);
+        // Library callers can force strict loading
+        assert!(matches!(
+            Config::from_file_strict(Path::new("config.yaml"), project.path()).err(),
+            Some(ConfigError::UnknownOptions { .. })
+        ));
+        std::fs::write(
+            &config_file,
+            "source: test.c\ntests: [{ score: 1, stdout: a }]",
+        )
+        .unwrap();
+        assert!(Config::from_file_strict(Path::new("config.yaml"), project.path()).is_ok());
+        std::fs::write(
+            &config_file,
+            "source: test.c\ntests: [{ score: 1, test-cases: [{ stdin: a }, { stdin: a }] }]",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::from_file_strict(Path::new("config.yaml"), project.path()).err(),
+            Some(ConfigError::DuplicateTestCases { .. })
+        ));
+
+        std::fs::write(&config_file, format!("strict: true\n{}", yaml)).unwrap();
+        // All unsupported options are reported at once, with their locations
+        match Config::from_file(Path::new("config.yaml"), project.path()) {
+            Err(ConfigError::UnknownOptions { options }) => assert_eq!(
+                options,
+                vec![
+                    UnknownOption {
+                        option: "sed".to_string(),
+                        context: String::new(),
+                    },
+                    UnknownOption {
+                        option: "stdou".to_string(),
+                        context: "tests[0] (name: 'typo')".to_string(),
+                    },
+                ]
+            ),
+            _ => panic!("unsupported options not reported"),
+        }
+
+        // Options of test cases are located in their tests
+        std::fs::write(
+            &config_file,
+            "
+strict: true
+source: test.c
+compiler: { CC: gcc, CFLAG: -O2 }
+tests:
+  - { score: 1, stdout: a }
+  - score: 1
+    test-cases:
+      - { stdout: a }
+      - { stdout: b, timout: 10 }",
+        )
+        .unwrap();
+        assert_eq!(
+            Config::from_file(Path::new("config.yaml"), project.path())
+                .err()
+                .unwrap()
+                .to_string(),
+            "unsupported options:\n  compiler: unsupported option 'CFLAG'\n  \
+             tests[1], test-cases[1]: unsupported option 'timout'"
+        );
+    }
+
+    #[test]
+    fn config_penalty_tests() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        let yaml = "
+source: test.c
+tests:
+  - { name: output, score: 2, stdout: a }
+  - { name: no stderr, score: -0.5, stderr: \"\" }";
+        std::fs::write(&config_file, yaml).unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.tests[1].score, -0.5);
+        assert!(!config.clamp_score);
+        // Penalty tests do not count to the maximal score
+        assert_eq!(config.report().max_score, 2.0);
+
+        std::fs::write(&config_file, format!("clamp-score: true\n{}", yaml)).unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert!(config.clamp_score);
+    }
+
+    #[test]
+    fn config_seed() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        let config = |seed: &str| {
+            std::fs::write(&config_file, format!("source: test.c\nseed: {}", seed)).unwrap();
+            Config::from_file(Path::new("config.yaml"), project.path())
+        };
+        assert_eq!(config("42").ok().unwrap().seed, Some(42));
+        assert_eq!(config("0").ok().unwrap().seed, Some(0));
+        assert!(matches!(
+            config("-1").err().unwrap(),
+            ConfigError::InvalidField { .. }
+        ));
+    }
+
+    #[test]
+    fn config_analyses_config() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(&config_file, "source: test.c").unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(
+            config.analyses_config.max_source_bytes,
+            crate::DEFAULT_MAX_SOURCE_BYTES
+        );
+        assert_eq!(config.analyses_config.oversize_penalty, None);
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+analyses-config: { max-source-bytes: 100000, penalty: -1.0 }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.analyses_config.max_source_bytes, 100000);
+        assert_eq!(config.analyses_config.oversize_penalty, Some(-1.0));
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+analyses-config: { max-source-bytes: large }",
+        )
+        .unwrap();
+        assert!(Config::from_file(Path::new("config.yaml"), project.path()).is_err());
+    }
+
+    #[test]
+    fn config_integer_scores() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+tests:
+  - { name: integer, score: 2, stdout: a }
+  - { name: float, score: 1.5, stdout: b }
+analyses:
+  - { analyser: no-call, funs: [ system ], penalty: -1 }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.tests[0].score, 2.0);
+        assert_eq!(config.tests[1].score, 1.5);
+        assert_eq!(config.analyses[0].penalty(), -1.0);
+    }
+
+    #[test]
+    fn config_programs() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(
+            &config_file,
+            "
+source: main.c
+tests:
+  - { name: encode, score: 1.0, program: encode, stdin: a, stdout: b }
+  - { name: round trip, score: 1.0, pipeline: [ encode, decode ], stdin: a, stdout: a }
+  - { name: main, score: 1.0, stdin: a, stdout: a }
+programs:
+  encode: { sources: [ encode.c, common.c ], CFLAGS: -DENCODE }
+  decode: { sources: [ decode.c, common.c ], LDFLAGS: -lm }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(
+            config.programs,
+            vec![
+                Program {
+                    name: "encode".to_string(),
+                    sources: vec!["encode.c".to_string(), "common.c".to_string()],
+                    c_flags: Some("-DENCODE".to_string()),
+                    ld_flags: None,
+                },
+                Program {
+                    name: "decode".to_string(),
+                    sources: vec!["decode.c".to_string(), "common.c".to_string()],
+                    c_flags: None,
+                    ld_flags: Some("-lm".to_string()),
+                },
+            ]
+        );
+        assert_eq!(config.tests[0].programs, vec!["encode"]);
+        assert_eq!(config.tests[1].programs, vec!["encode", "decode"]);
+        assert!(config.tests[2].programs.is_empty());
+
+        let error = |config: &str| {
+            std::fs::write(&config_file, format!("source: main.c\n{}", config)).unwrap();
+            Config::from_file(Path::new("config.yaml"), project.path())
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error(
+                "
+programs: { encode: { sources: [ encode.c ] } }
+tests: [ { name: t, score: 1.0, pipeline: [ encode, decode ] } ]"
+            ),
+            "tests[0] (name: 't'): undefined program 'decode' (defined programs: encode)"
+        );
+        assert_eq!(
+            error("tests: [ { name: t, score: 1.0, program: a, pipeline: [ a ] } ]"),
+            "tests[0] (name: 't'): 't' cannot combine field 'pipeline' with 'program'"
+        );
+        assert!(error("tests: [ { name: t, score: 1.0, pipeline: [] } ]").contains("non-empty"));
+        assert_eq!(
+            error("programs: { ../encode: { sources: [ encode.c ] } }"),
+            "'programs' has invalid value (file names of programs expected)"
+        );
+        assert_eq!(
+            error("programs: { encode: { CFLAGS: -O2 } }"),
+            "'encode' is missing a mandatory field 'sources'"
+        );
+    }
+
+    #[test]
+    fn config_binary_limits() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(&config_file, "source: test.c").unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.binary_limits.max_bytes, None);
+        assert!(!config.binary_limits.require_static);
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+compiler: { max-binary-bytes: 65536, require-static: true, binary-penalty: -1.0 }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.binary_limits.max_bytes, Some(65536));
+        assert!(config.binary_limits.require_static);
+        assert_eq!(config.binary_limits.penalty, Some(-1.0));
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+compiler: { max-binary-bytes: 64k }",
+        )
+        .unwrap();
+        assert!(Config::from_file(Path::new("config.yaml"), project.path()).is_err());
+    }
+
+    #[test]
+    fn config_warnings_penalty() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(&config_file, "source: test.c").unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.warnings_penalty.penalty, -0.5);
+        assert!(!config.warnings_penalty.per_warning);
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+compiler: { CFLAGS: -Wall, warnings-penalty: -0.1, per-warning: true }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.warnings_penalty.penalty, -0.1);
+        assert!(config.warnings_penalty.per_warning);
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+compiler: { warnings-penalty: none }",
+        )
+        .unwrap();
+        assert!(Config::from_file(Path::new("config.yaml"), project.path()).is_err());
+    }
+
+    #[test]
+    fn config_runner() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        let load = |test_config: &str| {
+            std::fs::write(
+                &config_file,
+                format!("source: test.c\ntest-config: {}", test_config),
+            )
+            .unwrap();
+            Config::from_file(Path::new("config.yaml"), project.path())
+        };
+        let config = load("{ exec-prefix: qemu-arm -L /usr/arm-linux-gnueabi }").unwrap();
+        assert_eq!(
+            config.test_config.exec_prefix,
+            vec!["qemu-arm", "-L", "/usr/arm-linux-gnueabi"]
+        );
+        let config = load("{ runner: \"qemu-arm -L '/opt/arm sysroot'\" }").unwrap();
+        assert_eq!(
+            config.test_config.exec_prefix,
+            vec!["qemu-arm", "-L", "/opt/arm sysroot"]
+        );
+
+        assert!(matches!(
+            load("{ runner: qemu-arm, exec-prefix: qemu-arm }")
+                .err()
+                .unwrap(),
+            ConfigError::IncompatibleFields { .. }
+        ));
+        assert!(matches!(
+            load("{ runner: \"qemu-arm -L '/opt\" }").err().unwrap(),
+            ConfigError::InvalidField { field, .. } if field == "runner"
+        ));
+    }
+
+    #[test]
+    fn config_test_order() {
+        let project = tempfile::tempdir().unwrap();
+        let config_file = project.path().join("config.yaml");
+        std::fs::write(&config_file, "source: test.c").unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.test_config.order, TestOrder::Config);
+
+        std::fs::write(
+            &config_file,
+            "
+source: test.c
+test-config: { order: by-cost }
+tests:
+  - { name: stress, score: 1.0, cost-hint: 30000 }
+  - { name: smoke, score: 1.0 }",
+        )
+        .unwrap();
+        let config = Config::from_file(Path::new("config.yaml"), project.path()).unwrap();
+        assert_eq!(config.test_config.order, TestOrder::ByCost);
+        assert_eq!(config.tests[0].cost_hint, Some(30000.0));
+        assert_eq!(config.tests[1].cost_hint, None);
+
+        for invalid in [
+            "test-config: { order: random }",
+            "tests: [ { score: 1.0, cost-hint: -1 } ]",
+            "tests: [ { score: 1.0, cost-hint: cheap } ]",
+        ] {
+            std::fs::write(&config_file, format!("source: test.c\n{}", invalid)).unwrap();
+            let err = Config::from_file(Path::new("config.yaml"), project.path())
+                .err()
+                .unwrap();
+            assert!(matches!(located(err).1, ConfigError::InvalidField { .. }));
+        }
+    }
+
+    #[test]
+    fn config_include() {
+        let project = tempfile::tempdir().unwrap();
+        let write = |file: &str, contents: &str| {
+            std::fs::write(project.path().join(file), contents).unwrap();
+        };
+        write(
+            "common.yaml",
+            "
+compiler: { CC: gcc, CFLAGS: -O2 }
+test-config: { timeout: 100, max-diff-lines: 5 }
+analyses:
+  - analyser: no-globals
+    penalty: -1.0
+tests:
+  - { name: common, score: 1.0, stdout: ok }",
+        );
+        write(
+            "extra.yaml",
+            "
+include: [ common.yaml ]
+tests:
+  - { name: extra, score: 1.0, stdout: ok }",
+        );
+        write(
+            "config.yaml",
+            "
+include: [ common.yaml, extra.yaml ]
+source: test.c
+compiler: { CC: clang }
+test-config: { timeout: 200 }
+tests:
+  - { name: own, score: 1.0, stdout: ok }",
+        );
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let config = res.unwrap();
+        let names: Vec<&str> = config.tests.iter().map(|t| t.name.as_str()).collect();
+        // The same file may be included multiple times (but not circularly)
+        assert_eq!(names, vec!["common", "common", "extra", "own"]);
+        assert_eq!(config.analyses.len(), 2);
+        assert_eq!(config.compiler.as_deref(), Some("clang"));
+        assert_eq!(config.c_flags.as_deref(), Some("-O2"));
+        assert_eq!(config.test_config.timeout, 200);
+        assert_eq!(config.test_config.max_diff_lines, 5);
+
+        write("common.yaml", "include: [ extra.yaml ]");
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(matches!(res, Err(ConfigError::CircularInclude { .. })));
+        let files = match res {
+            Err(ConfigError::CircularInclude { files }) => files,
+            _ => vec![],
+        };
+        let names: Vec<&str> = files
+            .iter()
+            .map(|f| Path::new(f).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["config.yaml", "common.yaml", "extra.yaml", "common.yaml"]
+        );
+
+        write("common.yaml", "include: common.yaml");
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn config_missing_files() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("in"), "input").unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+tests:
+  - name: first
+    score: 1.0
+    stdin: <in
+    stdout: <out1
+  - name: second
+    score: 1.0
+    stdin: <in2
+    stdout: <in
+  - name: third
+    score: 1.0
+    test-cases:
+      - stdout: <in
+      - stderr: <err",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert_eq!(res.as_ref().err().unwrap().kind(), ErrorKind::MissingFiles);
+        let path = project.path().display();
+        match res {
+            Err(ConfigError::MissingFiles { files }) => {
+                assert_eq!(files.len(), 3);
+                assert!(
+                    files[0].starts_with(&format!("test 'first', field 'stdout': {}/out1: ", path))
+                );
+                assert!(
+                    files[1].starts_with(&format!("test 'second', field 'stdin': {}/in2: ", path))
+                );
+                assert!(files[2].starts_with(&format!(
+                    "test 'third', case 1, field 'stderr': {}/err: ",
+                    path
+                )));
+            }
+            _ => panic!("expected missing files error"),
+        }
+    }
+
+    #[test]
+    fn config_output_commands() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("expected"), "<not a file\n").unwrap();
+        let config = |output: &str| {
+            std::fs::write(
+                project.path().join("config.yaml"),
+                format!(
+                    "
+source: test.c
+tests:
+  - name: generated
+    score: 1.0
+    test-cases:
+      - stdout: $(cat expected)
+        stderr: $(echo error)
+      - {}",
+                    output
+                ),
+            )
+            .unwrap();
+            Config::from_yaml(Path::new("config.yaml"), project.path())
+        };
+        // Commands are run in the project directory, their output is not a file reference
+        let res = config("stdout: ok");
+        assert!(res.is_ok());
+        let config_ok = res.unwrap();
+        let case = &config_ok.tests[0].test_cases[0];
+        assert_eq!(case.stdout.as_deref(), Some("<not a file\n"));
+        assert!(case.stdout_file.is_none());
+        assert_eq!(case.stderr.as_deref(), Some("error\n"));
+
+        let res = config("stderr: $(cat missing)");
+        assert!(matches!(res, Err(ConfigError::InTestCase { .. })));
+        assert!(res.err().unwrap().to_string().starts_with(
+            "test 'generated', case 1, field 'stderr': invalid command: 'cat missing' failed (exit status: 1)"
+        ));
+        let res = config("stdout: $(echo");
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "test 'generated', case 1, field 'stdout': invalid command: missing trailing ')'"
+        );
+    }
+
+    #[test]
+    fn config_invalid_command() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+tests:
+  - name: cmd
+    score: 1.0
+    test-cases:
+      - stdin: input
+      - stdin: $(echo",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(matches!(res, Err(ConfigError::InTestCase { .. })));
+        assert_eq!(res.as_ref().err().unwrap().kind(), ErrorKind::Config);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "test 'cmd', case 1, field 'stdin': invalid command: missing trailing ')'"
+        );
+    }
+
+    #[test]
+    fn late_penalty_from_yaml_ok() {
+        let yaml = YamlLoader::load_from_str(
+            "
+deadline: 2024-01-31 23:59
+curve: exponential
+per-day: 0.1",
+        )
+        .unwrap();
+        let res = late_penalty_from_yaml(&yaml[0]);
+        assert!(res.is_ok());
+        let late_penalty = res.unwrap();
+        assert_eq!(late_penalty.deadline, 1706745540);
+        assert!(matches!(late_penalty.curve, PenaltyCurve::Exponential));
+        let timestamp_file = Path::new("submitted-at");
+        assert!(matches!(late_penalty.timestamp, TimestampSource::File(f) if f == timestamp_file));
+        assert_eq!(late_penalty.per_day, 0.1);
+    }
+
+    #[test]
+    fn late_penalty_from_yaml_invalid() {
+        let yaml = YamlLoader::load_from_str("{ deadline: tomorrow, per-day: 0.1 }").unwrap();
+        let res = late_penalty_from_yaml(&yaml[0]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_ok() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: no-call
+  funs: [ f1, f2]
+  penalty: -1.0
+- analyser: no-header
+  header: header.h
+  penalty: -0.5
+- analyser: no-globals
+  penalty: -2.0
+- analyser: no-float-eq
+  penalty: -0.5
+  max-penalty: -1.5",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 4);
+        assert_eq!(analyses[0].penalty(), -1.0);
+        assert_eq!(analyses[1].penalty(), -0.5);
+        assert_eq!(analyses[2].penalty(), -2.0);
+        assert_eq!(analyses[3].penalty(), -0.5);
+    }
+
+    struct CustomAnalyser {
+        penalty: f64,
+    }
+
+    impl Analyser for CustomAnalyser {
+        fn name(&self) -> &str {
+            "custom"
+        }
+
+        fn analyse(&self, _solution: &crate::Solution) -> Result<Vec<Finding>, crate::AtstError> {
+            Ok(vec![])
+        }
+
+        fn penalty(&self) -> f64 {
+            self.penalty
+        }
+    }
+
+    #[test]
+    fn analyses_from_yaml_max_function_length() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: max-function-length
+  limit: 50
+  penalty: -0.5
+  per-function: true",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses[0].name(), "max-function-length");
+        let finding = Finding::new(None, String::new());
+        assert_eq!(analyses[0].penalty_for(&[finding.clone(), finding]), -1.0);
+
+        let yaml = YamlLoader::load_from_str("[{ analyser: max-function-length, penalty: -1.0 }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+        let yaml = YamlLoader::load_from_str(
+            "[{ analyser: max-function-length, limit: 0, penalty: -1.0 }]",
+        );
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_no_goto() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- { analyser: no-goto, penalty: -0.5, max-allowed: 0 }
+- { analyser: no-goto, penalty: -1.0 }",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses[0].name(), "no-goto");
+        assert_eq!(analyses[0].penalty(), -0.5);
+        assert_eq!(analyses[1].penalty(), -1.0);
+
+        let yaml =
+            YamlLoader::load_from_str("[{ analyser: no-goto, penalty: -1.0, max-allowed: -1 }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_no_recursion() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: no-recursion
+  penalty: -1.0
+- analyser: no-recursion
+  penalty: -1.0
+  except: [ gcd ]",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].name(), "no-recursion");
+
+        let penalty = |analyser: &dyn Analyser, src: &str| {
+            let solution = crate::test_utils::get_solution(src, false);
+            analyser.penalty_for(&analyser.analyse(&solution).unwrap())
+        };
+        let recursive = "int gcd(int a, int b) { return b == 0 ? a : gcd(b, a % b); }
+                         int main() { return gcd(4, 6); }";
+        let iterative = "int gcd(int a, int b) {
+                             while (b != 0) { int t = b; b = a % b; a = t; }
+                             return a;
+                         }
+                         int main() { return gcd(4, 6); }";
+        assert_eq!(penalty(analyses[0].as_ref(), recursive), -1.0);
+        assert_eq!(penalty(analyses[0].as_ref(), iterative), 0.0);
+        assert_eq!(penalty(analyses[1].as_ref(), recursive), 0.0);
+
+        let yaml = YamlLoader::load_from_str("[{ analyser: no-recursion }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_custom() {
+        register_analyser("custom", |yaml| {
+            Ok(Box::new(CustomAnalyser {
+                penalty: mandatory_field_f64(yaml, "custom", "penalty")?,
+            }))
+        });
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: custom
+  penalty: -3.0
+- analyser: unknown
+  penalty: -1.0",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 1);
+        assert_eq!(analyses[0].name(), "custom");
+        assert_eq!(analyses[0].penalty(), -3.0);
+    }
+
+    #[test]
+    fn analyses_from_yaml_severity() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: check-alloc
+- analyser: check-alloc
+  funs: [ my_alloc ]
+  severity: penalty
+  penalty: -1.0",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].penalty(), 0.0);
+        assert_eq!(analyses[1].penalty(), -1.0);
+
+        let yaml = YamlLoader::load_from_str("[{ analyser: check-alloc, severity: penalty }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+        let yaml = YamlLoader::load_from_str("[{ analyser: check-alloc, severity: fatal }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_unsafe_input() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: unsafe-input
+  penalty: -1.0
+- analyser: unsafe-input
+  rules: { gets: report, scanf-string: off }
+  messages: { gets: never use gets }",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].severity(), Severity::Penalty(-1.0));
+        assert_eq!(analyses[1].severity(), Severity::Report);
+
+        let solution = crate::test_utils::get_solution(
+            "int main() { char s[5]; gets(s); scanf(\"%s\", s); }",
+            false,
+        );
+        let findings = analyses[1].analyse(&solution).unwrap();
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages, vec!["gets: never use gets"]);
+
+        // Penalty is mandatory for the default rules
+        let yaml = YamlLoader::load_from_str("[{ analyser: unsafe-input }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+        for rules in ["{ printf: off }", "{ gets: warn }"] {
+            let yaml = YamlLoader::load_from_str(&format!(
+                "[{{ analyser: unsafe-input, penalty: -1.0, rules: {} }}]",
+                rules
+            ));
+            let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+            assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+        }
+    }
+
+    #[test]
+    fn config_hardcoded_output() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("out"), "Result: 42 apples\n").unwrap();
+        std::fs::write(
+            project.path().join("config.yaml"),
+            "
+source: test.c
+analyses:
+  - analyser: hardcoded-output
+    min-length: 5
+    threshold: 1.0
+tests:
+  - name: file
+    score: 1.0
+    stdout: <out",
+        )
+        .unwrap();
+        let res = Config::from_yaml(Path::new("config.yaml"), project.path());
+        assert!(res.is_ok());
+        let config = res.unwrap();
+        assert_eq!(config.analyses[0].severity(), Severity::Report);
+        // Outputs are loaded from files before the analyser is created
+        let mut solution = crate::test_utils::get_solution("int main() {}", false);
+        solution.literals = vec![(Some(1), "Result: 42 apples\n".to_string())];
+        let findings = config.analyses[0].analyse(&solution).unwrap();
+        assert_eq!(findings.len(), 1);
+    }
+
+    #[test]
+    fn analyses_from_yaml_pair_balance() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: pair-balance
+  pairs: [ [ malloc, free ], [ fopen, fclose ] ]
+  slack: 1
+  penalty: -1.0
+- analyser: pair-balance
+  pairs: [ [ malloc, free ] ]
+  severity: report",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses.len(), 2);
+        assert_eq!(analyses[0].penalty(), -1.0);
+        assert_eq!(analyses[1].penalty(), 0.0);
+
+        let yaml = YamlLoader::load_from_str(
+            "[{ analyser: pair-balance, pairs: [ [ malloc ] ], penalty: -1.0 }]",
+        );
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_source_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("skeleton.c"), "int main() {}").unwrap();
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: source-size
+  min-lines: 5
+  max-lines: 500
+  max-identical-to-file: skeleton.c
+  penalty: -1.0",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], dir.path(), &[]);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap()[0].penalty(), -1.0);
+
+        let yaml = YamlLoader::load_from_str(
+            "[{ analyser: source-size, max-identical-to-file: missing.c, penalty: -1.0 }]",
+        );
+        let res = analyses_from_yaml(&yaml.unwrap()[0], dir.path(), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingFiles { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_template_similarity() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("skeleton.c"), "int main() {}").unwrap();
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: template-similarity
+  template: skeleton.c
+  threshold: 0.8
+  penalty: -2.0
+- analyser: template-similarity
+  template: skeleton.c
+  severity: report",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], dir.path(), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses[0].penalty(), -2.0);
+        assert_eq!(analyses[1].severity(), Severity::Report);
+
+        let yaml = YamlLoader::load_from_str("[{ analyser: template-similarity, penalty: -1.0 }]");
+        let res = analyses_from_yaml(&yaml.unwrap()[0], dir.path(), &[]);
+        assert!(matches!(res, Err(ConfigError::MissingField { .. })));
+    }
+
+    #[test]
+    fn analyses_from_yaml_gate() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: no-call
+  funs: [ system ]
+  penalty: -1.0
+  gate: true
+  fail-score: -5.0
+- analyser: no-header
+  header: math.h
+  penalty: -1.0",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let analyses = res.unwrap();
+        assert_eq!(analyses[0].name(), "no-call");
+        assert_eq!(analyses[0].gate(), Some(-5.0));
+        assert_eq!(analyses[1].gate(), None);
+    }
+
+    #[test]
+    fn analyses_from_yaml_level() {
+        let yaml = YamlLoader::load_from_str(
+            "
+- analyser: no-header
+  header: math.h
+  penalty: -1.0
+- analyser: check-alloc
+- analyser: no-header
+  header: math.h
+  penalty: -1.0
+  level: warning",
+        )
+        .unwrap();
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
+        assert!(res.is_ok());
+        let levels: Vec<Level> = res.unwrap().iter().map(|a| a.level()).collect();
+        assert_eq!(levels, vec![Level::Error, Level::Warning, Level::Warning]);
+
+        let yaml = YamlLoader::load_from_str(
+            "[{ analyser: no-header, header: math.h, penalty: -1.0, level: info }]",
+        );
+        let res = analyses_from_yaml(&yaml.unwrap()[0], Path::new("."), &[]);
+        assert!(matches!(res, Err(ConfigError::InvalidField { .. })));
     }
 
     #[test]
     fn analyses_from_yaml_invalid() {
         let yaml = YamlLoader::load_from_str("[{ analyser: no-globals }]").unwrap();
-        let res = analyses_from_yaml(&yaml[0]);
+        let res = analyses_from_yaml(&yaml[0], Path::new("."), &[]);
         assert!(res.is_err());
         assert!(matches!(res, Err(ConfigError::MissingField { .. })));
     }
@@ -0,0 +1,125 @@
+//! Distribution of the scores of all solutions (for class-wide reports)
+
+use std::fmt::Write;
+
+/// Default width of histogram buckets (in points)
+pub const DEFAULT_BUCKET_WIDTH: f64 = 0.5;
+
+/// Percentiles reported along with the histogram
+const PERCENTILES: [u32; 5] = [10, 25, 50, 75, 90];
+
+/// Histogram of `scores` in buckets of `width` points
+/// Buckets are given by their lower bounds (multiples of `width`) and the numbers of scores
+/// which fall into them. All buckets from the lowest to the highest score are included,
+/// even if they are empty.
+pub fn buckets(scores: &[f64], width: f64) -> Vec<(f64, usize)> {
+    // Scores lying on a bound (e.g. 0.3 for width 0.1) must not fall below it due to
+    // rounding errors
+    let bucket = |score: f64| (score / width + 1e-9).floor() as i64;
+    let indices: Vec<i64> = scores.iter().map(|s| bucket(*s)).collect();
+    let (min, max) = match (indices.iter().min(), indices.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return vec![],
+    };
+    (min..=max)
+        .map(|b| {
+            (
+                b as f64 * width,
+                indices.iter().filter(|i| **i == b).count(),
+            )
+        })
+        .collect()
+}
+
+/// Percentile `p` (0-100) of `sorted` scores
+/// Uses linear interpolation between the closest ranks, so the 50th percentile is the median.
+pub fn percentile(sorted: &[f64], p: u32) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = p as f64 / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+/// CSV with the histogram of `scores` and their percentiles
+/// Each row has the form `<kind>,<label>,<value>`:
+///   - `bucket,<from>..<to>,<count>` for each bucket of the evaluated solutions,
+///   - `bucket,DNF,<count>` for solutions which did not finish (e.g. failed to compile),
+///   - `percentile,p<N>,<score>` for percentiles of the scores of all solutions.
+pub fn histogram_csv(scores: &[f64], dnf_scores: &[f64], width: f64) -> String {
+    let round = |n: f64| (n * 100.0).round() / 100.0;
+    let mut csv = "kind,label,value\n".to_string();
+    for (from, count) in buckets(scores, width) {
+        let _ = writeln!(
+            csv,
+            "bucket,{}..{},{}",
+            round(from),
+            round(from + width),
+            count
+        );
+    }
+    let _ = writeln!(csv, "bucket,DNF,{}", dnf_scores.len());
+
+    let mut all: Vec<f64> = scores.iter().chain(dnf_scores).cloned().collect();
+    all.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    for p in PERCENTILES {
+        if let Some(value) = percentile(&all, p) {
+            let _ = writeln!(csv, "percentile,p{},{}", p, round(value));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets() {
+        assert_eq!(
+            buckets(&[0.0, 0.4, 0.5, 1.6, 1.9], 0.5),
+            vec![(0.0, 2), (0.5, 1), (1.0, 0), (1.5, 2)]
+        );
+        // Scores on bounds belong to the upper bucket
+        assert_eq!(buckets(&[0.3, 0.6], 0.3), vec![(0.3, 1), (0.6, 1)]);
+        assert_eq!(buckets(&[-1.0, 0.2], 1.0), vec![(-1.0, 1), (0.0, 1)]);
+        assert_eq!(buckets(&[3.0, 3.0], 2.0), vec![(2.0, 2)]);
+        assert!(buckets(&[], 0.5).is_empty());
+    }
+
+    #[test]
+    fn histogram_percentiles() {
+        let scores: Vec<f64> = (1..=10).map(|s| s as f64).collect();
+        assert_eq!(percentile(&scores, 50), Some(5.5));
+        assert_eq!(percentile(&scores, 10), Some(1.9));
+        assert_eq!(percentile(&scores, 90), Some(9.1));
+        assert_eq!(percentile(&scores, 0), Some(1.0));
+        assert_eq!(percentile(&scores, 100), Some(10.0));
+        assert_eq!(percentile(&[2.0], 25), Some(2.0));
+        assert_eq!(percentile(&[], 25), None);
+    }
+
+    #[test]
+    fn histogram_csv_format() {
+        assert_eq!(
+            histogram_csv(&[1.0, 2.0, 2.5, 4.0], &[0.0], 1.0),
+            "kind,label,value
+bucket,1..2,1
+bucket,2..3,2
+bucket,3..4,0
+bucket,4..5,1
+bucket,DNF,1
+percentile,p10,0.4
+percentile,p25,1
+percentile,p50,2
+percentile,p75,2.5
+percentile,p90,3.4
+"
+        );
+        assert_eq!(
+            histogram_csv(&[], &[], 0.5),
+            "kind,label,value\nbucket,DNF,0\n"
+        );
+    }
+}
@@ -1,13 +1,36 @@
 mod analyses;
+pub mod audit;
+mod compare;
 mod config;
+mod digest;
+mod histogram;
 mod modules;
+mod results;
+mod toml;
+mod workspace;
 
-use config::Config;
-use log::warn;
+pub use analyses::{
+    register_analyser, Analyser, AnalyserConstructor, AnalyserFinding, Finding, Level, Severity,
+};
+use audit::{AuditLog, EvaluationObserver, Execution};
+pub use config::{Config, ConfigError, ConfigReport, UnknownOption};
+use log::{debug, info, warn};
 use modules::*;
-use std::collections::HashMap;
+use regex::Regex;
+pub use results::{
+    merge_results, read_results, write_results, LatePenaltyResult, SolutionResults, SourceMetrics,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use thiserror::Error;
+pub use workspace::Workspace;
+pub use yaml_rust::Yaml;
 
 /// One student task that is to be evaluated
 #[derive(Default)]
@@ -16,66 +39,877 @@ pub struct Solution {
     src_file: PathBuf,
     obj_file: PathBuf,
     bin_file: PathBuf,
+    // Executables of the additional programs which were built (see `Program`)
+    programs: BTreeMap<String, PathBuf>,
 
     included: Vec<String>,
     source: String,
+    // Lines of the original source file corresponding to the lines of `source`
+    line_map: Vec<Option<usize>>,
+    // Byte offsets in `source` where its lines start (and where the line after the last
+    // newline would start)
+    line_offsets: Vec<usize>,
+    // String literals of `source` (adjacent ones concatenated) and their original lines
+    literals: Vec<(Option<usize>, String)>,
+    // Object-like macros defined in the original source and their replacement texts
+    defines: Vec<(String, String)>,
+    // Analysers whose findings are suppressed on given lines (by `atst:allow` annotations)
+    allowed: Vec<(usize, String)>,
+    // Metrics of the source (None if it could not be read)
+    metrics: Option<SourceMetrics>,
+    // Number of warnings of the compiler (None if the solution was not built)
+    compiler_warnings: Option<usize>,
+    // Late penalty applied to the solution (None if submitted on time)
+    late_penalty: Option<LatePenaltyResult>,
 
+    findings: Vec<AnalyserFinding>,
+    test_results: Vec<TestResult>,
+    // Programs run for the solution (only recorded for an audit, see `EvaluationObserver`)
+    executions: Option<Vec<Execution>>,
+    // Reason of not running the tests and the score given instead (see `Analyser::gate`)
+    gate: Option<(String, f64)>,
     score: f64,
+    // Seed of the solution derived from the run seed (see `derive_seed`)
+    seed: u64,
+    // Exceptions granted to the solution (see `SolutionOverride`)
+    overrides: Option<SolutionOverride>,
+    // Increment of the nice value of programs run for the solution (see `RunOptions`)
+    nice: Option<i32>,
+    // Working directories of the run (see `RunOptions::workdir_root`)
+    workspace: Option<Arc<Workspace>>,
 }
 
 impl Solution {
-    pub fn new(path: &Path, config: &Config) -> Self {
+    /// Create a solution in the directory `path`
+    /// Fails if the solution cannot be evaluated, e.g. if its directory cannot be read.
+    pub fn new(path: &Path, config: &Config) -> Result<Self, AtstError> {
+        let invalid = |msg: String| AtstError::InvalidSolution { msg };
+        if path.file_name().and_then(|name| name.to_str()).is_none() {
+            return Err(invalid(format!(
+                "directory name {} is not valid UTF-8",
+                path.display()
+            )));
+        }
+        if let Err(e) = path.read_dir() {
+            return Err(invalid(format!("cannot read directory: {}", e)));
+        }
         let src_file = Path::new(&config.src_file);
-        Self {
+        let bin_file = src_file
+            .file_stem()
+            .ok_or_else(|| invalid(format!("invalid source file name '{}'", config.src_file)))?;
+        let entry_point = Self::entry_point(path, config);
+        Ok(Self {
             path: path.to_path_buf(),
-            src_file: src_file.to_path_buf(),
-            bin_file: PathBuf::from(src_file.file_stem().unwrap()),
-            obj_file: src_file.with_extension("o"),
+            // The binary keeps its name so that tests and scripts can rely on it
+            bin_file: PathBuf::from(bin_file),
+            obj_file: entry_point.with_extension("o"),
+            src_file: entry_point,
+            programs: BTreeMap::new(),
             included: vec![],
             source: String::new(),
+            line_map: vec![],
+            line_offsets: vec![],
+            literals: vec![],
+            defines: vec![],
+            allowed: vec![],
+            metrics: None,
+            compiler_warnings: None,
+            late_penalty: None,
+            findings: vec![],
+            test_results: vec![],
+            executions: None,
+            gate: None,
             score: 0.0,
+            seed: 0,
+            overrides: None,
+            nice: None,
+            workspace: None,
+        })
+    }
+
+    /// Command running `program` for the solution
+    /// All programs run for the solution (compilers, tests, scripts, etc.) should be
+    /// created here so that they run with the priority given for the run.
+    fn command<S: AsRef<OsStr>>(&self, program: S) -> Command {
+        let mut command = Command::new(program);
+        if let Some(nice) = self.nice {
+            lower_priority(&mut command, nice);
+        }
+        command
+    }
+
+    /// Apply exceptions granted to the solution
+    fn set_override(&mut self, overrides: SolutionOverride) {
+        if let Some(src_file) = &overrides.src_file {
+            self.src_file = PathBuf::from(src_file);
+            self.obj_file = self.src_file.with_extension("o");
         }
+        self.overrides = Some(overrides);
+    }
+
+    /// Select the source file containing the entry point of the solution in `path`.
+    /// A per-solution override from the config has precedence. Otherwise, if the
+    /// configured source file does not exist and detection is enabled, the only
+    /// C file in the solution directory defining `main` is used.
+    fn entry_point(path: &Path, config: &Config) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some((_, file)) = config.entry_points.iter().find(|(s, _)| *s == name) {
+            return PathBuf::from(file);
+        }
+        let src_file = PathBuf::from(&config.src_file);
+        if !config.detect_entry_point || path.join(&src_file).exists() {
+            return src_file;
+        }
+
+        let main_re = Regex::new(r"\bint\s+main\s*\(").unwrap();
+        let mut candidates = match path.read_dir() {
+            Ok(entries) => entries
+                .filter_map(|res| res.ok())
+                .map(|entry| PathBuf::from(entry.file_name()))
+                .filter(|file| file.extension().is_some_and(|ext| ext == "c"))
+                .filter(|file| {
+                    std::fs::read_to_string(path.join(file))
+                        .is_ok_and(|source| main_re.is_match(&source))
+                })
+                .collect::<Vec<PathBuf>>(),
+            Err(_) => vec![],
+        };
+        match candidates.len() {
+            1 => {
+                let entry_point = candidates.remove(0);
+                info!(
+                    "{}: using {} as the entry point",
+                    name,
+                    entry_point.display()
+                );
+                entry_point
+            }
+            0 => src_file,
+            _ => {
+                candidates.sort();
+                warn!(
+                    "{}: multiple files define main ({}), set the entry point explicitly",
+                    name,
+                    candidates
+                        .iter()
+                        .map(|c| c.display().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+                src_file
+            }
+        }
+    }
+
+    /// Directory containing the solution
+    pub fn path(&self) -> &Path {
+        &self.path
     }
+
+    /// Source file (relative to the solution directory)
+    pub fn src_file(&self) -> &Path {
+        &self.src_file
+    }
+
+    /// Object file (relative to the solution directory)
+    pub fn obj_file(&self) -> &Path {
+        &self.obj_file
+    }
+
+    /// Executable file (relative to the solution directory)
+    pub fn bin_file(&self) -> &Path {
+        &self.bin_file
+    }
+
+    /// Executable of the additional program `name` (None if it was not built)
+    pub fn program_file(&self, name: &str) -> Option<&Path> {
+        self.programs.get(name).map(PathBuf::as_path)
+    }
+
+    /// Preprocessed source code (without the included headers)
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Line of the original source file corresponding to the given byte offset in `source`
+    pub fn source_line(&self, offset: usize) -> Option<usize> {
+        let line = self
+            .line_offsets
+            .partition_point(|&start| start <= offset.min(self.source.len()));
+        self.line_map.get(line.saturating_sub(1)).copied().flatten()
+    }
+
+    /// String literals of the preprocessed source with the original lines where they start
+    /// Adjacent literals are concatenated and escape sequences are decoded.
+    pub fn literals(&self) -> &[(Option<usize>, String)] {
+        &self.literals
+    }
+
+    /// Object-like macros (`#define NAME text`) of the source and their replacement texts
+    pub fn defines(&self) -> &[(String, String)] {
+        &self.defines
+    }
+
+    /// Names of the headers included by the solution source
+    pub fn included(&self) -> &[String] {
+        &self.included
+    }
+
+    /// Findings of the analysers run on the solution
+    pub fn findings(&self) -> &[AnalyserFinding] {
+        &self.findings
+    }
+
+    /// Check if programs run for the solution are recorded (for an audit)
+    pub(crate) fn audited(&self) -> bool {
+        self.executions.is_some()
+    }
+
+    /// Record a program run for the solution (if the solution is audited)
+    pub(crate) fn record_execution(&mut self, execution: Execution) {
+        if let Some(executions) = self.executions.as_mut() {
+            executions.push(execution);
+        }
+    }
+
+    /// Results of the tests run on the solution
+    pub fn test_results(&self) -> &[TestResult] {
+        &self.test_results
+    }
+
+    /// Reason of the tests of the solution not being run due to a gating analysis
+    pub fn gate(&self) -> Option<&str> {
+        self.gate.as_ref().map(|(reason, _)| reason.as_str())
+    }
+
+    /// Exceptions granted to the solution by its override file
+    pub fn overrides(&self) -> Option<&SolutionOverride> {
+        self.overrides.as_ref()
+    }
+
+    /// Seed for randomized evaluation of the solution, derive per-test seeds from it
+    /// using `derive_seed`
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Name of the solution (the name of its directory)
+    pub fn name(&self) -> String {
+        solution_name(&self.path)
+    }
+}
+
+/// Name of the solution in `path` (the name of its directory)
+fn solution_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
 }
 
 /// Single test case for the project
 /// Contains test input (args and stdin) and expected output
-#[derive(Default)]
+/// If `exit_code` is set, the program must exit with one of the given codes
+#[derive(Debug, Default, Clone)]
 pub struct TestCase {
     pub args: Vec<String>,
     pub stdin: Option<String>,
     pub stdout: Option<String>,
     pub stderr: Option<String>,
+    pub exit_code: Option<Vec<i64>>,
     pub case_insensitive: bool,
+    // Remove trailing whitespace of each line and trailing blank lines before comparison
+    pub ignore_trailing_whitespace: bool,
+    // Normalize outputs to the Unicode NFC before comparison
+    pub normalize_unicode: bool,
+    // Sort fields separated by the given delimiter within each line before comparison
+    pub sort_fields: Option<String>,
+    // Compare lines regardless of their order (as multisets)
+    pub unordered: bool,
+    // Compare whitespace-delimited columns, numeric ones with the given tolerances
+    pub columns: Option<Vec<f64>>,
+    // Compare whitespace-delimited tokens, numeric ones with the given tolerance
+    pub tolerance: Option<Tolerance>,
+    // Groups of interchangeable tokens (each token is replaced by the first one of its group)
+    pub equivalences: Option<Vec<Vec<String>>>,
+    // Input streamed to stdin from a file instead of `stdin` (a file or a fixture)
+    pub stdin_file: Option<DataFile>,
+    // Expected stdout compared to the output as a stream instead of `stdout`
+    pub stdout_file: Option<DataFile>,
+    // SHA-256 digest (lowercase hex) of the exact expected stdout instead of `stdout`
+    pub stdout_sha256: Option<String>,
+    // Timeout (in ms) overriding the one from `TestConfig`
+    pub timeout: Option<u64>,
+    // Environment variables set for the program (in addition to the inherited ones)
+    pub env: Vec<(String, String)>,
 }
 
-pub enum TestCasesRequirement {
-    ALL,
-    ANY,
+impl TestCase {
+    /// Check if the output is compared without any normalization (except for trimming)
+    /// so that it can be compared as a stream (see `compare::streams_equal_trimmed`)
+    pub fn streamable(&self) -> bool {
+        !self.case_insensitive
+            && !self.ignore_trailing_whitespace
+            && !self.normalize_unicode
+            && self.sort_fields.is_none()
+            && !self.unordered
+            && self.columns.is_none()
+            && self.tolerance.is_none()
+            && self.equivalences.is_none()
+    }
 }
 
-impl Default for TestCasesRequirement {
-    fn default() -> Self {
-        TestCasesRequirement::ALL
+/// Allowed difference between a number in the output and the expected number
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    Absolute(f64),
+    /// Fraction of the (absolute value of the) expected number
+    Relative(f64),
+}
+
+impl Tolerance {
+    pub fn matches(&self, output: f64, expected: f64) -> bool {
+        let diff = (output - expected).abs();
+        match self {
+            Tolerance::Absolute(epsilon) => diff <= *epsilon,
+            Tolerance::Relative(fraction) => diff <= fraction * expected.abs(),
+        }
+    }
+}
+
+/// Data of a test case stored in a file (not in memory)
+/// `name` is the reference from the configuration ("<file" or "@fixture"), the contents
+/// of the file are included in the hash of the test definition separately (see
+/// `Test::definition_hash`).
+#[derive(Clone)]
+pub struct DataFile {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl std::fmt::Debug for DataFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
     }
 }
 
+#[derive(Debug, Default, Clone)]
+pub enum TestCasesRequirement {
+    #[default]
+    ALL,
+    ANY,
+}
+
 /// A scored test for the project
 /// Contains test `name`, `score`, and a list of test `cases`.
 /// The `requirement` field specifies when the score is awarded. Current possible values are:
 ///   - `ALL`: all test cases must pass
 ///   - `ANY`: at least one test case must pass
-#[derive(Default)]
+///
+/// If `timeout_partial_credit` is set, a test case which times out but whose output
+/// is a prefix of the expected output is awarded the given fraction of the score.
+/// If `defines` are set, the test is run on an executable built with the given
+/// preprocessor defines (`-D<name>=<value>`).
+/// `cost_hint` is the expected cost of running the test (in arbitrary units, e.g.
+/// milliseconds), used to run cheap tests first (see `TestOrder::ByCost`).
+/// `description` explains what the test checks, `hint` is shown only if the test fails.
+/// `programs` are the additional programs (see `Program`) run by the test instead of the
+/// main executable; if there are more of them, they are run as a pipeline.
+///
+/// A test with a negative `score` is a penalty test: it gives nothing if it passes and
+/// its (negative) score if it fails, `requirement` then specifies when it passes.
+#[derive(Debug, Default, Clone)]
 pub struct Test {
     pub name: String,
     pub score: f64,
     pub test_cases: Vec<TestCase>,
     pub requirement: TestCasesRequirement,
+    pub timeout_partial_credit: Option<f64>,
+    pub defines: Vec<(String, String)>,
+    pub description: Option<String>,
+    pub hint: Option<String>,
+    pub cost_hint: Option<f64>,
+    pub programs: Vec<String>,
+}
+
+impl Test {
+    /// Hash of the test definition (used to detect tests changed between runs)
+    /// Uses FNV-1a so that the hash is stable across builds and platforms. Data files
+    /// (see `DataFile`) are only referenced in the definition, so their digests are
+    /// hashed, too.
+    pub fn definition_hash(&self) -> String {
+        let mut definition = format!("{:?}", self);
+        for file in self
+            .test_cases
+            .iter()
+            .flat_map(|tc| tc.stdin_file.iter().chain(tc.stdout_file.iter()))
+        {
+            definition += &format!(
+                "\n{}: {}",
+                file.name,
+                audit::sha256_file(&file.path).unwrap_or_default()
+            );
+        }
+        format!("{:016x}", fnv1a(definition.as_bytes()))
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Derive a seed from the run seed and the given `parts` (e.g. a solution name and
+/// a test name). The derivation only depends on its arguments, so all seeds of a run
+/// can be reproduced from the run seed on any platform.
+pub fn derive_seed(seed: u64, parts: &[&str]) -> u64 {
+    let mut bytes = seed.to_le_bytes().to_vec();
+    for part in parts {
+        // Parts are terminated so that e.g. ["ab", "c"] and ["a", "bc"] differ
+        bytes.extend(part.bytes());
+        bytes.push(0);
+    }
+    // Finalize by the SplitMix64 mixer to spread the bits
+    let mut z = fnv1a(&bytes).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Generate a new run seed (kept below 2^63 so that it can be given in the config)
+fn generate_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    derive_seed(nanos, &[&std::process::id().to_string()]) & i64::MAX as u64
 }
 
 pub const DEFAULT_TEST_TIMEOUT: u64 = 5000;
+pub const DEFAULT_PARTIAL_CREDIT: f64 = 0.5;
+pub const DEFAULT_MAX_DIFF_LINES: usize = 10;
+pub const DEFAULT_EXPECTED_OUTPUT_WARN_BYTES: u64 = 1 << 20;
+pub const DEFAULT_MAX_SOURCE_BYTES: u64 = 4 << 20;
+/// Expected outputs read from files larger than this are compared as streams
+pub const STREAMED_OUTPUT_BYTES: u64 = 1 << 20;
+
+/// Configuration of test execution (common for all tests)
+///   - `timeout`: time (in ms) after which the solution execution is killed
+///   - `order`: order in which the tests are run on each solution
+///   - `exec_prefix`: command (with arguments) used to launch the solution binary,
+///     e.g. an emulator for cross-compiled solutions
+///   - `max_diff_lines`: maximum number of differing lines reported for a wrong output
+///   - `max_workdir_bytes`: maximum total size of files created by a single test case;
+///     if set, each test case is run in a fresh temporary working directory
+///   - `equivalences`: groups of interchangeable tokens used by test cases which do not
+///     set their own
+///   - `expected_output_warn_bytes`: expected outputs larger than this are reported as
+///     suspicious when loading the configuration
+pub struct TestConfig {
+    pub timeout: u64,
+    pub order: TestOrder,
+    pub exec_prefix: Vec<String>,
+    pub max_diff_lines: usize,
+    pub max_workdir_bytes: Option<u64>,
+    pub equivalences: Vec<Vec<String>>,
+    pub expected_output_warn_bytes: u64,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TEST_TIMEOUT,
+            order: TestOrder::default(),
+            exec_prefix: vec![],
+            max_diff_lines: DEFAULT_MAX_DIFF_LINES,
+            max_workdir_bytes: None,
+            equivalences: vec![],
+            expected_output_warn_bytes: DEFAULT_EXPECTED_OUTPUT_WARN_BYTES,
+        }
+    }
+}
+
+/// Order in which the tests are run on each solution
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TestOrder {
+    /// Order of the tests in the configuration
+    #[default]
+    Config,
+    /// Tests with a lower cost hint first, tests without a hint last (in the order of
+    /// the configuration)
+    ByCost,
+}
+
+/// Configuration of source analyses (common for all analysers)
+///   - `max_source_bytes`: analyses are skipped for larger (preprocessed) sources
+///   - `oversize_penalty`: penalty for a source whose analyses were skipped
+pub struct AnalysesConfig {
+    pub max_source_bytes: u64,
+    pub oversize_penalty: Option<f64>,
+}
+
+impl Default for AnalysesConfig {
+    fn default() -> Self {
+        Self {
+            max_source_bytes: DEFAULT_MAX_SOURCE_BYTES,
+            oversize_penalty: None,
+        }
+    }
+}
+
+/// Limits checked on the executable of a solution after it is built
+///   - `max_bytes`: maximum size of the executable
+///   - `require_static`: the executable is linked with -static and must not need the
+///     dynamic loader
+///   - `penalty`: penalty for each violated limit, if not set, the build fails instead
+#[derive(Debug, Default, Clone)]
+pub struct BinaryLimits {
+    pub max_bytes: Option<u64>,
+    pub require_static: bool,
+    pub penalty: Option<f64>,
+}
+
+/// Penalty for warnings produced when compiling a solution which builds successfully
+/// `penalty` is given once if there are any warnings, or for each of them if `per_warning`
+/// is set.
+#[derive(Debug, Clone)]
+pub struct WarningsPenalty {
+    pub penalty: f64,
+    pub per_warning: bool,
+}
+
+impl Default for WarningsPenalty {
+    fn default() -> Self {
+        Self {
+            penalty: -0.5,
+            per_warning: false,
+        }
+    }
+}
+
+/// Reason of a test not being passed by a solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureReason {
+    /// Output (stdout or stderr) does not match the expected one
+    WrongOutput,
+    /// Program did not finish in time
+    Timeout,
+    /// Program was terminated by a signal
+    Crash,
+    /// Program exited with an unexpected exit code
+    ExitCode,
+    /// Program created files exceeding the disk usage limit
+    DiskLimit,
+    /// Program executable could not be built
+    CompileFail,
+    /// Test was not run
+    Skipped,
+    /// Test was not run due to findings of a gating analysis
+    Gated,
+}
+
+impl FailureReason {
+    /// Stable machine-readable code of the reason
+    pub fn code(&self) -> &'static str {
+        match self {
+            FailureReason::WrongOutput => "WRONG_OUTPUT",
+            FailureReason::Timeout => "TIMEOUT",
+            FailureReason::Crash => "CRASH",
+            FailureReason::ExitCode => "EXIT_CODE",
+            FailureReason::DiskLimit => "DISK_LIMIT",
+            FailureReason::CompileFail => "COMPILE_FAIL",
+            FailureReason::Skipped => "SKIPPED",
+            FailureReason::Gated => "GATED",
+        }
+    }
+
+    /// Reason with the given code (see `code()`)
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "WRONG_OUTPUT" => Some(FailureReason::WrongOutput),
+            "TIMEOUT" => Some(FailureReason::Timeout),
+            "CRASH" => Some(FailureReason::Crash),
+            "EXIT_CODE" => Some(FailureReason::ExitCode),
+            "DISK_LIMIT" => Some(FailureReason::DiskLimit),
+            "COMPILE_FAIL" => Some(FailureReason::CompileFail),
+            "SKIPPED" => Some(FailureReason::Skipped),
+            "GATED" => Some(FailureReason::Gated),
+            _ => None,
+        }
+    }
+}
+
+/// Reason of a single test case not being passed by a solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaseFailure {
+    /// Standard output does not match the expected one
+    WrongStdout,
+    /// Standard error output does not match the expected one
+    WrongStderr,
+    /// Program exited with an unexpected exit code
+    WrongExitCode,
+    /// Program did not finish in time
+    Timeout,
+    /// Program was terminated by a signal (if known)
+    Crash(Option<i32>),
+    /// Program created files exceeding the disk usage limit
+    DiskLimit,
+}
+
+impl CaseFailure {
+    /// Stable machine-readable code of the failure
+    /// A crash caused by a known signal has the signal number appended (e.g. "CRASH:11").
+    pub fn code(&self) -> String {
+        match self {
+            CaseFailure::WrongStdout => "WRONG_STDOUT".to_string(),
+            CaseFailure::WrongStderr => "WRONG_STDERR".to_string(),
+            CaseFailure::WrongExitCode => "WRONG_EXIT_CODE".to_string(),
+            CaseFailure::Timeout => "TIMEOUT".to_string(),
+            CaseFailure::Crash(None) => "CRASH".to_string(),
+            CaseFailure::Crash(Some(signal)) => format!("CRASH:{}", signal),
+            CaseFailure::DiskLimit => "DISK_LIMIT".to_string(),
+        }
+    }
+
+    /// Failure with the given code (see `code()`)
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "WRONG_STDOUT" => Some(CaseFailure::WrongStdout),
+            "WRONG_STDERR" => Some(CaseFailure::WrongStderr),
+            "WRONG_EXIT_CODE" => Some(CaseFailure::WrongExitCode),
+            "TIMEOUT" => Some(CaseFailure::Timeout),
+            "CRASH" => Some(CaseFailure::Crash(None)),
+            "DISK_LIMIT" => Some(CaseFailure::DiskLimit),
+            _ => code
+                .strip_prefix("CRASH:")
+                .and_then(|signal| signal.parse().ok())
+                .map(|signal| CaseFailure::Crash(Some(signal))),
+        }
+    }
+
+    /// Reason of the whole test corresponding to the failure
+    pub fn test_reason(&self) -> FailureReason {
+        match self {
+            CaseFailure::WrongStdout | CaseFailure::WrongStderr => FailureReason::WrongOutput,
+            CaseFailure::WrongExitCode => FailureReason::ExitCode,
+            CaseFailure::Timeout => FailureReason::Timeout,
+            CaseFailure::Crash(_) => FailureReason::Crash,
+            CaseFailure::DiskLimit => FailureReason::DiskLimit,
+        }
+    }
+}
+
+/// Result of a single test case on a solution
+/// `message` is a human-readable description of the failure (meant as a feedback, its
+/// wording may change), tools processing the results should only rely on `reason`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub reason: Option<CaseFailure>,
+    pub message: Option<String>,
+}
+
+/// Result of a single test on a solution
+/// `reason` is set if the test was not passed (i.e. `score` is less than `max_score`),
+/// `hint` of the test is only kept in that case.
+/// `hash` is the hash of the test definition (see `Test::definition_hash`).
+/// `cases` holds the results of the cases which were run (empty if the test was not run).
+/// `order` is the position of the test in the order in which the tests were run on the
+/// solution, it is only set if the tests were not run in the order of the configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub score: f64,
+    pub max_score: f64,
+    pub reason: Option<FailureReason>,
+    pub description: Option<String>,
+    pub hint: Option<String>,
+    pub hash: Option<String>,
+    pub cases: Vec<CaseResult>,
+    pub order: Option<usize>,
+}
+
+impl TestResult {
+    pub fn new(test: &Test, score: f64, reason: Option<FailureReason>) -> Self {
+        Self {
+            name: test.name.clone(),
+            score,
+            max_score: test.score.max(0.0),
+            reason,
+            description: test.description.clone(),
+            hint: test.hint.clone().filter(|_| reason.is_some()),
+            hash: Some(test.definition_hash()),
+            cases: vec![],
+            order: None,
+        }
+    }
+}
+
+/// Source of the submission timestamp of a solution
+pub enum TimestampSource {
+    /// Timestamp stored in a file inside the solution directory
+    File(PathBuf),
+    /// Modification time of the solution source file
+    SourceMtime,
+}
+
+/// Shape of the late submission penalty
+///   - `Linear`: the score is reduced by `per_day` fraction for each started day
+///   - `Exponential`: the score is multiplied by `1 - per_day` for each started day
+pub enum PenaltyCurve {
+    Linear,
+    Exponential,
+}
+
+/// Configuration of the penalty for submissions after `deadline` (Unix timestamp)
+pub struct LatePenaltyConfig {
+    pub deadline: i64,
+    pub timestamp: TimestampSource,
+    pub curve: PenaltyCurve,
+    pub per_day: f64,
+}
+
+/// Name of the file in a solution directory granting exceptions to the solution
+pub const OVERRIDE_FILE: &str = "atst-override.yaml";
+
+/// Exceptions granted to a single solution by `OVERRIDE_FILE` in its directory
+/// (see `Config::solution_override`), they cannot change scores nor add scripts
+///   - `timeout`: minimal timeout (in ms) of all test cases of the solution
+///   - `src_file`: source file used instead of the configured one
+///   - `excluded_tests`: names of tests which are not run (they give no score)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SolutionOverride {
+    pub timeout: Option<u64>,
+    pub src_file: Option<String>,
+    pub excluded_tests: Vec<String>,
+}
+
+impl std::fmt::Display for SolutionOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(timeout) = self.timeout {
+            parts.push(format!("timeout {} ms", timeout));
+        }
+        if let Some(src_file) = &self.src_file {
+            parts.push(format!("source {}", src_file));
+        }
+        if !self.excluded_tests.is_empty() {
+            parts.push(format!("excluded tests {}", self.excluded_tests.join(", ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Additional program built from the solution besides the main executable
+/// The executable is named `name` and is built from `sources` (relative to the solution
+/// directory) in a single compiler run, `c_flags` and `ld_flags` are added to the flags
+/// of the compiler.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Program {
+    pub name: String,
+    pub sources: Vec<String>,
+    pub c_flags: Option<String>,
+    pub ld_flags: Option<String>,
+}
+
+/// Configuration of scoring based on the line coverage of the solution source by the tests
+/// written by the student. The inputs of the tests are the `*.in` files in `tests_dir`
+/// (relative to the solution directory). The solution gets `score` multiplied by the ratio of
+/// the achieved line coverage and `threshold` (in percent), at most `score`.
+pub struct CoverageConfig {
+    pub score: f64,
+    pub threshold: f64,
+    pub tests_dir: PathBuf,
+}
+
+/// Modules which can be selected in the evaluation pipeline
+/// Source parsing is always run (right after compilation, if that is selected).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModuleKind {
+    Compile,
+    Tests,
+    Analyses,
+    Coverage,
+    Scripts,
+    LatePenalty,
+}
+
+impl ModuleKind {
+    /// Default order of the modules
+    pub const DEFAULT_PIPELINE: [ModuleKind; 6] = [
+        ModuleKind::Compile,
+        ModuleKind::Tests,
+        ModuleKind::Analyses,
+        ModuleKind::Coverage,
+        ModuleKind::Scripts,
+        ModuleKind::LatePenalty,
+    ];
+
+    /// Name of the module used in the config and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModuleKind::Compile => "compile",
+            ModuleKind::Tests => "tests",
+            ModuleKind::Analyses => "analyses",
+            ModuleKind::Coverage => "coverage",
+            ModuleKind::Scripts => "scripts",
+            ModuleKind::LatePenalty => "late-penalty",
+        }
+    }
+}
+
+impl FromStr for ModuleKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::DEFAULT_PIPELINE
+            .iter()
+            .find(|m| m.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "invalid module '{}' (expected one of: {})",
+                    s,
+                    Self::DEFAULT_PIPELINE
+                        .iter()
+                        .map(|m| m.name())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Coarse category of errors, stable across versions
+/// New variants of the error types map to one of the existing kinds where possible.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Invalid configuration file
+    Config,
+    /// Files referenced by the configuration cannot be read
+    MissingFiles,
+    /// An external program cannot be executed
+    Exec,
+    /// No solutions to evaluate
+    NoSolutions,
+    /// Results cannot be written
+    Output,
+    /// Invalid or conflicting results files
+    Results,
+    /// Other I/O errors
+    Io,
+    /// A solution cannot be evaluated
+    Solution,
+    /// Internal errors
+    Internal,
+}
+
+/// Error of a run (see `run_with_options`)
+pub type RunError = AtstError;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum AtstError {
     #[error("Configuration error: {source}")]
     ConfigError {
@@ -86,33 +920,652 @@ pub enum AtstError {
     ExecError(String),
     #[error("Internal error: {msg}")]
     InternalError { msg: String },
+    #[error("{msg}")]
+    InvalidSolution { msg: String },
+    #[error(
+        "no solutions found in {path} matching '{pattern}'{}",
+        did_you_mean(.suggestions)
+    )]
+    NoSolutions {
+        path: String,
+        pattern: String,
+        suggestions: Vec<String>,
+    },
+    #[error("cannot write output to {path}: {source}")]
+    OutputError {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("invalid results file {path}: {msg}")]
+    InvalidResults { path: String, msg: String },
+    #[error("conflicting results:{}", .conflicts.iter().map(|c| format!("\n  {}", c)).collect::<String>())]
+    ResultsConflict { conflicts: Vec<String> },
     #[error("solution execution error: {source}")]
     SolutionExecErr {
         #[from]
         source: std::io::Error,
     },
+    #[error("{source}")]
+    ModuleError {
+        #[from]
+        source: ModuleError,
+    },
+}
+
+impl AtstError {
+    /// Category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AtstError::ConfigError { source } => source.kind(),
+            AtstError::ExecError(_) => ErrorKind::Exec,
+            AtstError::InternalError { .. } => ErrorKind::Internal,
+            AtstError::InvalidSolution { .. } => ErrorKind::Solution,
+            AtstError::NoSolutions { .. } => ErrorKind::NoSolutions,
+            AtstError::OutputError { .. } => ErrorKind::Output,
+            AtstError::InvalidResults { .. } | AtstError::ResultsConflict { .. } => {
+                ErrorKind::Results
+            }
+            AtstError::SolutionExecErr { .. } => ErrorKind::Io,
+            AtstError::ModuleError { source } => source.kind(),
+        }
+    }
+}
+
+/// Error of a module evaluating a solution
+#[derive(Error, Debug)]
+#[error("module '{module}' failed on solution {solution}: {source}")]
+#[non_exhaustive]
+pub struct ModuleError {
+    pub module: String,
+    pub solution: String,
+    pub source: Box<AtstError>,
+}
+
+impl ModuleError {
+    /// Category of the error (the category of the underlying error)
+    pub fn kind(&self) -> ErrorKind {
+        self.source.kind()
+    }
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions.is_empty() {
+        true => String::new(),
+        false => format!("; did you mean one of: {}?", suggestions.join(", ")),
+    }
+}
+
+/// Additional output produced after all solutions are evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    /// Findings of all analysers as `<solution>:<source file>:<line>: [<analyser>] <message>`
+    /// lines (the source file is relative to the solution directory)
+    Findings(PathBuf),
+    /// Results of all solutions and their tests in JSON
+    Json(PathBuf),
+    /// Histogram of the scores of all solutions and their percentiles in CSV
+    Histogram(PathBuf),
+    /// Source metrics of all solutions in CSV (see `SourceMetrics`)
+    Metrics(PathBuf),
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    /// Parse output given as `<kind>:<path>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("findings", path)) if !path.is_empty() => Ok(Output::Findings(path.into())),
+            Some(("json", path)) if !path.is_empty() => Ok(Output::Json(path.into())),
+            Some(("histogram", path)) if !path.is_empty() => Ok(Output::Histogram(path.into())),
+            Some(("metrics", path)) if !path.is_empty() => Ok(Output::Metrics(path.into())),
+            _ => Err(format!(
+                "invalid output '{}' (expected 'findings:<path>', 'json:<path>', \
+                 'histogram:<path>', or 'metrics:<path>')",
+                s
+            )),
+        }
+    }
+}
+
+/// Filters of the solution results printed to the console
+/// If any filter is set, results are printed after all solutions are evaluated. Filters do
+/// not affect outputs nor the results returned from `run_with_options`.
+#[derive(Default, Clone)]
+pub struct ConsoleFilter {
+    /// Show only N solutions with the best score
+    pub top: Option<usize>,
+    /// Show only N solutions with the worst score
+    pub bottom: Option<usize>,
+    /// Show only solutions which failed (see `fail_below`)
+    pub failures_only: bool,
+    /// Solutions with a score below this threshold are considered failed
+    pub fail_below: f64,
+}
+
+impl ConsoleFilter {
+    fn is_active(&self) -> bool {
+        self.top.is_some() || self.bottom.is_some() || self.failures_only
+    }
+}
+
+/// Outcome of the evaluation of a single solution
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    NoSource,
+    CompilationFailed(f64),
+    Evaluated(f64),
+    // Tests were not run due to a gating analysis (with the given reason)
+    Gated(f64, String),
+    // Solution could not be evaluated (with the error message)
+    Error(String),
+}
+
+impl Outcome {
+    fn score(&self) -> f64 {
+        match self {
+            Outcome::NoSource | Outcome::Error(_) => 0.0,
+            Outcome::CompilationFailed(score)
+            | Outcome::Evaluated(score)
+            | Outcome::Gated(score, _) => *score,
+        }
+    }
+
+    fn failed(&self, threshold: f64) -> bool {
+        match self {
+            Outcome::Evaluated(score) => *score < threshold,
+            _ => true,
+        }
+    }
+}
+
+/// Select solutions to print according to `filter`
+/// Solutions are sorted by their scores (the best first) if `top` or `bottom` is set.
+fn filter_outcomes<'a>(
+    outcomes: &'a [(String, Outcome)],
+    filter: &ConsoleFilter,
+) -> Vec<&'a (String, Outcome)> {
+    let mut selected: Vec<&(String, Outcome)> = outcomes
+        .iter()
+        .filter(|(_, outcome)| !filter.failures_only || outcome.failed(filter.fail_below))
+        .collect();
+    if filter.top.is_none() && filter.bottom.is_none() {
+        return selected;
+    }
+    selected.sort_by(|(n1, o1), (n2, o2)| {
+        o2.score()
+            .partial_cmp(&o1.score())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(n1.cmp(n2))
+    });
+    let len = selected.len();
+    let top = filter.top.unwrap_or(0).min(len);
+    let bottom = len - filter.bottom.unwrap_or(0).min(len);
+    selected
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i < top || *i >= bottom)
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// Order in which the results of solutions are yielded by `run_iter`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ResultOrder {
+    /// Sorted by the names of the solutions, a result is yielded once the evaluations of
+    /// the solution and of all the preceding ones are finished
+    #[default]
+    Name,
+    /// In the order in which the evaluations finish (differs from `Name` only if
+    /// solutions are evaluated in parallel)
+    Completion,
+}
+
+/// Lower the CPU priority of `command` by increasing its nice value by `increment` and
+/// set its I/O priority to the idle class (on Linux)
+/// Failures cannot be reported from the child, the command is run with the default
+/// priority then (see `check_io_priority`).
+#[cfg(unix)]
+fn lower_priority(command: &mut Command, increment: i32) {
+    use std::os::unix::process::CommandExt;
+    // SAFETY: only async-signal-safe system calls are made in the child
+    unsafe {
+        command.pre_exec(move || {
+            libc::nice(increment);
+            #[cfg(target_os = "linux")]
+            let _ = set_idle_io_priority();
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_priority(_command: &mut Command, _increment: i32) {}
+
+/// Set the I/O priority of the calling thread to the idle class
+#[cfg(target_os = "linux")]
+fn set_idle_io_priority() -> std::io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    // SAFETY: the system call only changes the I/O priority of the calling thread
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << 13,
+        )
+    };
+    match res {
+        -1 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Warn if the I/O priority of programs cannot be lowered (see `lower_priority`)
+/// The priority is set for a short-lived thread, since it is per-thread on Linux.
+fn check_io_priority() {
+    #[cfg(target_os = "linux")]
+    if let Ok(Err(e)) = thread::spawn(set_idle_io_priority).join() {
+        warn!("cannot lower the I/O priority of programs: {}", e);
+    }
+}
+
+/// Pause after evaluating a solution for `busy` time so that the time spent evaluating
+/// is at most `percent` of the total time
+fn throttle_pause(percent: u32, busy: Duration) -> Duration {
+    busy * (100 - percent.min(100)) / percent.max(1)
+}
+
+/// Options of a run
+#[derive(Default, Clone)]
+pub struct RunOptions {
+    /// Evaluate only the solution with this name (all solutions if empty)
+    pub only_solution: String,
+    pub verbosity: u32,
+    pub outputs: Vec<Output>,
+    pub console: ConsoleFilter,
+    /// Modules not to run (even if the configuration selects them)
+    pub skip_modules: Vec<ModuleKind>,
+    /// JSON results of a previous run, only the tests changed since then are re-run
+    /// for solutions evaluated in that run
+    pub rerun_changed: Option<PathBuf>,
+    /// Fail on suspicious expected outputs instead of warning about them
+    pub strict: bool,
+    /// Seed of the run (overrides the seed from the configuration)
+    pub seed: Option<u64>,
+    /// Width of the buckets of the score histogram (see `Output::Histogram`)
+    pub histogram_bucket: Option<f64>,
+    /// Number of solutions evaluated in parallel (0 and 1 evaluate them one by one)
+    /// Verbose runs always evaluate solutions one by one.
+    pub jobs: usize,
+    /// Audit log to which all programs run, findings, and score changes are appended
+    /// (see `audit::AuditLog`)
+    pub audit: Option<PathBuf>,
+    /// Order of the results yielded by `run_iter`
+    pub result_order: ResultOrder,
+    /// Increment of the nice value of all programs run for solutions (their I/O
+    /// priority is lowered, too)
+    pub nice: Option<i32>,
+    /// Maximum percentage of time spent evaluating solutions, a pause is made after each
+    /// solution to keep the average utilization of the CPU under it
+    pub throttle: Option<u32>,
+    /// Include source metrics of solutions (see `SourceMetrics`) in the results
+    pub metrics: bool,
+    /// Directory in which the working directories of the run (see `Workspace`) are
+    /// created (the system temporary directory if not set)
+    pub workdir_root: Option<PathBuf>,
+    /// Keep the working directories of the run (for debugging)
+    pub keep_workdirs: bool,
 }
 
 /// Main entry point of the program
 /// Runs evaluation of all tests in `path` as defined in `config_file`
 /// If `solution` is set, only evaluate that solution
+/// If no solutions are found, `AtstError::NoSolutions` is returned
 pub fn run(
-    path: &PathBuf,
-    config_file: &PathBuf,
+    path: &Path,
+    config_file: &Path,
     only_solution: &str,
     verbosity: u32,
-) -> Result<HashMap<String, f64>, AtstError> {
-    let config = Config::from_yaml(&config_file, &path)?;
+) -> Result<HashMap<String, f64>, RunError> {
+    run_with_options(
+        path,
+        config_file,
+        &RunOptions {
+            only_solution: only_solution.to_string(),
+            verbosity,
+            ..Default::default()
+        },
+    )
+}
+
+/// Result of the evaluation of a single solution
+struct Evaluation {
+    outcome: Outcome,
+    /// Score of the solution (if it was evaluated)
+    score: Option<f64>,
+    /// Findings with the source file they belong to (relative to the solution)
+    findings: Vec<(PathBuf, AnalyserFinding)>,
+    test_results: Vec<TestResult>,
+    /// Exceptions granted to the solution (see `SolutionOverride`)
+    overrides: Option<SolutionOverride>,
+    metrics: Option<SourceMetrics>,
+    compiler_warnings: Option<usize>,
+    late_penalty: Option<LatePenaltyResult>,
+    /// Result printed on the console
+    summary: String,
+}
+
+/// Evaluate the solution `name` by running `modules` on it
+/// Solutions evaluated in the `previous` run only have their changed tests re-run.
+/// The events of the evaluation are passed to the `observer` (if any).
+fn evaluate_solution(
+    name: &str,
+    solution: Result<Solution, AtstError>,
+    modules: &[Box<dyn Module + '_>],
+    previous: &HashMap<String, SolutionResults>,
+    config: &Config,
+    verbosity: u32,
+    observer: Option<&dyn EvaluationObserver>,
+) -> Result<Evaluation, RunError> {
+    let skipped = |outcome: Outcome, summary: String| Evaluation {
+        outcome,
+        score: None,
+        findings: vec![],
+        test_results: config
+            .tests
+            .iter()
+            .map(|test| TestResult::new(test, 0.0, Some(FailureReason::Skipped)))
+            .collect(),
+        overrides: None,
+        metrics: None,
+        compiler_warnings: None,
+        late_penalty: None,
+        summary,
+    };
+    let mut solution = match solution {
+        Ok(solution) => solution,
+        Err(e) => {
+            warn!("{}: {}", name, e);
+            return Ok(skipped(
+                Outcome::Error(e.to_string()),
+                format!("evaluation error: {}", e),
+            ));
+        }
+    };
+
+    let src_file = solution.path.join(&solution.src_file);
+    if !src_file.exists() {
+        info!("{}: source file {} not found", name, src_file.display());
+        return Ok(skipped(Outcome::NoSource, "no source found".to_string()));
+    }
+
+    if observer.is_some() {
+        solution.executions = Some(vec![]);
+    }
+    if let (Some(overrides), true) = (&solution.overrides, verbosity > 0) {
+        println!("  overridden configuration: {}", overrides);
+    }
+    // Solutions evaluated in the previous run only have their changed tests re-run, the
+    // other modules are run as usual so that the score is computed the same way
+    let previous = previous.get(name);
+    for m in modules {
+        let (score, findings) = (solution.score, solution.findings.len());
+        match previous {
+            Some(previous) if m.name() == ModuleKind::Tests.name() => {
+                rerun_changed_tests(&mut solution, previous, config, verbosity)?
+            }
+            _ => execute_module(m.as_ref(), &mut solution, verbosity)?,
+        }
+        notify_observer(observer, &mut solution, m.name(), score, findings);
+    }
+    if let Some((_, fail_score)) = solution.gate {
+        let (score, findings) = (solution.score, solution.findings.len());
+        solution.score = fail_score;
+        notify_observer(observer, &mut solution, "gate", score, findings);
+    }
+    if config.clamp_score && solution.score < 0.0 {
+        let (score, findings) = (solution.score, solution.findings.len());
+        solution.score = 0.0;
+        notify_observer(observer, &mut solution, "clamp", score, findings);
+    }
 
+    let rounded_score = (solution.score * 100.0).round() / 100.0;
+    info!("{}: total score {}", name, rounded_score);
+    let summary = match (verbosity, solution.gate()) {
+        (0, Some(reason)) => format!("{} (tests not run: {})", rounded_score, reason),
+        (0, None) => rounded_score.to_string(),
+        _ => format!("Total: {}", rounded_score),
+    };
+    let outcome = match solution.path.join(&solution.bin_file).exists() {
+        _ if solution.gate.is_some() => {
+            Outcome::Gated(rounded_score, solution.gate().unwrap().to_string())
+        }
+        true => Outcome::Evaluated(rounded_score),
+        false => Outcome::CompilationFailed(rounded_score),
+    };
+    let findings_file = solution.src_file.clone();
+    Ok(Evaluation {
+        outcome,
+        score: Some(solution.score),
+        findings: solution
+            .findings
+            .drain(..)
+            .map(|f| (findings_file.clone(), f))
+            .collect(),
+        test_results: std::mem::take(&mut solution.test_results),
+        overrides: solution.overrides.take(),
+        metrics: solution.metrics.take(),
+        compiler_warnings: solution.compiler_warnings,
+        late_penalty: solution.late_penalty,
+        summary,
+    })
+}
+
+/// Pass the events of `solution` since the previous notification to `observer`: the
+/// programs run, the findings after the first `findings_before` ones, and the change
+/// of the score from `score_before` (made by `source`)
+fn notify_observer(
+    observer: Option<&dyn EvaluationObserver>,
+    solution: &mut Solution,
+    source: &str,
+    score_before: f64,
+    findings_before: usize,
+) {
+    let observer = match observer {
+        Some(observer) => observer,
+        None => return,
+    };
+    let name = solution.name();
+    for execution in solution.executions.iter_mut().flat_map(|e| e.drain(..)) {
+        observer.execution(&name, &execution);
+    }
+    for finding in solution.findings.iter().skip(findings_before) {
+        observer.finding(&name, finding);
+    }
+    if solution.score != score_before {
+        observer.score_changed(&name, source, score_before, solution.score);
+    }
+}
+
+/// Evaluate `solutions` using `evaluate` in `jobs` worker threads
+/// The evaluations are passed to `record` in the order of `solutions`, each one as soon as
+/// it and all the preceding ones are done, or in the order in which they are done if
+/// `in_order` is not set. The first error, or `record` returning false, stops the
+/// evaluation.
+fn evaluate_parallel<S: Send, E: Send>(
+    solutions: Vec<(String, S)>,
+    jobs: usize,
+    in_order: bool,
+    evaluate: impl Fn(&str, S) -> Result<E, RunError> + Sync,
+    mut record: impl FnMut(String, E) -> bool,
+) -> Result<(), RunError> {
+    let queue = Mutex::new(solutions.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (queue, evaluate) = (&queue, &evaluate);
+            scope.spawn(move || loop {
+                let next = queue.lock().unwrap().next();
+                let (i, (name, solution)) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                info!("Evaluating solution {}", name);
+                let evaluation = evaluate(&name, solution);
+                if sender.send((i, name, evaluation)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut done = BTreeMap::new();
+        let mut next = 0;
+        for (i, name, evaluation) in receiver {
+            // Without ordering, each evaluation is taken as the next one
+            done.insert(if in_order { i } else { next }, (name, evaluation));
+            while let Some((name, evaluation)) = done.remove(&next) {
+                next += 1;
+                let result = match evaluation.map(|evaluation| record(name, evaluation)) {
+                    Ok(true) => continue,
+                    Ok(false) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                // Workers finish the solutions they are evaluating and stop
+                queue.lock().unwrap().by_ref().for_each(drop);
+                return result;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Result of the evaluation of a single solution (see `run_iter`)
+/// `results` are the results as written to the JSON output (with a rounded score),
+/// `score` is the exact score of a solution which was evaluated (None for solutions
+/// without a source or which could not be evaluated at all).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionResult {
+    pub results: SolutionResults,
+    pub score: Option<f64>,
+}
+
+/// Iterator over the results of solutions being evaluated (see `run_iter`)
+pub struct RunIter {
+    receiver: mpsc::Receiver<Result<SolutionResult, RunError>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Iterator for RunIter {
+    type Item = Result<SolutionResult, RunError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.receiver.recv() {
+            Ok(item) => Some(item),
+            Err(_) => {
+                // The run is over, a panic of the run is passed on to the consumer
+                if let Some(Err(panic)) = self.thread.take().map(thread::JoinHandle::join) {
+                    std::panic::resume_unwind(panic);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Same as `run_with_options`, however, the result of each solution is yielded as soon
+/// as its evaluation finishes (in the order given by `options.result_order`).
+/// The run takes place in a background thread. An error of the run is yielded as the last
+/// item, errors of the configuration are yielded before any result. Outputs are written
+/// after all solutions are evaluated. Dropping the iterator stops the run once the
+/// solutions being evaluated are finished (outputs are not written then).
+pub fn run_iter(path: &Path, config_file: &Path, options: RunOptions) -> RunIter {
+    let (sender, receiver) = mpsc::channel();
+    let (path, config_file) = (path.to_path_buf(), config_file.to_path_buf());
+    let thread = thread::spawn(move || {
+        let result = run_streaming(&path, &config_file, &options, |result| {
+            sender.send(Ok(result)).is_ok()
+        });
+        if let Err(e) = result {
+            let _ = sender.send(Err(e));
+        }
+    });
+    RunIter {
+        receiver,
+        thread: Some(thread),
+    }
+}
+
+/// Same as `run` with additional options of the run
+pub fn run_with_options(
+    path: &Path,
+    config_file: &Path,
+    options: &RunOptions,
+) -> Result<HashMap<String, f64>, RunError> {
+    let mut scores = HashMap::new();
+    for result in run_iter(path, config_file, options.clone()) {
+        let result = result?;
+        if let Some(score) = result.score {
+            scores.insert(result.results.name, score);
+        }
+    }
+    Ok(scores)
+}
+
+/// Run the evaluation as described by `options` and pass the result of each solution to
+/// `emit` as soon as it is available; `emit` returning false stops the run
+fn run_streaming(
+    path: &Path,
+    config_file: &Path,
+    options: &RunOptions,
+    mut emit: impl FnMut(SolutionResult) -> bool,
+) -> Result<(), RunError> {
+    let only_solution = options.only_solution.as_str();
+    let verbosity = options.verbosity;
+    // Working directories of test cases and generated fixtures of the whole run
+    let root = options
+        .workdir_root
+        .clone()
+        .unwrap_or_else(std::env::temp_dir);
+    let workspace = Arc::new(Workspace::new(&root, options.keep_workdirs).map_err(|e| {
+        AtstError::OutputError {
+            path: root.display().to_string(),
+            source: e,
+        }
+    })?);
+    if options.keep_workdirs {
+        info!(
+            "Working directories are kept in {}",
+            workspace.path().display()
+        );
+    }
+    let mut config = Config::from_file_in(config_file, path, &workspace)?;
+    if options.strict {
+        config = config.check_strict()?;
+    }
+    for lint in &config.output_lints {
+        warn!("suspicious expected output: {}", lint);
+    }
+    for duplicate in &config.duplicate_cases {
+        warn!("duplicate test case: {}", duplicate);
+    }
+    // Fail before any solution is evaluated
+    config.check()?;
+    debug!("Loaded configuration:\n{}", config.report());
+
+    // Solutions which cannot be constructed are kept (with the error) and reported
+    // in the results so that a single broken solution does not stop the run
     let mut solutions = vec![];
+    let named = |path: &Path| (solution_name(path), Solution::new(path, &config));
 
     if !only_solution.is_empty() {
         // Single solution
-        let s = Solution::new(&path.join(only_solution), &config);
-        if s.path.exists() {
-            solutions.push(s);
-        } else {
-            warn!("Selected solution does not exist");
+        let solution_path = path.join(only_solution);
+        if solution_path.exists() {
+            solutions.push(named(&solution_path));
         }
     } else {
         // Solutions are sub-dirs of the project directory except those explicitly excluded
@@ -121,75 +1574,1541 @@ pub fn run(
             .map_err(|_| AtstError::InternalError {
                 msg: "could not read project directory".to_string(),
             })?
-            .filter_map(|res| res.ok())
+            .filter_map(|res| {
+                res.map_err(|e| warn!("cannot read an entry of the project directory: {}", e))
+                    .ok()
+            })
             .filter(|entry| {
                 entry.path().is_dir()
                     && !config
                         .excluded_dirs
-                        .contains(&entry.file_name().into_string().unwrap())
+                        .iter()
+                        .any(|dir| entry.file_name() == dir.as_str())
             })
-            .map(|entry| Solution::new(&entry.path(), &config))
+            .map(|entry| named(&entry.path()))
             .collect();
+        // Sorted so that the results are printed in the same order in each run
+        solutions.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+    }
+
+    // All randomness of the run is derived from a single seed so that the run can be
+    // reproduced by passing the same seed
+    let seed = options.seed.or(config.seed).unwrap_or_else(generate_seed);
+    info!("Using seed {}", seed);
+    if verbosity > 0 {
+        println!("Seed: {}", seed);
+    }
+    // Settings of the run recorded in the JSON output
+    let mut metadata = vec![("seed", seed.to_string())];
+    if let Some(nice) = options.nice {
+        info!("Running programs with nice value increased by {}", nice);
+        check_io_priority();
+        metadata.push(("nice", nice.to_string()));
+    }
+    if let Some(percent) = options.throttle {
+        info!("Throttling the evaluation to {}% of the time", percent);
+        metadata.push(("throttle", percent.to_string()));
+    }
+    for (name, solution) in &mut solutions {
+        if let Ok(solution) = solution {
+            solution.seed = derive_seed(seed, &[name]);
+            solution.nice = options.nice;
+            solution.workspace = Some(workspace.clone());
+        }
+    }
+    // Exceptions of individual solutions are checked before any solution is evaluated
+    for (name, solution) in &mut solutions {
+        if let Ok(solution) = solution {
+            let overrides =
+                config
+                    .solution_override(&solution.path)
+                    .map_err(|e| ConfigError::InOverride {
+                        solution: name.clone(),
+                        source: Box::new(e),
+                    })?;
+            if let Some(overrides) = overrides {
+                info!("{}: overridden configuration: {}", name, overrides);
+                solution.set_override(overrides);
+            }
+        }
     }
 
     if solutions.is_empty() {
-        warn!("No solutions to analyse");
-        return Ok(HashMap::new());
+        // Suggest the closest directories to what the user might have meant:
+        // other solutions if a single one was selected, sibling directories
+        // of the project otherwise.
+        let (pattern, suggestions) = match only_solution.is_empty() {
+            true => {
+                let project_dir = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                let project_name = project_dir.file_name().unwrap_or_default();
+                (
+                    "*".to_string(),
+                    nearest_dirs(
+                        project_dir.parent().unwrap_or(path),
+                        &project_name.to_string_lossy(),
+                    ),
+                )
+            }
+            false => (only_solution.to_string(), nearest_dirs(path, only_solution)),
+        };
+        return Err(AtstError::NoSolutions {
+            path: path.display().to_string(),
+            pattern,
+            suggestions,
+        });
     }
 
     // Create modules that will be run on each solution
-    // Currently used modules:
+    // The modules are selected by the config (`pipeline`), the default order is:
     //  - compilation
     //  - source parsing
     //  - test cases execution
-    //  - source analyses
+    //  - source analyses (before tests if some analysis gates them)
+    //  - coverage by the student's tests (if configured)
     //  - custom scripts
+    //  - late submission penalty
+    let pipeline = match &config.pipeline {
+        Some(pipeline) => pipeline.clone(),
+        None => {
+            let mut pipeline = ModuleKind::DEFAULT_PIPELINE.to_vec();
+            if config.analyses.iter().any(|a| a.gate().is_some()) {
+                pipeline.swap(1, 2);
+            }
+            pipeline
+        }
+    };
     let mut modules: Vec<Box<dyn Module>> = vec![];
-    modules.push(Box::new(Compiler::new(&config)));
-    modules.push(Box::new(Parser {}));
-    modules.push(Box::new(TestExec::new(&config.tests, config.timeout)));
-    modules.push(Box::new(AnalysesExec::new(&config.analyses)));
-    for script in &config.scripts {
-        modules.push(Box::new(ScriptExec::new(script)));
+    for kind in pipeline
+        .iter()
+        .filter(|m| !options.skip_modules.contains(m))
+    {
+        match kind {
+            ModuleKind::Compile => {
+                modules.push(Box::new(Compiler::new(&config)));
+                modules.push(Box::new(Parser {}));
+            }
+            ModuleKind::Tests => {
+                modules.push(Box::new(TestExec::new(&config.tests, &config.test_config)))
+            }
+            ModuleKind::Analyses => modules.push(Box::new(AnalysesExec::new(
+                &config.analyses,
+                &config.analyses_config,
+            ))),
+            ModuleKind::Coverage => {
+                if let Some(coverage) = &config.coverage {
+                    modules.push(Box::new(CoverageExec::new(coverage, &config.test_config)));
+                }
+            }
+            ModuleKind::Scripts => {
+                for script in &config.scripts {
+                    modules.push(Box::new(ScriptExec::new(script)));
+                }
+            }
+            ModuleKind::LatePenalty => {
+                if let Some(late_penalty) = &config.late_penalty {
+                    modules.push(Box::new(LatePenalty::new(late_penalty)));
+                }
+            }
+        }
+    }
+    let position = |kind| pipeline.iter().position(|m| *m == kind);
+    if config.analyses.iter().any(|a| a.gate().is_some())
+        && position(ModuleKind::Tests).is_some()
+        && position(ModuleKind::Tests) < position(ModuleKind::Analyses)
+    {
+        warn!("gating analyses are run after tests, tests will not be gated");
     }
+    if !pipeline.contains(&ModuleKind::Compile)
+        || options.skip_modules.contains(&ModuleKind::Compile)
+    {
+        modules.insert(0, Box::new(Parser {}));
+    }
+
+    let previous = match &options.rerun_changed {
+        Some(file) => read_results(file)?
+            .into_iter()
+            .map(|results| (results.name.clone(), results))
+            .collect(),
+        None => HashMap::new(),
+    };
 
-    let mut result = HashMap::new();
+    // With console filters, the results are printed at the end (unless being verbose)
+    let deferred = options.console.is_active();
+    let print_inline = !deferred || verbosity > 0;
+
+    let mut findings = vec![];
+    let mut outcomes = vec![];
+    let mut all_results = vec![];
+    let mut stopped = false;
+    // Metrics are only reported on request (or for the metrics output)
+    let metrics = options.metrics
+        || options
+            .outputs
+            .iter()
+            .any(|o| matches!(o, Output::Metrics(_)));
+    let mut record = |name: String, evaluation: Evaluation| {
+        let mut results = solution_results(&name, &evaluation);
+        if !metrics {
+            results.metrics = None;
+        }
+        findings.extend(
+            evaluation
+                .findings
+                .into_iter()
+                .map(|(path, finding)| (name.clone(), path, finding)),
+        );
+        outcomes.push((name, evaluation.outcome));
+        all_results.push(results.clone());
+        stopped = !emit(SolutionResult {
+            results,
+            score: evaluation.score,
+        });
+        !stopped
+    };
+    let audit = options.audit.as_deref().map(AuditLog::open).transpose()?;
+    let observer = audit.as_ref().map(|log| log as &dyn EvaluationObserver);
+    let evaluate = |name: &str, solution| {
+        let start = Instant::now();
+        let evaluation = evaluate_solution(
+            name, solution, &modules, &previous, &config, verbosity, observer,
+        );
+        if let Some(percent) = options.throttle {
+            let pause = throttle_pause(percent, start.elapsed());
+            debug!("{}: throttling, pausing for {:?}", name, pause);
+            thread::sleep(pause);
+        }
+        evaluation
+    };
     // Evaluation - run all modules on each solution
-    for mut solution in solutions {
-        let name = solution
-            .path
-            .file_name()
-            .unwrap()
-            .to_str()
+    // Verbose output is printed by the modules while running, so it is only readable
+    // if the solutions are evaluated one by one
+    let jobs = match verbosity {
+        0 => options.jobs.max(1),
+        _ => 1,
+    };
+    if jobs == 1 {
+        for (name, solution) in solutions {
+            info!("Evaluating solution {}", name);
+            if print_inline {
+                print!("{}: ", name);
+                if verbosity > 0 {
+                    println!();
+                }
+            }
+            let evaluation = evaluate(&name, solution)?;
+            if print_inline {
+                println!("{}", evaluation.summary);
+                if verbosity > 0 && evaluation.score.is_some() {
+                    println!();
+                }
+            }
+            if !record(name, evaluation) {
+                break;
+            }
+        }
+    } else {
+        let in_order = options.result_order == ResultOrder::Name;
+        evaluate_parallel(solutions, jobs, in_order, evaluate, |name, evaluation| {
+            if print_inline {
+                println!("{}: {}", name, evaluation.summary);
+            }
+            record(name, evaluation)
+        })?;
+    }
+    if stopped {
+        info!("Results are not consumed anymore, stopping the run");
+        return Ok(());
+    }
+
+    if let Some(audit) = audit {
+        audit.finish()?;
+    }
+
+    if deferred {
+        for (name, outcome) in filter_outcomes(&outcomes, &options.console) {
+            match outcome {
+                Outcome::NoSource => println!("{}: no source found", name),
+                Outcome::Error(msg) => println!("{}: evaluation error: {}", name, msg),
+                Outcome::CompilationFailed(score) => {
+                    println!("{}: {} (compilation failed)", name, score)
+                }
+                Outcome::Evaluated(score) => println!("{}: {}", name, score),
+                Outcome::Gated(score, reason) => {
+                    println!("{}: {} (tests not run: {})", name, score, reason)
+                }
+            }
+        }
+    }
+
+    for output in &options.outputs {
+        match output {
+            Output::Findings(file) => write_findings(file, &mut findings)?,
+            Output::Json(file) if !options.metrics => {
+                let mut results = all_results.clone();
+                results.iter_mut().for_each(|r| r.metrics = None);
+                write_json(file, &metadata, &mut results)?
+            }
+            Output::Json(file) => write_json(file, &metadata, &mut all_results)?,
+            Output::Histogram(file) => write_histogram(
+                file,
+                options
+                    .histogram_bucket
+                    .unwrap_or(histogram::DEFAULT_BUCKET_WIDTH),
+                &outcomes,
+            )?,
+            Output::Metrics(file) => write_metrics(file, &mut all_results)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Run module `m` on `solution`
+fn execute_module(m: &dyn Module, solution: &mut Solution, verbosity: u32) -> Result<(), RunError> {
+    m.execute(solution, verbosity).map_err(|e| {
+        ModuleError {
+            module: m.name().to_string(),
+            solution: solution.name(),
+            source: Box::new(e),
+        }
+        .into()
+    })
+}
+
+/// Run the tests whose definition changed since the `previous` results of `solution`
+/// Replaces the tests module when re-running changed tests, so the other modules of the
+/// pipeline are run as usual. Results of the unchanged tests are kept (unless the tests
+/// were gated or skipped) and their scores are added to the score of the solution.
+fn rerun_changed_tests(
+    solution: &mut Solution,
+    previous: &SolutionResults,
+    config: &Config,
+    verbosity: u32,
+) -> Result<(), RunError> {
+    let unchanged = |test: &Test| {
+        let hash = test.definition_hash();
+        previous.tests.iter().find(|t| {
+            t.name == test.name
+                && t.hash.as_ref() == Some(&hash)
+                && !matches!(
+                    t.reason,
+                    Some(FailureReason::Gated) | Some(FailureReason::Skipped)
+                )
+        })
+    };
+    // All tests of a gated solution are gated
+    let changed = config
+        .tests
+        .iter()
+        .filter(|test| solution.gate.is_some() || unchanged(test).is_none())
+        .cloned()
+        .collect::<Vec<Test>>();
+    info!(
+        "{}: re-running {} changed tests",
+        solution.name(),
+        changed.len()
+    );
+
+    // The re-run tests add their scores to the solution
+    execute_module(
+        &TestExec::new(&changed, &config.test_config),
+        solution,
+        verbosity,
+    )?;
+
+    let mut rerun = std::mem::take(&mut solution.test_results).into_iter();
+    let mut test_results = vec![];
+    for test in &config.tests {
+        match unchanged(test).filter(|_| solution.gate.is_none()) {
+            Some(result) => {
+                solution.score += result.score;
+                test_results.push(result.clone());
+            }
+            None => test_results.push(rerun.next().ok_or(AtstError::InternalError {
+                msg: "missing result of a re-run test".to_string(),
+            })?),
+        }
+    }
+    solution.test_results = test_results;
+    Ok(())
+}
+
+/// Write results of all solutions (sorted by name) and of their tests in JSON.
+/// Tests which were not passed have a `reason` set to one of `FailureReason` codes, their
+/// cases have a `reason` set to one of `CaseFailure` codes.
+fn write_json(
+    file: &Path,
+    metadata: &[(&str, String)],
+    solutions: &mut [SolutionResults],
+) -> Result<(), AtstError> {
+    solutions.sort_by(|s1, s2| s1.name.cmp(&s2.name));
+    results::write_results_with_metadata(file, metadata, solutions)
+}
+
+/// Results of the solution `name` in the JSON results format
+fn solution_results(name: &str, evaluation: &Evaluation) -> SolutionResults {
+    let outcome = &evaluation.outcome;
+    SolutionResults {
+        name: name.to_string(),
+        status: match outcome {
+            Outcome::NoSource => "no-source",
+            Outcome::Error(_) => "error",
+            Outcome::CompilationFailed(_) => "compilation-failed",
+            Outcome::Evaluated(_) => "evaluated",
+            Outcome::Gated(..) => "gated",
+        }
+        .to_string(),
+        score: outcome.score(),
+        gate: match outcome {
+            Outcome::Gated(_, reason) => Some(reason.clone()),
+            _ => None,
+        },
+        error: match outcome {
+            Outcome::Error(msg) => Some(msg.clone()),
+            _ => None,
+        },
+        overrides: evaluation.overrides.as_ref().map(|o| o.to_string()),
+        metrics: evaluation.metrics.clone(),
+        compiler_warnings: evaluation.compiler_warnings,
+        late_penalty: evaluation.late_penalty,
+        tests: evaluation.test_results.clone(),
+    }
+}
+
+/// Write findings of analysers sorted by source files and lines, one per line,
+/// in the format `<source file>:<line>: [<analyser>] <message>`.
+/// Findings of analysers with the `Report` severity are marked by "(report)".
+fn write_findings(
+    file: &Path,
+    findings: &mut [(String, PathBuf, AnalyserFinding)],
+) -> Result<(), AtstError> {
+    findings.sort_by(|(name1, path1, f1), (name2, path2, f2)| {
+        (name1, path1, f1.finding.line, &f1.analyser).cmp(&(
+            name2,
+            path2,
+            f2.finding.line,
+            &f2.analyser,
+        ))
+    });
+    let lines = findings
+        .iter()
+        .map(|(name, path, f)| {
+            format!(
+                "{}:{}:{} [{}]{} {}\n",
+                name,
+                path.display(),
+                f.finding.line.map_or(String::new(), |l| format!("{}:", l)),
+                f.analyser,
+                match f.severity {
+                    Severity::Report => " (report)",
+                    Severity::Penalty(_) => "",
+                },
+                f.finding.message
+            )
+        })
+        .collect::<String>();
+    std::fs::write(file, lines).map_err(|e| AtstError::OutputError {
+        path: file.display().to_string(),
+        source: e,
+    })
+}
+
+/// Write the histogram of scores of all solutions in CSV (see `histogram::histogram_csv`)
+/// Solutions which could not be evaluated or failed to compile are counted as "DNF".
+fn write_histogram(
+    file: &Path,
+    bucket_width: f64,
+    outcomes: &[(String, Outcome)],
+) -> Result<(), AtstError> {
+    let (scores, dnf_scores): (Vec<&Outcome>, Vec<&Outcome>) =
+        outcomes.iter().map(|(_, o)| o).partition(|o| match o {
+            Outcome::Evaluated(_) | Outcome::Gated(_, _) => true,
+            Outcome::NoSource | Outcome::CompilationFailed(_) | Outcome::Error(_) => false,
+        });
+    let score = |outcomes: Vec<&Outcome>| outcomes.iter().map(|o| o.score()).collect::<Vec<_>>();
+    let csv = histogram::histogram_csv(&score(scores), &score(dnf_scores), bucket_width);
+    std::fs::write(file, csv).map_err(|e| AtstError::OutputError {
+        path: file.display().to_string(),
+        source: e,
+    })
+}
+
+/// Write source metrics of all solutions (sorted by name) in CSV
+fn write_metrics(file: &Path, solutions: &mut [SolutionResults]) -> Result<(), AtstError> {
+    solutions.sort_by(|s1, s2| s1.name.cmp(&s2.name));
+    std::fs::write(file, results::metrics_csv(solutions)).map_err(|e| AtstError::OutputError {
+        path: file.display().to_string(),
+        source: e,
+    })
+}
+
+/// Parse a timestamp given either as a Unix timestamp or as a UTC date and time
+/// in the format "YYYY-MM-DD[ HH:MM[:SS]]" (a 'T' separator and a 'Z' suffix are allowed)
+pub(crate) fn parse_timestamp(string: &str) -> Option<i64> {
+    let string = string.trim();
+    if let Ok(ts) = string.parse::<i64>() {
+        return Some(ts);
+    }
+
+    let re =
+        Regex::new(r"^(\d{4})-(\d{2})-(\d{2})(?:[ T](\d{2}):(\d{2})(?::(\d{2}))?)?Z?$").ok()?;
+    let caps = re.captures(string)?;
+    let num = |i: usize| {
+        caps.get(i)
+            .map_or(Some(0), |m| m.as_str().parse::<i64>().ok())
+    };
+    let (y, m, d) = (num(1)?, num(2)?, num(3)?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    // Days since 1970-01-01 (see http://howardhinnant.github.io/date_algorithms.html)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Some(days * 86400 + num(4)? * 3600 + num(5)? * 60 + num(6)?)
+}
+
+/// Get up to 3 sub-directories of `dir` whose names are the closest to `name`
+/// (excluding `name` itself)
+fn nearest_dirs(dir: &Path, name: &str) -> Vec<String> {
+    let mut dirs: Vec<(usize, String)> = match dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|res| res.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|d| d != name && !d.starts_with('.'))
+            .map(|d| (edit_distance(&d, name), d))
+            .collect(),
+        Err(_) => vec![],
+    };
+    dirs.sort();
+    dirs.into_iter().take(3).map(|(_, d)| d).collect()
+}
+
+/// Levenshtein distance of two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let subst = prev[j] + if ca == *cb { 0 } else { 1 };
+            cur.push(subst.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn edit_distance_ok() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("xideal", "ideal"), 1);
+    }
+
+    #[test]
+    fn parse_timestamp_ok() {
+        assert_eq!(parse_timestamp("1700000000"), Some(1700000000));
+        assert_eq!(parse_timestamp("1970-01-01"), Some(0));
+        assert_eq!(parse_timestamp("2000-03-01 12:30"), Some(951913800));
+        assert_eq!(parse_timestamp("2024-02-29T23:59:59Z\n"), Some(1709251199));
+    }
+
+    #[test]
+    fn parse_timestamp_invalid() {
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp("2024-13-01"), None);
+        assert_eq!(parse_timestamp("2024-01-01 25"), None);
+    }
+
+    #[test]
+    fn run_no_solutions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.yaml"), "source: proj.c").unwrap();
+        std::fs::create_dir(dir.path().join("xlogin")).unwrap();
+
+        let res = run(dir.path(), Path::new("config.yaml"), "xlogn", 0);
+        assert!(res.is_err());
+        let err = res.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoSolutions);
+        assert!(err.to_string().contains("did you mean one of: xlogin?"));
+    }
+
+    #[test]
+    fn run_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.yaml"), "tests: []").unwrap();
+        std::fs::create_dir(dir.path().join("xlogin")).unwrap();
+
+        let res = run(dir.path(), Path::new("config.yaml"), "", 0);
+        assert!(res.is_err());
+        let err = res.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Config);
+        assert!(err.to_string().contains("mandatory field 'source'"));
+
+        let res = run(dir.path(), Path::new("missing.yaml"), "", 0);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Io);
+    }
+
+    #[test]
+    fn run_unchecked_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("xlogin")).unwrap();
+        let config = dir.path().join("config.yaml");
+
+        // Missing scripts are reported before any solution is evaluated
+        std::fs::write(&config, "source: main.c\nscripts: [ check.sh ]").unwrap();
+        let res = run(dir.path(), Path::new("config.yaml"), "", 0);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::MissingFiles);
+
+        std::fs::write(
+            &config,
+            "source: main.c\ntests:\n  - { name: t, score: 1.0, stdot: ok }",
+        )
+        .unwrap();
+        let options = RunOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "Configuration error: unsupported options:\n  tests[0] (name: 't'): unsupported option 'stdot'"
+        );
+    }
+
+    #[test]
+    fn solution_command_priority() {
+        let niceness = |solution: &Solution| {
+            let output = solution.command("nice").output().unwrap();
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<i32>()
+                .unwrap()
+        };
+        let mut solution = Solution::default();
+        let default = niceness(&solution);
+        solution.nice = Some(5);
+        assert_eq!(niceness(&solution), (default + 5).min(19));
+    }
+
+    #[test]
+    fn throttle_pause_ratio() {
+        let busy = Duration::from_millis(300);
+        assert_eq!(throttle_pause(100, busy), Duration::ZERO);
+        assert_eq!(throttle_pause(50, busy), busy);
+        assert_eq!(throttle_pause(25, busy), Duration::from_millis(900));
+    }
+
+    #[test]
+    fn run_nice_throttle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "source: main.c\ntests: [ { name: t, score: 1.0, stdout: ok } ]",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        std::fs::write(
+            dir.path().join("xlogin00/main.c"),
+            "#include <stdio.h>\nint main() { puts(\"ok\"); }\n",
+        )
+        .unwrap();
+        let json_file = dir.path().join("results.json");
+        let options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            seed: Some(1),
+            nice: Some(3),
+            throttle: Some(90),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+        let json = std::fs::read_to_string(&json_file).unwrap();
+        assert!(json.starts_with("{\n  \"seed\": 1,\n  \"nice\": 3,\n  \"throttle\": 90,\n"));
+        assert_eq!(read_results(&json_file).unwrap().len(), 1);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn idle_io_priority() {
+        // Only the priority of the spawned thread is changed
+        let res = std::thread::spawn(set_idle_io_priority).join().unwrap();
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn definition_hash_data_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input");
+        std::fs::write(&input, "1 2 3").unwrap();
+        let test = Test {
+            name: "streamed".to_string(),
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdin_file: Some(DataFile {
+                    name: "<input".to_string(),
+                    path: input.clone(),
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let hash = test.definition_hash();
+        assert_eq!(test.definition_hash(), hash);
+
+        // Changing the contents of a streamed file changes the definition
+        std::fs::write(&input, "1 2 4").unwrap();
+        assert_ne!(test.definition_hash(), hash);
+    }
+
+    #[test]
+    fn evaluate_parallel_order() {
+        let solutions: Vec<(String, u64)> = (0..12).map(|i| (format!("s{:02}", i), i)).collect();
+        // Later solutions finish sooner
+        let evaluate = |_: &str, i: u64| {
+            std::thread::sleep(std::time::Duration::from_millis(5 * (12 - i)));
+            match i {
+                9 => Err(AtstError::InternalError {
+                    msg: "failed".to_string(),
+                }),
+                _ => Ok(i),
+            }
+        };
+        let mut recorded = vec![];
+        let res = evaluate_parallel(solutions[..9].to_vec(), 4, true, evaluate, |name, i| {
+            recorded.push((name, i));
+            true
+        });
+        assert!(res.is_ok());
+        assert_eq!(recorded, solutions[..9].to_vec());
+
+        recorded.clear();
+        let res = evaluate_parallel(solutions.clone(), 4, true, evaluate, |name, i| {
+            recorded.push((name, i));
+            true
+        });
+        assert!(matches!(res, Err(AtstError::InternalError { .. })));
+        assert_eq!(recorded, solutions[..9].to_vec());
+
+        // In the order of completion, the first solution is not done first (it only
+        // finishes after another result was recorded)
+        recorded.clear();
+        let other_recorded = AtomicBool::new(false);
+        let first_last = |name: &str, i: u64| {
+            while i == 0 && !other_recorded.load(Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            evaluate(name, i)
+        };
+        let res = evaluate_parallel(solutions[..8].to_vec(), 4, false, first_last, |name, i| {
+            other_recorded.store(true, Ordering::SeqCst);
+            recorded.push((name, i));
+            true
+        });
+        assert!(res.is_ok());
+        assert_eq!(recorded.len(), 8);
+        assert_ne!(recorded[0], solutions[0]);
+        recorded.sort();
+        assert_eq!(recorded, solutions[..8].to_vec());
+
+        // Refusing a result stops the evaluation
+        recorded.clear();
+        let res = evaluate_parallel(solutions.clone(), 2, true, evaluate, |name, i| {
+            recorded.push((name, i));
+            recorded.len() < 3
+        });
+        assert!(res.is_ok());
+        assert_eq!(recorded, solutions[..3].to_vec());
+    }
+
+    #[test]
+    fn run_solution_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |file: &str, contents: &str| {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(
+            "config.yaml",
+            "
+source: main.c
+test-config: { timeout: 100 }
+tests:
+  - { name: slow, score: 1.0, args: slow, stdout: ok }
+  - { name: fast, score: 2.0, stdout: ok }",
+        );
+        let source = r#"#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+int main(int argc, char **argv) {
+    if (argc > 1 && strcmp(argv[1], "slow") == 0)
+        usleep(300000);
+    puts("ok");
+}
+"#;
+        write("xlogin00/main.c", source);
+        write("xlogin01/program.c", source);
+        write(
+            &format!("xlogin01/{}", OVERRIDE_FILE),
+            "{ timeout: 2000, source: program.c }",
+        );
+        write("xlogin02/main.c", source);
+        write(
+            &format!("xlogin02/{}", OVERRIDE_FILE),
+            "exclude-tests: [ slow ]",
+        );
+        let json = dir.path().join("results.json");
+        let options = RunOptions {
+            outputs: vec![Output::Json(json.clone())],
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options).unwrap();
+        assert_eq!(res["xlogin00"], 2.0);
+        assert_eq!(res["xlogin01"], 3.0);
+        assert_eq!(res["xlogin02"], 2.0);
+
+        let results = read_results(&json).unwrap();
+        let overrides: Vec<Option<&str>> = results.iter().map(|r| r.overrides.as_deref()).collect();
+        assert_eq!(
+            overrides,
+            vec![
+                None,
+                Some("timeout 2000 ms; source program.c"),
+                Some("excluded tests slow")
+            ]
+        );
+        assert_eq!(results[2].tests[0].reason, Some(FailureReason::Skipped));
+
+        // Overrides cannot change scores
+        write(
+            &format!("xlogin02/{}", OVERRIDE_FILE),
+            "tests: [ { name: fast, score: 10.0 } ]",
+        );
+        let res = run(dir.path(), Path::new("config.yaml"), "", 0);
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            format!(
+                "Configuration error: {} of solution 'xlogin02': 'tests' cannot be overridden \
+                 for a solution (only timeout, source, and exclude-tests can)",
+                OVERRIDE_FILE
+            )
+        );
+    }
+
+    #[test]
+    fn run_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "
+source: main.c
+tests:
+  - { name: a, score: 1.0, stdin: a, stdout: a, env: { X: \"1\" } }
+  - { name: b, score: 2.0, stdout: b }
+analyses:
+  - { analyser: no-call, funs: [ getchar ], penalty: -0.5 }",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        std::fs::write(
+            dir.path().join("xlogin00").join("main.c"),
+            "#include <stdio.h>\nint main() { putchar(getchar()); }\n",
+        )
+        .unwrap();
+
+        let log = dir.path().join("audit.log");
+        let options = RunOptions {
+            audit: Some(log.clone()),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 0.5);
+
+        let events: Vec<Yaml> = std::fs::read_to_string(&log)
             .unwrap()
-            .to_string();
-        print!("{}: ", name);
-        if verbosity > 0 {
-            println!();
+            .lines()
+            .map(|l| yaml_rust::YamlLoader::load_from_str(l).unwrap().remove(0))
+            .collect();
+        let field = |i: usize, f: &str| events[i][f].as_str().unwrap_or_default().to_string();
+        let kinds: Vec<String> = (0..events.len())
+            .map(|i| match field(i, "event").as_str() {
+                "execution" => field(i, "kind"),
+                "score" => format!("score {}", field(i, "source")),
+                event => event.to_string(),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "compile",
+                "link",
+                "test",
+                "test",
+                "score tests",
+                "finding",
+                "score analyses"
+            ]
+        );
+        assert_eq!(field(0, "solution"), "xlogin00");
+        assert_eq!(
+            events[0]["cwd"].as_str(),
+            dir.path().join("xlogin00").to_str()
+        );
+        assert_eq!(
+            events[2]["command"][0].as_str(),
+            dir.path().join("xlogin00").join("main").to_str()
+        );
+        assert_eq!(field(2, "input-hash"), audit::sha256(b"a"));
+        assert_eq!(field(2, "stdout-hash"), audit::sha256(b"a"));
+        assert_eq!(field(2, "env-hash"), audit::sha256(b"X=1\n"));
+        assert_eq!(field(2, "result"), "passed");
+        assert_eq!(field(3, "result"), "WRONG_OUTPUT");
+        assert_eq!(events[4]["after"].as_i64(), Some(1));
+        assert_eq!(events[6]["before"].as_i64(), Some(1));
+        assert_eq!(events[6]["after"].as_f64(), Some(0.5));
+
+        // Next runs are appended to the log
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert!(res.is_ok());
+        let lines = std::fs::read_to_string(&log).unwrap().lines().count();
+        assert_eq!(lines, 2 * events.len());
+    }
+
+    #[test]
+    fn run_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "
+source: main.c
+tests:
+  - { name: a, score: 1.0, args: a, stdout: a }
+  - { name: b, score: 2.0, args: b, stdout: b }",
+        )
+        .unwrap();
+        for (i, output) in ["\"a\"", "\"b\"", "\"x\"", "argv[1]"].iter().enumerate() {
+            let solution = dir.path().join(format!("xlogin{:02}", i));
+            std::fs::create_dir(&solution).unwrap();
+            std::fs::write(
+                solution.join("main.c"),
+                format!(
+                    "#include <stdio.h>\nint main(int argc, char **argv) {{ printf({}); }}\n",
+                    output
+                ),
+            )
+            .unwrap();
         }
+        std::fs::create_dir(dir.path().join("xlogin04")).unwrap();
+
+        let run_jobs = |jobs| {
+            let options = RunOptions {
+                jobs,
+                ..Default::default()
+            };
+            run_with_options(dir.path(), Path::new("config.yaml"), &options).unwrap()
+        };
+        let sequential = run_jobs(1);
+        assert_eq!(sequential.len(), 4);
+        assert_eq!(sequential["xlogin00"], 1.0);
+        assert_eq!(sequential["xlogin01"], 2.0);
+        assert_eq!(sequential["xlogin02"], 0.0);
+        assert_eq!(sequential["xlogin03"], 3.0);
+        assert_eq!(run_jobs(3), sequential);
 
-        let src_file = &solution.path.join(&solution.src_file);
-        if !src_file.exists() {
-            println!("no source found");
-            continue;
+        for result_order in [ResultOrder::Name, ResultOrder::Completion] {
+            let options = RunOptions {
+                jobs: 3,
+                result_order,
+                ..Default::default()
+            };
+            let mut results: Vec<SolutionResult> =
+                run_iter(dir.path(), Path::new("config.yaml"), options)
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+            if result_order == ResultOrder::Completion {
+                results.sort_by(|r1, r2| r1.results.name.cmp(&r2.results.name));
+            }
+            let names: Vec<&str> = results.iter().map(|r| r.results.name.as_str()).collect();
+            assert_eq!(
+                names,
+                vec!["xlogin00", "xlogin01", "xlogin02", "xlogin03", "xlogin04"]
+            );
+            assert_eq!(results[3].score, Some(3.0));
+            assert_eq!(results[3].results.tests.len(), 2);
+            assert_eq!(results[4].score, None);
+            assert_eq!(results[4].results.status, "no-source");
         }
 
-        for m in &modules {
-            m.execute(&mut solution, verbosity)?;
+        // Errors of the configuration are yielded before any result
+        let mut results = run_iter(dir.path(), Path::new("missing.yaml"), RunOptions::default());
+        assert!(matches!(
+            results.next(),
+            Some(Err(AtstError::ConfigError { .. }))
+        ));
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn error_kind_source() {
+        use std::error::Error;
+
+        let err = AtstError::from(ModuleError {
+            module: "tests".to_string(),
+            solution: "xlogin00".to_string(),
+            source: Box::new(AtstError::ExecError("gcc".to_string())),
+        });
+        assert_eq!(err.kind(), ErrorKind::Exec);
+        assert_eq!(
+            err.to_string(),
+            "module 'tests' failed on solution xlogin00: error executing 'gcc' (not installed?)"
+        );
+        let source = err.source().and_then(|e| e.source());
+        assert_eq!(
+            source.map(|e| e.to_string()),
+            Some("error executing 'gcc' (not installed?)".to_string())
+        );
+
+        let err = AtstError::from(ConfigError::InTestCase {
+            location: "test 't'".to_string(),
+            field: "stdin".to_string(),
+            source: Box::new(ConfigError::InvalidCommand {
+                msg: "x".to_string(),
+            }),
+        });
+        assert_eq!(err.kind(), ErrorKind::Config);
+        assert!(err.source().and_then(|e| e.source()).is_some());
+    }
+
+    #[test]
+    fn solution_entry_point() {
+        let dir = tempfile::tempdir().unwrap();
+        for (solution, file, source) in &[
+            ("plain", "proj.c", "int main() {}"),
+            ("split", "app.c", "int main(void) {}"),
+            ("split", "util.c", "int helper() {}"),
+            ("ambiguous", "a.c", "int main() {}"),
+            ("ambiguous", "b.c", "int main() {}"),
+            ("custom", "b.c", "int main() {}"),
+        ] {
+            std::fs::create_dir_all(dir.path().join(solution)).unwrap();
+            std::fs::write(dir.path().join(solution).join(file), source).unwrap();
+        }
+        let config = Config {
+            src_file: "proj.c".to_string(),
+            detect_entry_point: true,
+            entry_points: vec![("custom".to_string(), "b.c".to_string())],
+            ..Default::default()
+        };
+
+        let entry_point = |name| Solution::new(&dir.path().join(name), &config).unwrap();
+        assert_eq!(entry_point("plain").src_file(), Path::new("proj.c"));
+        assert_eq!(entry_point("split").src_file(), Path::new("app.c"));
+        assert_eq!(entry_point("split").obj_file(), Path::new("app.o"));
+        assert_eq!(entry_point("split").bin_file(), Path::new("proj"));
+        assert_eq!(entry_point("ambiguous").src_file(), Path::new("proj.c"));
+        assert_eq!(entry_point("custom").src_file(), Path::new("b.c"));
+    }
+
+    #[test]
+    fn filter_outcomes_ok() {
+        let outcomes = vec![
+            ("a".to_string(), Outcome::Evaluated(5.0)),
+            ("b".to_string(), Outcome::NoSource),
+            ("c".to_string(), Outcome::Evaluated(8.0)),
+            ("d".to_string(), Outcome::CompilationFailed(1.0)),
+            ("e".to_string(), Outcome::Evaluated(2.0)),
+        ];
+        let names = |filter: ConsoleFilter| -> Vec<String> {
+            filter_outcomes(&outcomes, &filter)
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            names(ConsoleFilter {
+                top: Some(2),
+                ..Default::default()
+            }),
+            vec!["c", "a"]
+        );
+        assert_eq!(
+            names(ConsoleFilter {
+                top: Some(1),
+                bottom: Some(2),
+                ..Default::default()
+            }),
+            vec!["c", "d", "b"]
+        );
+        assert_eq!(
+            names(ConsoleFilter {
+                failures_only: true,
+                fail_below: 3.0,
+                ..Default::default()
+            }),
+            vec!["b", "d", "e"]
+        );
+        assert_eq!(
+            names(ConsoleFilter {
+                bottom: Some(10),
+                failures_only: true,
+                ..Default::default()
+            }),
+            vec!["d", "b"]
+        );
+    }
+
+    #[test]
+    fn output_from_str() {
+        assert_eq!(
+            "findings:out.txt".parse::<Output>(),
+            Ok(Output::Findings(PathBuf::from("out.txt")))
+        );
+        assert_eq!(
+            "json:out.json".parse::<Output>(),
+            Ok(Output::Json(PathBuf::from("out.json")))
+        );
+        assert_eq!(
+            "histogram:scores.csv".parse::<Output>(),
+            Ok(Output::Histogram(PathBuf::from("scores.csv")))
+        );
+        assert_eq!(
+            "metrics:metrics.csv".parse::<Output>(),
+            Ok(Output::Metrics(PathBuf::from("metrics.csv")))
+        );
+        assert!("findings:".parse::<Output>().is_err());
+        assert!("scores:out.txt".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn run_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = "
+source: main.c
+tests:
+  - score: 1.0
+    stdout: a
+analyses:
+  - analyser: no-call
+    funs: [ puts ]
+    penalty: -0.5";
+        std::fs::write(dir.path().join("config.yaml"), config).unwrap();
+        std::fs::write(
+            dir.path().join("pipeline.yaml"),
+            format!("{}\npipeline: [ compile, analyses ]", config),
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        std::fs::write(
+            dir.path().join("xlogin00").join("main.c"),
+            "#include <stdio.h>\nint main() {\n    puts(\"a\");\n}\n",
+        )
+        .unwrap();
+
+        let score = |config: &str, skip_modules: Vec<ModuleKind>| {
+            let options = RunOptions {
+                skip_modules,
+                ..Default::default()
+            };
+            run_with_options(dir.path(), Path::new(config), &options).unwrap()["xlogin00"]
+        };
+        assert_eq!(score("config.yaml", vec![]), 0.5);
+        assert_eq!(score("config.yaml", vec![ModuleKind::Analyses]), 1.0);
+        assert_eq!(score("pipeline.yaml", vec![]), -0.5);
+        assert_eq!(score("pipeline.yaml", vec![ModuleKind::Compile]), -0.5);
+    }
+
+    #[test]
+    fn run_penalty_clamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = "
+source: main.c
+tests:
+  - { score: 0.5, stdout: a }
+  - { score: -1.0, stderr: \"\" }";
+        std::fs::write(dir.path().join("config.yaml"), config).unwrap();
+        std::fs::write(
+            dir.path().join("clamp.yaml"),
+            format!("clamp-score: true\n{}", config),
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        std::fs::write(
+            dir.path().join("xlogin00").join("main.c"),
+            "#include <stdio.h>\nint main() {\n    fputs(\"a\", stderr);\n}\n",
+        )
+        .unwrap();
+
+        let score = |config: &str| run(dir.path(), Path::new(config), "", 0).unwrap()["xlogin00"];
+        assert_eq!(score("config.yaml"), -1.0);
+        assert_eq!(score("clamp.yaml"), 0.0);
+    }
+
+    #[test]
+    fn run_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "source: main.c\ntests: [ { score: 1.0, stdout: a } ]",
+        )
+        .unwrap();
+        for (login, src) in [
+            ("xlogin00", "int main() {\n    return 0;\n}\n"),
+            // Metrics are computed even if the solution does not compile
+            ("xlogin01", "// broken\nint main() {\n    return 0\n}\n"),
+        ] {
+            std::fs::create_dir(dir.path().join(login)).unwrap();
+            std::fs::write(dir.path().join(login).join("main.c"), src).unwrap();
         }
+        std::fs::create_dir(dir.path().join("xlogin02")).unwrap();
 
-        let rounded_score = (solution.score * 100.0).round() / 100.0;
-        if verbosity > 0 {
-            println!("Total: {}", rounded_score);
-            println!();
-        } else {
-            println!("{}", rounded_score);
+        let json_file = dir.path().join("results.json");
+        let metrics_file = dir.path().join("metrics.csv");
+        let mut options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            ..Default::default()
+        };
+        let metrics = |options: &RunOptions| {
+            run_with_options(dir.path(), Path::new("config.yaml"), options).unwrap();
+            read_results(&json_file)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.metrics)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(metrics(&options), vec![None, None, None]);
+
+        options.outputs.push(Output::Metrics(metrics_file.clone()));
+        // Requesting the metrics output does not add metrics to the JSON results
+        assert_eq!(metrics(&options), vec![None, None, None]);
+        assert_eq!(
+            std::fs::read_to_string(&metrics_file).unwrap(),
+            "solution,status,lines,functions,includes,longest-function,comment-ratio\n\
+             xlogin00,evaluated,3,1,0,3,0\n\
+             xlogin01,compilation-failed,3,1,0,3,0.25\n\
+             xlogin02,no-source,,,,,\n"
+        );
+
+        options.metrics = true;
+        let results = metrics(&options);
+        assert_eq!(results[1].as_ref().map(|m| m.comment_ratio), Some(0.25));
+        assert_eq!(results[2], None);
+    }
+
+    #[test]
+    fn run_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "
+source: main.c
+tests:
+  - name: output
+    description: prints a
+    hint: use puts
+    score: 1.0
+    stdout: a
+analyses:
+  - analyser: no-call
+    funs: [ puts ]
+    penalty: -1.0
+  - analyser: check-alloc",
+        )
+        .unwrap();
+        for (login, src) in [
+            (
+                "xlogin01",
+                "#include <stdio.h>\nint main() {\n    puts(\"a\");\n}\n",
+            ),
+            (
+                "xlogin00",
+                "#include <stdlib.h>\nint main() {\n    int *p = malloc(4);\n    return *p;\n}\n",
+            ),
+        ] {
+            std::fs::create_dir(dir.path().join(login)).unwrap();
+            std::fs::write(dir.path().join(login).join("main.c"), src).unwrap();
         }
 
-        result.insert(name.to_string(), solution.score);
+        let findings_file = dir.path().join("findings");
+        let json_file = dir.path().join("results.json");
+        let histogram_file = dir.path().join("histogram.csv");
+        let options = RunOptions {
+            outputs: vec![
+                Output::Findings(findings_file.clone()),
+                Output::Json(json_file.clone()),
+                Output::Histogram(histogram_file.clone()),
+            ],
+            seed: Some(42),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert!(res.is_ok());
+        assert_eq!(
+            std::fs::read_to_string(findings_file).unwrap(),
+            "xlogin00:main.c:3: [check-alloc] (report) result of 'malloc' assigned to \
+             'p' is used at line 4 without a NULL check\n\
+             xlogin01:main.c:3: [no-call] call of 'puts'\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(histogram_file).unwrap(),
+            "kind,label,value\nbucket,0..0.5,2\nbucket,DNF,0\npercentile,p10,0\n\
+             percentile,p25,0\npercentile,p50,0\npercentile,p75,0\npercentile,p90,0\n"
+        );
+        let hash = Config::from_yaml(Path::new("config.yaml"), dir.path())
+            .unwrap()
+            .tests[0]
+            .definition_hash();
+        assert_eq!(
+            std::fs::read_to_string(json_file).unwrap(),
+            format!(
+                r#"{{
+  "seed": 42,
+  "solutions": [
+    {{
+      "name": "xlogin00",
+      "status": "evaluated",
+      "score": 0,
+      "compiler-warnings": 0,
+      "tests": [
+        {{ "name": "output", "score": 0, "max-score": 1, "reason": "WRONG_OUTPUT", "description": "prints a", "hint": "use puts", "hash": "{hash}", "cases": [{{ "reason": "WRONG_STDOUT", "message": "output does not match the expected one" }}] }}
+      ]
+    }},
+    {{
+      "name": "xlogin01",
+      "status": "evaluated",
+      "score": 0,
+      "compiler-warnings": 0,
+      "tests": [
+        {{ "name": "output", "score": 1, "max-score": 1, "reason": null, "description": "prints a", "hint": null, "hash": "{hash}", "cases": [{{ "reason": null, "message": null }}] }}
+      ]
+    }}
+  ]
+}}
+"#,
+                hash = hash
+            )
+        );
+    }
+
+    #[test]
+    fn seed_derivation() {
+        // Derived seeds must not change between builds and platforms
+        assert_eq!(derive_seed(42, &["xlogin00"]), 8646714649945616469);
+        assert_eq!(derive_seed(42, &["xlogin00", "test"]), 10767093076130236336);
+        assert_ne!(
+            derive_seed(43, &["xlogin00"]),
+            derive_seed(42, &["xlogin00"])
+        );
+        assert_ne!(derive_seed(42, &["ab", "c"]), derive_seed(42, &["a", "bc"]));
+        assert!(generate_seed() <= i64::MAX as u64);
+    }
+
+    #[test]
+    fn run_rerun_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = |expected_b: &str| {
+            format!(
+                "
+source: main.c
+tests:
+  - name: a
+    score: 1.0
+    stdout: a
+  - name: b
+    score: 1.0
+    stdout: {}
+",
+                expected_b
+            )
+        };
+        std::fs::write(dir.path().join("config.yaml"), config("b")).unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        let src = dir.path().join("xlogin00").join("main.c");
+        std::fs::write(&src, "#include <stdio.h>\nint main() { printf(\"a\"); }\n").unwrap();
+
+        let json_file = dir.path().join("results.json");
+        let options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+
+        // Only test 'b' changed, the result of 'a' is kept even though it would fail now
+        std::fs::write(dir.path().join("config.yaml"), config("c")).unwrap();
+        std::fs::write(&src, "#include <stdio.h>\nint main() { printf(\"c\"); }\n").unwrap();
+        let options = RunOptions {
+            rerun_changed: Some(json_file),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 2.0);
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &Default::default());
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+    }
+
+    #[test]
+    fn run_rerun_changed_gated() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = |expected: &str| {
+            format!(
+                "
+source: main.c
+tests: [ {{ name: a, score: 1.0, stdout: {} }} ]
+analyses: [ {{ analyser: no-call, funs: [ system ], penalty: 0, gate: true, fail-score: -1.0 }} ]",
+                expected
+            )
+        };
+        std::fs::write(dir.path().join("config.yaml"), config("a")).unwrap();
+        std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+        std::fs::write(
+            dir.path().join("xlogin00").join("main.c"),
+            "#include <stdio.h>\n#include <stdlib.h>\n\
+             int main() { printf(\"b\"); system(\"true\"); }\n",
+        )
+        .unwrap();
+
+        let json_file = dir.path().join("results.json");
+        let options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], -1.0);
+
+        // The solution stays gated even though the changed test would pass
+        std::fs::write(dir.path().join("config.yaml"), config("b")).unwrap();
+        let options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            rerun_changed: Some(json_file.clone()),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], -1.0);
+        let results = read_results(&json_file).unwrap();
+        assert_eq!(results[0].status, "gated");
+        assert_eq!(results[0].tests[0].reason, Some(FailureReason::Gated));
+    }
+
+    #[test]
+    fn run_workdirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "
+source: main.c
+fixtures: { generated: $(echo input) }
+test-config: { max-workdir-bytes: 1000 }
+tests:
+  - { name: in/out, score: 1.0, stdin: '@generated', stdout: input }",
+        )
+        .unwrap();
+        let solution = dir.path().join("xlogin00");
+        std::fs::create_dir(&solution).unwrap();
+        std::fs::write(
+            solution.join("main.c"),
+            "#include <stdio.h>\nint main() { char s[8]; if (scanf(\"%7s\", s) == 1) \
+             printf(\"%s\\n\", s); }\n",
+        )
+        .unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let entries = |dir: &Path| {
+            let mut entries = dir
+                .read_dir()
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .collect::<Vec<PathBuf>>();
+            entries.sort();
+            entries
+        };
+
+        let mut options = RunOptions {
+            workdir_root: Some(root.path().to_path_buf()),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+        assert!(entries(root.path()).is_empty());
+
+        // A single directory of the run holds the case directories and the fixtures
+        options.keep_workdirs = true;
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+        let runs = entries(root.path());
+        assert_eq!(runs.len(), 1);
+        assert_eq!(
+            entries(&runs[0]),
+            vec![runs[0].join("fixtures"), runs[0].join("xlogin00")]
+        );
+        assert_eq!(
+            entries(&runs[0].join("xlogin00")),
+            vec![runs[0].join("xlogin00").join("in_out-0")]
+        );
     }
 
-    Ok(result)
+    #[test]
+    fn run_rerun_changed_late_penalty() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = |expected_b: &str| {
+            format!(
+                "
+source: main.c
+tests:
+  - {{ name: a, score: 1.0, stdout: a }}
+  - {{ name: b, score: 1.0, stdout: {} }}
+late-penalty: {{ deadline: 2000-01-01, per-day: 0.25, timestamp-source: file }}",
+                expected_b
+            )
+        };
+        std::fs::write(dir.path().join("config.yaml"), config("b")).unwrap();
+        let solution = dir.path().join("xlogin00");
+        std::fs::create_dir(&solution).unwrap();
+        std::fs::write(
+            solution.join("main.c"),
+            "#include <stdio.h>\nint main() { printf(\"a\"); }\n",
+        )
+        .unwrap();
+        // Submitted within the second day after the deadline (-50%)
+        std::fs::write(solution.join("submitted-at"), "2000-01-02 12:00").unwrap();
+
+        let json_file = dir.path().join("results.json");
+        let options = RunOptions {
+            outputs: vec![Output::Json(json_file.clone())],
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 0.5);
+        assert_eq!(
+            read_results(&json_file).unwrap()[0].late_penalty,
+            Some(LatePenaltyResult {
+                days: 2,
+                penalty: -0.5
+            })
+        );
+
+        // The penalty is applied to the new total, not added to the penalized score
+        std::fs::write(dir.path().join("config.yaml"), config("a")).unwrap();
+        let options = RunOptions {
+            rerun_changed: Some(json_file),
+            ..Default::default()
+        };
+        let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+        assert_eq!(res.unwrap()["xlogin00"], 1.0);
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use atst::run;
+use atst::{run_with_options, Config, ConsoleFilter, ErrorKind, ModuleKind, Output, RunOptions};
 use env_logger::Builder;
 use log::{error, LevelFilter};
 use std::io::Write;
@@ -16,25 +16,143 @@ struct Project {
     solution: String,
     #[structopt(short, long, parse(from_occurrences))]
     verbosity: u32,
+    /// Do not fail if there are no solutions to evaluate
+    #[structopt(long)]
+    allow_empty: bool,
+    /// Additional output to produce (findings:<path>, json:<path>, histogram:<path>,
+    /// metrics:<path>)
+    #[structopt(long, number_of_values = 1)]
+    output: Vec<Output>,
+    /// Show only N solutions with the best score
+    #[structopt(long, value_name = "N")]
+    top: Option<usize>,
+    /// Show only N solutions with the worst score
+    #[structopt(long, value_name = "N")]
+    bottom: Option<usize>,
+    /// Show only solutions which could not be evaluated, failed to compile,
+    /// or have a score below --fail-below
+    #[structopt(long)]
+    failures_only: bool,
+    /// Score threshold for --failures-only
+    #[structopt(long, default_value = "0", value_name = "SCORE")]
+    fail_below: f64,
+    /// Do not run the given module (compile, tests, analyses, coverage, scripts,
+    /// late-penalty)
+    #[structopt(long, number_of_values = 1, value_name = "MODULE")]
+    skip_module: Vec<ModuleKind>,
+    /// Re-run only the tests changed since the run which produced the given JSON results
+    #[structopt(long, parse(from_os_str), value_name = "RESULTS")]
+    rerun_changed: Option<PathBuf>,
+    /// Fail if some expected outputs look suspicious (e.g. have trailing whitespace)
+    #[structopt(long)]
+    strict: bool,
+    /// Seed of the run (to reproduce a previous run)
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Width of the buckets of the score histogram (in points)
+    #[structopt(long, default_value = "0.5", value_name = "POINTS", parse(try_from_str = positive))]
+    histogram_bucket: f64,
+    /// Number of solutions to evaluate in parallel (verbose runs evaluate them one by one)
+    #[structopt(short, long, default_value = "1", value_name = "N")]
+    jobs: usize,
+    /// Append a log of all programs run, analyser findings, and score changes to a file
+    #[structopt(long, parse(from_os_str), value_name = "PATH")]
+    audit: Option<PathBuf>,
+    /// Run compilers, tests, and scripts with the nice value increased by N (0-19) and
+    /// with the idle I/O priority (on Linux)
+    #[structopt(long, value_name = "N", parse(try_from_str = niceness))]
+    nice: Option<i32>,
+    /// Pause between solutions to keep the CPU busy at most PERCENT (1-100) of the time
+    #[structopt(long, value_name = "PERCENT", parse(try_from_str = percentage))]
+    throttle: Option<u32>,
+    /// Include source metrics of solutions (lines of code, functions, includes, etc.)
+    /// in the JSON results
+    #[structopt(long)]
+    metrics: bool,
+    /// Create working directories of test cases in DIR (the system temporary directory
+    /// by default)
+    #[structopt(long, parse(from_os_str), value_name = "DIR")]
+    workdir_root: Option<PathBuf>,
+    /// Keep working directories of test cases and generated fixtures (for debugging)
+    #[structopt(long)]
+    keep_workdirs: bool,
+    /// Only check the configuration and print its summary (no solution is evaluated)
+    #[structopt(long)]
+    check: bool,
+}
+
+fn positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(n) if n > 0.0 => Ok(n),
+        _ => Err(format!("'{}' is not a positive number", s)),
+    }
+}
+
+fn niceness(s: &str) -> Result<i32, String> {
+    match s.parse::<i32>() {
+        Ok(n) if (0..=19).contains(&n) => Ok(n),
+        _ => Err(format!("'{}' is not a nice value increment (0-19)", s)),
+    }
+}
+
+fn percentage(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(p) if (1..=100).contains(&p) => Ok(p),
+        _ => Err(format!("'{}' is not a percentage (1-100)", s)),
+    }
 }
 
 fn main() {
-    // Initialize logging (warnings + errors)
+    // Initialize logging (warnings + errors by default, can be overridden using RUST_LOG)
     Builder::new()
         .format(|buf, record| writeln!(buf, "{}: {}", record.level(), record.args()))
         .filter(None, LevelFilter::Warn)
+        .parse_default_env()
         .init();
 
     // Parse CLI arguments
     let project = Project::from_args();
+    if project.check {
+        match Config::validate(&project.config_file, &project.path) {
+            Ok(report) => print!("{}", report),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
     // Run the actual analysis
-    if let Err(e) = run(
-        &project.path,
-        &project.config_file,
-        &project.solution,
-        project.verbosity,
-    ) {
-        error!("{}", e);
-        std::process::exit(1);
+    let options = RunOptions {
+        only_solution: project.solution,
+        verbosity: project.verbosity,
+        outputs: project.output,
+        console: ConsoleFilter {
+            top: project.top,
+            bottom: project.bottom,
+            failures_only: project.failures_only,
+            fail_below: project.fail_below,
+        },
+        skip_modules: project.skip_module,
+        rerun_changed: project.rerun_changed,
+        strict: project.strict,
+        seed: project.seed,
+        histogram_bucket: Some(project.histogram_bucket),
+        jobs: project.jobs,
+        audit: project.audit,
+        nice: project.nice,
+        throttle: project.throttle,
+        metrics: project.metrics,
+        workdir_root: project.workdir_root,
+        keep_workdirs: project.keep_workdirs,
+        ..Default::default()
+    };
+    match run_with_options(&project.path, &project.config_file, &options) {
+        Err(e) if e.kind() == ErrorKind::NoSolutions && project.allow_empty => {}
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
     }
 }
@@ -1,94 +1,396 @@
-use crate::analyses::Analyser;
+use crate::analyses::{object_like_defines, source_metrics, string_literals};
+use crate::analyses::{Analyser, AnalyserFinding, Finding, Level, Severity};
+use crate::audit::{sha256, sha256_file, Execution};
+use crate::compare::{compose, fold_case, streams_equal_trimmed};
 use crate::config::Config;
-use crate::{AtstError, Solution};
-use crate::{Test, TestCasesRequirement};
+use crate::digest::Sha256;
+use crate::TestResult;
+use crate::{
+    parse_timestamp, AnalysesConfig, BinaryLimits, CoverageConfig, LatePenaltyConfig, PenaltyCurve,
+    Program, TimestampSource, WarningsPenalty,
+};
+use crate::{AtstError, CaseFailure, CaseResult, FailureReason, LatePenaltyResult, Solution};
+use crate::{Test, TestCase, TestCasesRequirement, TestConfig, TestOrder, Tolerance, Workspace};
+use log::{debug, info, trace, warn};
 use regex::Regex;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
 use std::fs::{read_to_string, remove_file, File};
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
 use wait_timeout::ChildExt;
 
 /// Modules are used to prepare or evaluate individual project solutions
 /// This trait is used to execute each module on a solution
-pub trait Module {
+/// Modules are shared by solutions evaluated in parallel.
+pub trait Module: Sync {
+    /// Name of the module used in error messages
+    fn name(&self) -> &str;
     fn execute(&self, solution: &mut Solution, verbosity: u32) -> Result<(), AtstError>;
 }
 
 /// C compiler
+/// Besides the main build, builds one executable for each distinct set of preprocessor
+/// defines required by the tests (see `defines_bin_file`) and an executable instrumented
+/// for coverage if coverage scoring is configured (see `coverage_bin_file`).
+/// The main executable is checked against `binary_limits`. Additional programs are built
+/// independently of the main executable (see `Program`). Warnings of the main build are
+/// penalized by `warnings_penalty`.
 pub struct Compiler {
     compiler: String,
     c_flags: String,
     ld_flags: String,
+    define_sets: Vec<Vec<(String, String)>>,
+    coverage: bool,
+    binary_limits: BinaryLimits,
+    programs: Vec<Program>,
+    warnings_penalty: WarningsPenalty,
 }
 
 impl Compiler {
     pub fn new(config: &Config) -> Self {
+        let mut define_sets = vec![];
+        for test in &config.tests {
+            if !test.defines.is_empty() && !define_sets.contains(&test.defines) {
+                define_sets.push(test.defines.clone());
+            }
+        }
         Self {
             compiler: config.compiler.clone().unwrap_or("gcc".to_string()),
             c_flags: config.c_flags.clone().unwrap_or_default(),
             ld_flags: config.ld_flags.clone().unwrap_or_default(),
+            define_sets,
+            coverage: config.coverage.is_some(),
+            binary_limits: config.binary_limits.clone(),
+            programs: config.programs.clone(),
+            warnings_penalty: config.warnings_penalty.clone(),
         }
     }
-}
 
-impl Module for Compiler {
-    fn execute(&self, solution: &mut Solution, _verbosity: u32) -> Result<(), AtstError> {
-        let _ = remove_file(solution.path.join(&solution.obj_file));
-        let _ = remove_file(solution.path.join(&solution.bin_file));
+    /// Build the additional program (each in a single compiler run)
+    /// Built programs are recorded in the solution, failures only make tests using the
+    /// program fail.
+    fn build_program(&self, solution: &mut Solution, program: &Program) -> Result<(), AtstError> {
+        let bin_file = PathBuf::from(&program.name);
+        let _ = remove_file(solution.path.join(&bin_file));
+        debug!(
+            "{}: building program {} from {}",
+            solution.name(),
+            program.name,
+            program.sources.join(" ")
+        );
+        let mut cc = solution.command(&self.compiler);
+        cc.args(self.c_flags.split_whitespace())
+            .args(program.c_flags.iter().flat_map(|f| f.split_whitespace()))
+            .args(["-o", &program.name])
+            .args(&program.sources)
+            .args(self.ld_flags.split_whitespace())
+            .args(program.ld_flags.iter().flat_map(|f| f.split_whitespace()))
+            .args(self.binary_limits.require_static.then_some("-static"))
+            .current_dir(&solution.path)
+            .stderr(Stdio::null());
+        let status = cc
+            .status()
+            .map_err(|_| AtstError::ExecError(self.compiler.clone()))?;
+        record_build(solution, "compile", &cc, status);
+        match status.success() {
+            true => {
+                solution.programs.insert(program.name.clone(), bin_file);
+            }
+            false => info!(
+                "{}: building program {} failed",
+                solution.name(),
+                program.name
+            ),
+        }
+        Ok(())
+    }
+
+    /// Check the executable `bin_file` against the limits, returns the violated ones
+    fn check_binary(&self, solution: &Solution, bin_file: &Path) -> Vec<String> {
+        let path = solution.path.join(bin_file);
+        let mut violations = vec![];
+        if let Some(max_bytes) = self.binary_limits.max_bytes {
+            let size = path.metadata().map(|m| m.len()).unwrap_or_default();
+            if size > max_bytes {
+                violations.push(format!(
+                    "executable too large ({} bytes, at most {} allowed)",
+                    size, max_bytes
+                ));
+            }
+        }
+        if self.binary_limits.require_static {
+            match elf_interpreter(&path) {
+                Ok(None) => {}
+                Ok(Some(interpreter)) => violations.push(format!(
+                    "executable is not statically linked (requires {})",
+                    interpreter
+                )),
+                Err(e) => violations.push(format!("cannot check linkage of executable: {}", e)),
+            }
+        }
+        violations
+    }
+
+    /// Build the solution into `bin_file` (using `obj_file` as the intermediate object file),
+    /// passing `defines` as -D flags to the compiler and `flags` to both compiler and linker.
+    /// Returns the error output of the compilation if the build succeeded, None otherwise.
+    fn build(
+        &self,
+        solution: &mut Solution,
+        defines: &[(String, String)],
+        flags: &[&str],
+        obj_file: &Path,
+        bin_file: &Path,
+    ) -> Result<Option<String>, AtstError> {
+        let _ = remove_file(solution.path.join(obj_file));
+        let _ = remove_file(solution.path.join(bin_file));
 
         // Compile .c -> .o
-        let mut cc = Command::new(&self.compiler);
+        debug!(
+            "{}: compiling {} using {} {}",
+            solution.name(),
+            solution.src_file.display(),
+            self.compiler,
+            self.c_flags
+        );
+        let mut cc = solution.command(&self.compiler);
         cc.args(self.c_flags.split_whitespace())
+            .args(defines.iter().map(|(name, value)| match value.is_empty() {
+                true => format!("-D{}", name),
+                false => format!("-D{}={}", name, value),
+            }))
+            .args(flags)
             .arg("-c")
-            .args(&["-o", &solution.obj_file.to_str().unwrap()])
+            .args(["-o", obj_file.to_str().unwrap()])
             .arg(&solution.src_file)
             .current_dir(&solution.path)
-            .stderr(Stdio::null());
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
 
-        if !cc
-            .status()
-            .map_err(|_| AtstError::ExecError(self.compiler.clone()))?
-            .success()
-        {
-            return Ok(());
+        let output = cc
+            .output()
+            .map_err(|_| AtstError::ExecError(self.compiler.clone()))?;
+        let status = output.status;
+        record_build(solution, "compile", &cc, status);
+        if !status.success() {
+            info!("{}: compilation failed", solution.name());
+            return Ok(None);
         }
 
         // Link .o -> executable
-        if !Command::new(&self.compiler)
-            .args(self.ld_flags.split_whitespace())
-            .args(&["-o", &solution.bin_file.to_str().unwrap()])
-            .args(&solution.obj_file)
+        debug!("{}: linking {}", solution.name(), bin_file.display());
+        let mut ld = solution.command(&self.compiler);
+        ld.args(self.ld_flags.split_whitespace())
+            .args(self.binary_limits.require_static.then_some("-static"))
+            .args(flags)
+            .args(["-o", bin_file.to_str().unwrap()])
+            .arg(obj_file)
             .current_dir(&solution.path)
-            .stderr(Stdio::null())
+            .stderr(Stdio::null());
+        let status = ld
             .status()
-            .map_err(|_| AtstError::ExecError(self.compiler.to_string()))?
-            .success()
-        {
+            .map_err(|_| AtstError::ExecError(self.compiler.to_string()))?;
+        record_build(solution, "link", &ld, status);
+        if !status.success() {
+            info!("{}: linking failed", solution.name());
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stderr).to_string()))
+    }
+}
+
+impl Module for Compiler {
+    fn name(&self) -> &str {
+        "compile"
+    }
+
+    fn execute(&self, solution: &mut Solution, _verbosity: u32) -> Result<(), AtstError> {
+        for program in &self.programs {
+            self.build_program(solution, program)?;
+        }
+
+        let (obj_file, bin_file) = (solution.obj_file.clone(), solution.bin_file.clone());
+        let stderr = match self.build(solution, &[], &[], &obj_file, &bin_file)? {
+            Some(stderr) => stderr,
+            None => return Ok(()),
+        };
+
+        let violations = self.check_binary(solution, &bin_file);
+        let severity = match self.binary_limits.penalty {
+            Some(penalty) => Severity::Penalty(penalty),
+            None => Severity::Report,
+        };
+        for message in &violations {
+            info!("{}: {}", solution.name(), message);
+            solution.findings.push(AnalyserFinding {
+                analyser: self.name().to_string(),
+                severity,
+                level: Level::Error,
+                finding: Finding::new(None, message.clone()),
+            });
+            solution.score += severity.penalty();
+        }
+        // Without a penalty, the build fails (tests are not run)
+        if !violations.is_empty() && self.binary_limits.penalty.is_none() {
+            info!("{}: executable rejected", solution.name());
+            let _ = remove_file(solution.path.join(&bin_file));
             return Ok(());
         }
 
-        // Compile again with -Werror to see if there are warnings
-        cc.arg("-Werror");
-        if !cc.status().unwrap().success() {
-            solution.score -= 0.5;
+        // Warnings are only penalized if the build succeeded (e.g. not with -Werror)
+        let warnings = count_warnings(&stderr);
+        solution.compiler_warnings = Some(warnings);
+        if warnings > 0 {
+            let penalty = match self.warnings_penalty.per_warning {
+                true => self.warnings_penalty.penalty * warnings as f64,
+                false => self.warnings_penalty.penalty,
+            };
+            info!(
+                "{}: compilation produced {} warning(s) ({})",
+                solution.name(),
+                warnings,
+                penalty
+            );
+            solution.score += penalty;
+        }
+
+        // Build executables with preprocessor defines required by tests
+        for defines in &self.define_sets {
+            let bin_file = defines_bin_file(&solution.bin_file, defines);
+            self.build(
+                solution,
+                defines,
+                &[],
+                &bin_file.with_extension("o"),
+                &bin_file,
+            )?;
+        }
+
+        // Build executable instrumented for coverage measurement
+        if self.coverage {
+            let bin_file = coverage_bin_file(&solution.bin_file);
+            let obj_file = bin_file.with_extension("o");
+            self.build(solution, &[], &["--coverage"], &obj_file, &bin_file)?;
         }
         Ok(())
     }
 }
 
+/// Number of warnings in the error output of the compiler
+/// Counts the lines of diagnostics of the form `<file>:<line>:<column>: warning: <message>`
+/// (as printed by GCC and Clang).
+fn count_warnings(stderr: &str) -> usize {
+    stderr.lines().filter(|l| l.contains(": warning: ")).count()
+}
+
+/// Record a run of the compiler or the linker `cmd` for the audit
+fn record_build(solution: &mut Solution, kind: &str, cmd: &Command, status: ExitStatus) {
+    if solution.audited() {
+        let mut execution = Execution::new(kind, cmd);
+        execution.exit_code = status.code();
+        execution.result = match status.success() {
+            true => "success",
+            false => "failure",
+        }
+        .to_string();
+        solution.record_execution(execution);
+    }
+}
+
+/// Program interpreter (the dynamic loader) requested by the ELF executable `file`
+/// None if the executable is statically linked, an error if it is not an ELF file.
+fn elf_interpreter(file: &Path) -> Result<Option<String>, String> {
+    const PT_INTERP: u64 = 3;
+    let data = std::fs::read(file).map_err(|e| e.to_string())?;
+    if !data.starts_with(b"\x7fELF") {
+        return Err("not an ELF file".to_string());
+    }
+    let is_64 = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err("invalid ELF class".to_string()),
+    };
+    let little_endian = match data.get(5) {
+        Some(1) => true,
+        Some(2) => false,
+        _ => return Err("invalid ELF data encoding".to_string()),
+    };
+    // Unsigned integer of `size` bytes at `offset`
+    let field = |offset: u64, size: u64| -> Result<u64, String> {
+        let bytes = usize::try_from(offset)
+            .ok()
+            .and_then(|start| data.get(start..start.checked_add(size as usize)?))
+            .ok_or("truncated ELF file")?;
+        Ok(match little_endian {
+            true => bytes.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64),
+            false => bytes.iter().fold(0, |acc, &b| acc << 8 | b as u64),
+        })
+    };
+
+    let (ph_offset, ph_entry_size, ph_count) = match is_64 {
+        true => (field(0x20, 8)?, field(0x36, 2)?, field(0x38, 2)?),
+        false => (field(0x1c, 4)?, field(0x2a, 2)?, field(0x2c, 2)?),
+    };
+    for i in 0..ph_count {
+        let header = ph_offset + i * ph_entry_size;
+        if field(header, 4)? != PT_INTERP {
+            continue;
+        }
+        let (offset, size) = match is_64 {
+            true => (field(header + 0x08, 8)?, field(header + 0x20, 8)?),
+            false => (field(header + 0x04, 4)?, field(header + 0x10, 4)?),
+        };
+        let interpreter = usize::try_from(offset)
+            .ok()
+            .and_then(|start| data.get(start..start.checked_add(size as usize)?))
+            .ok_or("truncated ELF file")?;
+        return Ok(Some(
+            String::from_utf8_lossy(interpreter)
+                .trim_end_matches('\0')
+                .to_string(),
+        ));
+    }
+    Ok(None)
+}
+
+/// Name of the executable instrumented for coverage measurement
+pub fn coverage_bin_file(bin_file: &Path) -> PathBuf {
+    PathBuf::from(format!("{}-coverage", bin_file.display()))
+}
+
+/// Name of the executable built with the given set of preprocessor defines
+/// The name is derived from the hash of the defines so that each set is built only once.
+pub fn defines_bin_file(bin_file: &Path, defines: &[(String, String)]) -> PathBuf {
+    if defines.is_empty() {
+        return bin_file.to_path_buf();
+    }
+    let mut hasher = DefaultHasher::new();
+    defines.hash(&mut hasher);
+    PathBuf::from(format!("{}-{:016x}", bin_file.display(), hasher.finish()))
+}
+
 /// Parsing the solution source files for later analyses
-/// Currently does 2 things:
+/// Currently does 3 things:
 ///   1. parses out names of the inlined headers and stores them in solution.included
-///   2. preprocesses the source file (except for the included headers) and stores its contents
+///   2. computes metrics of the source and stores them in solution.metrics
+///   3. preprocesses the source file (except for the included headers) and stores its contents
 ///      in solution.source
 pub struct Parser {}
 
 impl Module for Parser {
+    fn name(&self) -> &str {
+        "parse"
+    }
+
     fn execute(&self, solution: &mut Solution, _verbosity: u32) -> Result<(), AtstError> {
         // Run dos2unix to unify line endings and other stuff
-        let _ = Command::new("dos2unix")
+        let _ = solution
+            .command("dos2unix")
             .arg(solution.src_file.to_str().unwrap())
             .stderr(Stdio::null())
             .current_dir(&solution.path)
@@ -112,15 +414,50 @@ impl Module for Parser {
         for include in re.captures_iter(&src_lines) {
             solution.included.push(include[1].to_string());
         }
+        debug!(
+            "{}: included headers: {:?}",
+            solution.name(),
+            solution.included
+        );
 
-        // Preprocess the file (except for the included headers) and store its contents
-        let source_lines = src_lines
+        // Parse annotations suppressing findings of analysers on a line, e.g.
+        // `x = 1; /* atst:allow no-globals */`
+        let allow =
+            Regex::new(r"(?://|/\*)\s*atst:allow\s+([\w-]+(?:\s*,\s*[\w-]+)*)").map_err(|_| {
+                AtstError::InternalError {
+                    msg: "source parser regex error".to_string(),
+                }
+            })?;
+        solution.allowed = src_lines
             .lines()
-            .filter(|l| !re.is_match(l))
-            .fold(String::new(), |s, l| s + l + "\n");
+            .enumerate()
+            .filter_map(|(i, l)| allow.captures(l).map(|c| (i + 1, c[1].to_string())))
+            .flat_map(|(line, names)| {
+                names
+                    .split(',')
+                    .map(|name| (line, name.trim().to_string()))
+                    .collect::<Vec<(usize, String)>>()
+            })
+            .collect();
+
+        solution.defines = object_like_defines(&src_lines);
+        solution.metrics = Some(source_metrics(&src_lines)?);
+
+        // Preprocess the file (except for the included headers) and store its contents
+        // Remember numbers of the kept lines to map the preprocessed source back to them
+        let mut kept_lines = vec![];
+        let mut source_lines = String::with_capacity(src_lines.len() + 1);
+        for (i, l) in src_lines.lines().enumerate() {
+            if !re.is_match(l) {
+                kept_lines.push(i + 1);
+                source_lines.push_str(l);
+                source_lines.push('\n');
+            }
+        }
 
-        let mut gcc_cmd = Command::new("gcc")
-            .args(&["-E", "-"])
+        let mut gcc_cmd = solution
+            .command("gcc")
+            .args(["-E", "-"])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -139,13 +476,47 @@ impl Module for Parser {
             .map_err(|_| AtstError::InternalError {
                 msg: "preprocessor error".to_string(),
             })?;
-        solution.source = std::str::from_utf8(&output.stdout)
-            .map_err(|_| AtstError::InternalError {
+        let preprocessed =
+            std::str::from_utf8(&output.stdout).map_err(|_| AtstError::InternalError {
                 msg: "invalid preprocessor output".to_string(),
-            })?
-            .lines()
-            .filter(|l| !l.starts_with('#'))
-            .fold(String::new(), |s, l| s + l + "\n");
+            })?;
+
+        // Drop lines starting with '#', line markers (# <line> "<file>") are used
+        // to track the original lines
+        let marker = Regex::new(r#"^# (\d+) "(.*)""#).map_err(|_| AtstError::InternalError {
+            msg: "source parser regex error".to_string(),
+        })?;
+        let mut line = None;
+        solution.source = String::with_capacity(preprocessed.len());
+        solution.line_map = vec![];
+        solution.line_offsets = vec![0];
+        for l in preprocessed.lines() {
+            if let Some(m) = marker.captures(l) {
+                line = match &m[2] {
+                    "<stdin>" => m[1].parse::<usize>().ok(),
+                    _ => None,
+                };
+                continue;
+            }
+            if !l.starts_with('#') {
+                solution.source.push_str(l);
+                solution.source.push('\n');
+                solution.line_offsets.push(solution.source.len());
+                solution
+                    .line_map
+                    .push(line.and_then(|n| kept_lines.get(n - 1).copied()));
+            }
+            line = line.map(|n| n + 1);
+        }
+        solution.literals = string_literals(&solution.source)
+            .into_iter()
+            .map(|(offset, literal)| (solution.source_line(offset), literal))
+            .collect();
+        trace!(
+            "{}: preprocessed source:\n{}",
+            solution.name(),
+            solution.source
+        );
         Ok(())
     }
 }
@@ -153,131 +524,918 @@ impl Module for Parser {
 /// Running test cases
 pub struct TestExec<'t> {
     tests: &'t Vec<Test>,
-    timeout: u64,
+    config: &'t TestConfig,
 }
 
 impl<'t> TestExec<'t> {
-    pub fn new(tests: &'t Vec<Test>, timeout: u64) -> Self {
-        Self { tests, timeout }
+    pub fn new(tests: &'t Vec<Test>, config: &'t TestConfig) -> Self {
+        Self { tests, config }
+    }
+
+    /// Indices of the tests in the order in which they are run
+    fn execution_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tests.len()).collect();
+        if self.config.order == TestOrder::ByCost {
+            // The sort is stable, so tests with the same cost keep the config order
+            order.sort_by(|&a, &b| {
+                let cost = |i: usize| self.tests[i].cost_hint.unwrap_or(f64::INFINITY);
+                cost(a).total_cmp(&cost(b))
+            });
+        }
+        order
     }
 }
 
 impl<'t> Module for TestExec<'t> {
+    fn name(&self) -> &str {
+        "tests"
+    }
+
     fn execute(&self, solution: &mut Solution, verbosity: u32) -> Result<(), AtstError> {
-        // Make sure that the executable exists
-        let prog = solution.path.join(&solution.bin_file);
-        if !prog.exists() {
+        if solution.gate.is_some() {
+            solution.test_results.extend(
+                self.tests
+                    .iter()
+                    .map(|test| TestResult::new(test, 0.0, Some(FailureReason::Gated))),
+            );
             return Ok(());
         }
 
-        for test in self.tests {
+        // With a disk usage limit, each case is run in its own working directory (in the
+        // workspace of the run, if the solution is evaluated within one)
+        let workspace = match (self.config.max_workdir_bytes, &solution.workspace) {
+            (None, _) => None,
+            (Some(_), Some(workspace)) => Some(workspace.clone()),
+            (Some(_), None) => Some(Arc::new(Workspace::new(&std::env::temp_dir(), false)?)),
+        };
+
+        let overrides = solution.overrides.clone().unwrap_or_default();
+        let order = self.execution_order();
+        let first_result = solution.test_results.len();
+        for test in order.iter().map(|&i| &self.tests[i]) {
+            if overrides.excluded_tests.contains(&test.name) {
+                info!("{}: test '{}' excluded", solution.name(), test.name);
+                if verbosity > 0 {
+                    println!("  {}: skipped (excluded for the solution)", test.name);
+                }
+                solution.test_results.push(TestResult::new(
+                    test,
+                    0.0,
+                    Some(FailureReason::Skipped),
+                ));
+                continue;
+            }
+            // Tests of additional programs run them (as a pipeline if there are more of
+            // them), tests with preprocessor defines use a dedicated executable
+            let progs: Option<Vec<PathBuf>> = match test.programs.is_empty() {
+                true => Some(vec![solution
+                    .path
+                    .join(defines_bin_file(&solution.bin_file, &test.defines))]),
+                false => test
+                    .programs
+                    .iter()
+                    .map(|name| solution.program_file(name).map(|f| solution.path.join(f)))
+                    .collect(),
+            };
+            let (prog, upstream_progs) = match progs.as_ref().and_then(|p| p.split_last()) {
+                Some((prog, upstream)) if prog.exists() && upstream.iter().all(|p| p.exists()) => {
+                    (prog, upstream)
+                }
+                _ => {
+                    solution.test_results.push(TestResult::new(
+                        test,
+                        0.0,
+                        Some(FailureReason::CompileFail),
+                    ));
+                    continue;
+                }
+            };
+
             if verbosity > 0 {
-                print!("  {}: ", test.name);
+                match test.description.as_ref() {
+                    Some(description) => print!("  {} ({}): ", test.name, description),
+                    None => print!("  {}: ", test.name),
+                }
             }
             let mut cases_passed = 0;
-            for test_case in &test.test_cases {
+            let mut credits = vec![];
+            let mut partial_credit = false;
+            // Reason of the first case which was not passed
+            let mut reason = None;
+            let mut cases = vec![];
+            for (i, test_case) in test.test_cases.iter().enumerate() {
+                trace!(
+                    "{}: test '{}', case {}: running with args {:?}",
+                    solution.name(),
+                    test.name,
+                    i,
+                    test_case.args
+                );
+                let workdir = match &workspace {
+                    Some(w) => Some(w.dir(&solution.name(), &format!("{}-{}", test.name, i))?),
+                    None => None,
+                };
                 // Create process with correct arguments
-                let mut cmd = Command::new(prog.clone())
-                    .args(&test_case.args)
-                    .stdin(Stdio::piped())
+                // If an exec prefix is set, the binary is passed as its first argument
+                let command = |prog: &Path| {
+                    let mut cmd = match self.config.exec_prefix.split_first() {
+                        Some((launcher, launcher_args)) => {
+                            let mut c = solution.command(launcher);
+                            c.args(launcher_args).arg(prog);
+                            c
+                        }
+                        None => solution.command(prog),
+                    };
+                    if let Some(dir) = &workdir {
+                        cmd.current_dir(dir);
+                    }
+                    cmd.args(&test_case.args)
+                        .envs(test_case.env.iter().map(|(var, value)| (var, value)));
+                    cmd
+                };
+                let case_stdin = || -> Result<Stdio, AtstError> {
+                    Ok(match &test_case.stdin_file {
+                        Some(file) => Stdio::from(File::open(&file.path)?),
+                        None => Stdio::piped(),
+                    })
+                };
+                let spawn_error = |e: std::io::Error| match self.config.exec_prefix.first() {
+                    Some(launcher) => AtstError::ExecError(launcher.clone()),
+                    None => e.into(),
+                };
+                // In a pipeline, the input of the case is passed to the first program and the
+                // output of each program to the next one, the outputs of the last program are
+                // checked (errors of the other programs are ignored, as in a shell)
+                let mut upstream: Vec<(std::process::Child, Option<Execution>)> = vec![];
+                let mut pipe = None;
+                for upstream_prog in upstream_progs {
+                    let stdin = match pipe.take() {
+                        Some(pipe) => pipe,
+                        None => case_stdin()?,
+                    };
+                    let mut stage = command(upstream_prog);
+                    let stage_execution =
+                        solution.audited().then(|| Execution::new("test", &stage));
+                    let mut child = stage
+                        .stdin(stdin)
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::null())
+                        .spawn()
+                        .map_err(spawn_error)?;
+                    pipe = child.stdout.take().map(Stdio::from);
+                    upstream.push((child, stage_execution));
+                }
+                let stdin = match pipe {
+                    Some(pipe) => pipe,
+                    None => case_stdin()?,
+                };
+                let mut cmd = command(prog);
+                let mut execution = solution.audited().then(|| Execution::new("test", &cmd));
+                let mut cmd = cmd
+                    .stdin(stdin)
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped())
-                    .spawn()?;
+                    .spawn()
+                    .map_err(spawn_error)?;
+                // Large expected output (or its digest) is compared with the output while
+                // it is produced
+                let stdout_stream = match (&test_case.stdout_file, &test_case.stdout_sha256) {
+                    (None, None) => None,
+                    (file, digest) => {
+                        let output = cmd.stdout.take().ok_or(AtstError::InternalError {
+                            msg: "error getting output of a solution program".to_string(),
+                        })?;
+                        let path = file.as_ref().map(|f| f.path.clone());
+                        let digest = digest.clone().unwrap_or_default();
+                        Some(std::thread::spawn(move || match path {
+                            Some(path) => match_output_stream(output, &path),
+                            None => match_output_digest(output, &digest),
+                        }))
+                    }
+                };
 
                 if let Some(test_stdin) = test_case.stdin.as_ref() {
                     // Pass stdin to the process and capture its output
-                    let _ = cmd
-                        .stdin
-                        .as_mut()
+                    let input = match upstream.first_mut() {
+                        Some((first, _)) => first.stdin.as_mut(),
+                        None => cmd.stdin.as_mut(),
+                    };
+                    let _ = input
                         .ok_or(AtstError::InternalError {
                             msg: "error getting stdin of a solution program".to_string(),
                         })?
                         .write_all(test_stdin.as_bytes());
                 }
+                // Input of a pipeline is closed once it is written (the last program has
+                // its input closed when it is waited for)
+                for (child, _) in upstream.iter_mut() {
+                    drop(child.stdin.take());
+                }
 
-                let timeout = Duration::from_millis(self.timeout);
-                let _ = match cmd.wait_timeout(timeout)? {
-                    Some(code) => code.code(),
+                // Timeout of the case takes precedence over the global one
+                let timeout_ms = test_case
+                    .timeout
+                    .unwrap_or(self.config.timeout)
+                    .max(overrides.timeout.unwrap_or_default());
+                let timeout = Duration::from_millis(timeout_ms);
+                let mut timed_out = false;
+                let (exit_code, signal) = match cmd.wait_timeout(timeout)? {
+                    Some(status) => (status.code(), termination_signal(&status)),
                     None => {
+                        debug!(
+                            "{}: test '{}', case {}: timed out after {} ms",
+                            solution.name(),
+                            test.name,
+                            i,
+                            timeout_ms
+                        );
+                        timed_out = true;
                         cmd.kill()?;
-                        cmd.wait()?.code()
+                        cmd.wait()?;
+                        (None, None)
                     }
                 };
+                // Other programs of a pipeline are waited for (as in a shell), they are
+                // killed if the case timed out or if they do not finish in time
+                let mut stage_executions = vec![];
+                for (mut child, stage_execution) in upstream {
+                    let status = match timed_out {
+                        false => child.wait_timeout(timeout).ok().flatten(),
+                        true => None,
+                    };
+                    if status.is_none() {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                    }
+                    if let Some(mut execution) = stage_execution {
+                        execution.exit_code = status.and_then(|s| s.code());
+                        execution.result = match status {
+                            Some(status) if status.success() => "success",
+                            Some(_) => "failure",
+                            None => "TIMEOUT",
+                        }
+                        .to_string();
+                        stage_executions.push(execution);
+                    }
+                }
+                if let Some(signal) = signal {
+                    debug!(
+                        "{}: test '{}', case {}: killed by signal {}",
+                        solution.name(),
+                        test.name,
+                        i,
+                        signal
+                    );
+                }
 
-                if match_output(
-                    &mut cmd.stdout,
-                    &test_case.stdout,
-                    test_case.case_insensitive,
-                )? && match_output(
-                    &mut cmd.stderr,
-                    &test_case.stderr,
-                    test_case.case_insensitive,
-                )? {
+                let (stdout, stdout_mismatch) = match stdout_stream {
+                    Some(stream) => (
+                        String::new(),
+                        stream
+                            .join()
+                            .unwrap_or_else(|_| Err("output cannot be read".to_string()))
+                            .err(),
+                    ),
+                    None => (read_output(&mut cmd.stdout)?, None),
+                };
+                let stderr = read_output(&mut cmd.stderr)?;
+                // Files created over the limit are removed right away
+                let disk_exceeded = match (&workdir, self.config.max_workdir_bytes) {
+                    (Some(dir), Some(max_bytes)) if dir_size(dir) > max_bytes => {
+                        debug!(
+                            "{}: test '{}', case {}: disk usage limit exceeded",
+                            solution.name(),
+                            test.name,
+                            i
+                        );
+                        let _ = std::fs::remove_dir_all(dir);
+                        true
+                    }
+                    _ => false,
+                };
+                let exit_code_matches = match_exit_code(exit_code, &test_case.exit_code);
+                let stdout_matches = stdout_mismatch.is_none()
+                    && match_output(&stdout, &test_case.stdout, test_case);
+                let stderr_matches = match_output(&stderr, &test_case.stderr, test_case);
+                let credit = if disk_exceeded {
+                    0.0
+                } else if exit_code_matches && stdout_matches && stderr_matches {
+                    1.0
+                } else {
+                    // Output produced before timeout may be awarded a partial credit
+                    // if it is a prefix of the expected output
+                    match test.timeout_partial_credit {
+                        Some(fraction)
+                            if timed_out
+                                && match_output_prefix(&stdout, &test_case.stdout, test_case) =>
+                        {
+                            partial_credit = true;
+                            fraction
+                        }
+                        _ => 0.0,
+                    }
+                };
+                let case_failure = match exit_code {
+                    _ if credit == 1.0 => None,
+                    _ if disk_exceeded => Some(CaseFailure::DiskLimit),
+                    _ if timed_out => Some(CaseFailure::Timeout),
+                    None => Some(CaseFailure::Crash(signal)),
+                    _ if !exit_code_matches => Some(CaseFailure::WrongExitCode),
+                    _ if !stdout_matches => Some(CaseFailure::WrongStdout),
+                    _ => Some(CaseFailure::WrongStderr),
+                };
+                let case_reason = case_failure.map(|failure| failure.test_reason());
+                if reason.is_none() {
+                    reason = case_reason;
+                }
+                cases.push(CaseResult {
+                    reason: case_failure,
+                    message: case_failure.map(|failure| match failure {
+                        CaseFailure::WrongStdout => stdout_mismatch.clone().unwrap_or_else(|| {
+                            "output does not match the expected one".to_string()
+                        }),
+                        CaseFailure::WrongStderr => {
+                            "error output does not match the expected one".to_string()
+                        }
+                        CaseFailure::WrongExitCode => {
+                            exit_code_mismatch(exit_code, signal, &test_case.exit_code)
+                        }
+                        CaseFailure::Timeout => format!("timed out after {} ms", timeout_ms),
+                        CaseFailure::Crash(Some(signal)) => {
+                            format!("program killed by signal {}", signal)
+                        }
+                        CaseFailure::Crash(None) => "program crashed".to_string(),
+                        CaseFailure::DiskLimit => "disk usage limit exceeded".to_string(),
+                    }),
+                });
+                let input_hash = || match &test_case.stdin_file {
+                    Some(file) => sha256_file(&file.path),
+                    None => Some(sha256(test_case.stdin.as_deref().unwrap_or("").as_bytes())),
+                };
+                // Each program of a pipeline is recorded, the input of the case is passed
+                // to the first one
+                for (stage, mut execution) in stage_executions.into_iter().enumerate() {
+                    if stage == 0 {
+                        execution.input_hash = input_hash();
+                    }
+                    solution.record_execution(execution);
+                }
+                if let Some(mut execution) = execution.take() {
+                    if upstream_progs.is_empty() {
+                        execution.input_hash = input_hash();
+                    }
+                    // Streamed outputs are not kept
+                    if test_case.stdout_file.is_none() && test_case.stdout_sha256.is_none() {
+                        execution.stdout_hash = Some(sha256(stdout.as_bytes()));
+                    }
+                    execution.stderr_hash = Some(sha256(stderr.as_bytes()));
+                    execution.exit_code = exit_code;
+                    execution.result = match case_reason {
+                        None => "passed".to_string(),
+                        Some(reason) => reason.code().to_string(),
+                    };
+                    solution.record_execution(execution);
+                }
+                if disk_exceeded && verbosity > 1 {
+                    println!("    case {}: disk usage limit exceeded", i);
+                } else if credit < 1.0 && verbosity > 1 {
+                    if !timed_out && !match_exit_code(exit_code, &test_case.exit_code) {
+                        println!(
+                            "    case {}: {}",
+                            i,
+                            exit_code_mismatch(exit_code, signal, &test_case.exit_code)
+                        );
+                    }
+                    // Streamed outputs are not kept, so they cannot be diffed
+                    if let Some(mismatch) = stdout_mismatch.as_ref() {
+                        println!("    case {}: {}", i, mismatch);
+                    }
+                    if let Some(expected) = test_case.stdout.as_ref() {
+                        let (stdout, expected) = normalize(&stdout, expected, test_case);
+                        for line in
+                            output_diff(&stdout, &expected, test_case, self.config.max_diff_lines)
+                        {
+                            println!("    case {}: {}", i, line);
+                        }
+                    }
+                }
+                debug!(
+                    "{}: test '{}', case {}: {}",
+                    solution.name(),
+                    test.name,
+                    i,
+                    match credit {
+                        1.0 => "passed",
+                        c if c > 0.0 => "timeout (partial credit)",
+                        _ => "failed",
+                    }
+                );
+                if credit == 1.0 {
                     cases_passed += 1;
                 }
+                credits.push(credit);
             }
             // Award score if the requirement of passed cases is fulfilled
-            let test_passed = match test.requirement {
-                TestCasesRequirement::ALL => cases_passed == test.test_cases.len(),
-                TestCasesRequirement::ANY => cases_passed >= 1,
+            // Cases awarded a partial credit count as passed, the score is scaled down
+            let credit = match test.requirement {
+                TestCasesRequirement::ALL => credits.iter().cloned().fold(1.0, f64::min),
+                TestCasesRequirement::ANY => credits.iter().cloned().fold(0.0, f64::max),
             };
-            let test_score = if test_passed { test.score } else { 0.0 };
+            // Penalty tests (with a negative score) give their score if they fail
+            let test_score = match test.score < 0.0 {
+                true => test.score * (1.0 - credit),
+                false => test.score * credit,
+            };
+            info!(
+                "{}: test '{}': {}/{} cases passed, score {}",
+                solution.name(),
+                test.name,
+                cases_passed,
+                test.test_cases.len(),
+                test_score
+            );
             solution.score += test_score;
+            let mut result = TestResult::new(test, test_score, reason.filter(|_| credit < 1.0));
+            result.cases = cases;
             if verbosity > 0 {
-                println!("{}", test_score);
+                match partial_credit && credit > 0.0 && credit < 1.0 {
+                    true => println!("{} (timeout (partial credit))", test_score),
+                    false => println!("{}", test_score),
+                }
+                if let Some(hint) = result.hint.as_ref() {
+                    println!("    hint: {}", hint);
+                }
             }
+            solution.test_results.push(result);
+        }
+        // Results are kept in the order of the configuration, the order of the run is
+        // recorded in them
+        if self.config.order != TestOrder::Config {
+            let mut results: Vec<(usize, TestResult)> = order
+                .into_iter()
+                .zip(solution.test_results.drain(first_result..))
+                .enumerate()
+                .map(|(position, (index, mut result))| {
+                    result.order = Some(position);
+                    (index, result)
+                })
+                .collect();
+            results.sort_by_key(|(index, _)| *index);
+            solution
+                .test_results
+                .extend(results.into_iter().map(|(_, result)| result));
         }
         Ok(())
     }
 }
 
-fn match_output(
-    stream: &mut Option<impl Read>,
-    expected: &Option<String>,
-    case_insensitive: bool,
-) -> Result<bool, AtstError> {
-    if let Some(expected_output) = expected.as_ref() {
-        let mut output = String::new();
-        let _ = stream
-            .as_mut()
-            .ok_or(AtstError::InternalError {
-                msg: "error getting output of a solution program".to_string(),
-            })?
-            .read_to_string(&mut output);
+/// Check that the program exited with one of the `expected` codes (if any are given).
+/// Fails if the program did not exit normally (timed out or was killed by a signal).
+fn match_exit_code(exit_code: Option<i32>, expected: &Option<Vec<i64>>) -> bool {
+    match expected {
+        Some(codes) => exit_code.is_some_and(|c| codes.contains(&(c as i64))),
+        None => true,
+    }
+}
+
+/// Signal which terminated the program (if it was killed by one)
+#[cfg(unix)]
+fn termination_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn termination_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Description of an exit code which does not match the `expected` ones
+fn exit_code_mismatch(
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    expected: &Option<Vec<i64>>,
+) -> String {
+    let expected = expected
+        .iter()
+        .flatten()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" or ");
+    match (exit_code, signal) {
+        (Some(code), _) => format!("exit code {} (expected {})", code, expected),
+        (None, Some(signal)) => format!(
+            "program killed by signal {} (expected exit code {})",
+            signal, expected
+        ),
+        (None, None) => format!("program crashed (expected exit code {})", expected),
+    }
+}
+
+/// Total size of files in `dir` (including sub-directories, symlinks are not followed)
+fn dir_size(dir: &Path) -> u64 {
+    dir.read_dir()
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| match entry.file_type() {
+                    Ok(t) if t.is_dir() => dir_size(&entry.path()),
+                    _ => entry.metadata().map_or(0, |m| m.len()),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Read the whole contents of an output stream of a solution program
+fn read_output(stream: &mut Option<impl Read>) -> Result<String, AtstError> {
+    let mut output = String::new();
+    let _ = stream
+        .as_mut()
+        .ok_or(AtstError::InternalError {
+            msg: "error getting output of a solution program".to_string(),
+        })?
+        .read_to_string(&mut output);
+    Ok(output)
+}
+
+/// Compare an output stream of a solution program with the expected output in a file
+/// The rest of the output is read even if it does not match so that the program is
+/// not killed by a broken pipe.
+fn match_output_stream(mut output: impl Read, expected: &Path) -> Result<(), String> {
+    let matched = File::open(expected)
+        .and_then(|file| streams_equal_trimmed(&mut output, file))
+        .unwrap_or(false);
+    let _ = std::io::copy(&mut output, &mut std::io::sink());
+    match matched {
+        true => Ok(()),
+        false => Err(format!("output differs from {}", expected.display())),
+    }
+}
 
-        // TODO: do not ignore whitespace
-        output = output.trim().to_string();
-        let mut expected = expected_output.trim().to_string();
-        if case_insensitive {
-            output = output.to_lowercase();
-            expected = expected.to_lowercase();
+/// Compare the SHA-256 digest of an output stream of a solution program with `expected`
+/// On mismatch, the size and the digest of the obtained output are reported.
+fn match_output_digest(mut output: impl Read, expected: &str) -> Result<(), String> {
+    let mut digest = Sha256::default();
+    let mut buf = [0u8; 1 << 16];
+    loop {
+        match output.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => digest.update(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("output cannot be read: {}", e)),
         }
+    }
+    let size = digest.size();
+    match digest.finish() {
+        d if d == expected => Ok(()),
+        d => Err(format!("obtained {} bytes with SHA-256 {}", size, d)),
+    }
+}
 
-        return Ok(match expected.as_str() {
-            "*" => !output.is_empty(),
-            o => o == output,
+/// Normalize program output and expected output before comparison
+/// Normalizations are given by the test case and applied in this order:
+///   - leading and trailing whitespace is removed
+///   - trailing whitespace of each line is removed (if `ignore_trailing_whitespace` is set)
+///   - outputs are normalized to the Unicode NFC (if `normalize_unicode` is set)
+///   - case of the outputs is folded (if `case_insensitive` is set)
+///   - equivalent tokens are replaced by the first token of their group (if `equivalences`
+///     are set)
+///   - fields of each line are sorted (if `sort_fields` is set)
+///   - lines are sorted (if `unordered` is set)
+fn normalize(output: &str, expected: &str, test_case: &TestCase) -> (String, String) {
+    // TODO: do not ignore whitespace
+    let mut output = output.trim().to_string();
+    let mut expected = expected.trim().to_string();
+    if test_case.ignore_trailing_whitespace {
+        output = trim_lines_end(&output);
+        expected = trim_lines_end(&expected);
+    }
+    output = normalize_text(&output, test_case);
+    expected = normalize_text(&expected, test_case);
+    if let Some(groups) = test_case.equivalences.as_ref() {
+        output = canonicalize_tokens(&output, groups, test_case);
+        expected = canonicalize_tokens(&expected, groups, test_case);
+    }
+    if test_case.columns.is_some() {
+        output = join_columns(&output);
+        expected = join_columns(&expected);
+    }
+    if let Some(delimiter) = test_case.sort_fields.as_ref() {
+        output = sort_fields(&output, delimiter);
+        expected = sort_fields(&expected, delimiter);
+    }
+    if test_case.unordered {
+        output = sort_lines(&output);
+        expected = sort_lines(&expected);
+    }
+    (output, expected)
+}
+
+/// Sort lines of `s` (duplicate lines are kept)
+fn sort_lines(s: &str) -> String {
+    let mut lines: Vec<&str> = s.lines().collect();
+    lines.sort_unstable();
+    lines.join("\n")
+}
+
+/// Remove trailing whitespace of each line of `s` and the trailing blank lines
+fn trim_lines_end(s: &str) -> String {
+    let mut lines: Vec<&str> = s.lines().map(str::trim_end).collect();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Apply the Unicode normalizations of `test_case` to `s` (composition and case folding)
+fn normalize_text(s: &str, test_case: &TestCase) -> String {
+    let mut s = s.to_string();
+    if test_case.normalize_unicode {
+        s = compose(&s);
+    }
+    if test_case.case_insensitive {
+        s = fold_case(&s);
+    }
+    s
+}
+
+/// Replace each whitespace-delimited token of `s` which belongs to one of the `groups`
+/// by the first token of the group. `s` is expected to be normalized already (see
+/// `normalize_text`), the tokens of the groups are normalized before matching.
+fn canonicalize_tokens(s: &str, groups: &[Vec<String>], test_case: &TestCase) -> String {
+    let re = Regex::new(r"\S+").unwrap();
+    re.replace_all(s, |caps: &regex::Captures| {
+        let token = &caps[0];
+        groups
+            .iter()
+            .find(|group| group.iter().any(|t| normalize_text(t, test_case) == token))
+            .map_or(token.to_string(), |group| group[0].clone())
+    })
+    .into_owned()
+}
+
+/// Describe differences between the lines of `output` and `expected` output
+/// At most `max_lines` differing lines are described, the remaining differences are only
+/// counted so that huge outputs do not slow down the evaluation nor flood the report.
+fn output_diff(
+    output: &str,
+    expected: &str,
+    test_case: &TestCase,
+    max_lines: usize,
+) -> Vec<String> {
+    if expected == "*" {
+        return vec![];
+    }
+    let mut output_lines = output.lines();
+    let mut expected_lines = expected.lines();
+    let mut result = vec![];
+    let mut more = 0;
+    for line in 1.. {
+        let (out, exp) = match (output_lines.next(), expected_lines.next()) {
+            (None, None) => break,
+            (Some(out), Some(exp)) if match_line(out, exp, test_case) => continue,
+            lines => lines,
+        };
+        if result.len() == max_lines {
+            more += 1;
+            continue;
+        }
+        result.push(match (out, exp) {
+            (Some(out), Some(exp)) => format!("line {}: expected '{}', got '{}'", line, exp, out),
+            (Some(out), None) => format!("line {}: unexpected '{}'", line, out),
+            (None, Some(exp)) => format!("line {}: missing '{}'", line, exp),
+            (None, None) => unreachable!(),
         });
     }
-    Ok(true)
+    if more > 0 {
+        result.push(format!("and {} more differences", more));
+    }
+    result
+}
+
+/// Sort fields separated by `delimiter` within each line of `text`
+/// Fields are trimmed, a whitespace-only delimiter splits on any whitespace.
+fn sort_fields(text: &str, delimiter: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut fields: Vec<&str> = match delimiter.trim().is_empty() {
+                true => line.split_whitespace().collect(),
+                false => line.split(delimiter).map(str::trim).collect(),
+            };
+            fields.sort_unstable();
+            fields.join(delimiter)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Split each line of `text` into whitespace-delimited columns and join them by single spaces
+fn join_columns(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Compare a (normalized) line of the output with the expected one
+/// If the test case compares columns, numeric columns may differ by the column tolerance
+/// (the last tolerance applies to all the remaining columns). If the test case has
+/// a tolerance, numeric tokens of the line may differ by it.
+fn match_line(output: &str, expected: &str, test_case: &TestCase) -> bool {
+    match test_case.columns.as_ref() {
+        Some(tolerances) if !tolerances.is_empty() => {
+            let output: Vec<&str> = output.split(' ').collect();
+            let expected: Vec<&str> = expected.split(' ').collect();
+            output.len() == expected.len()
+                && output
+                    .iter()
+                    .zip(expected.iter())
+                    .enumerate()
+                    .all(|(i, (o, e))| {
+                        let tolerance = tolerances.get(i).or(tolerances.last()).unwrap();
+                        o == e
+                            || match (o.parse::<f64>(), e.parse::<f64>()) {
+                                (Ok(o), Ok(e)) => (o - e).abs() <= *tolerance,
+                                _ => false,
+                            }
+                    })
+        }
+        _ => match test_case.tolerance.as_ref() {
+            Some(tolerance) => match_tokens(output, expected, tolerance),
+            None => output == expected,
+        },
+    }
+}
+
+/// Compare whitespace-delimited tokens of `output` with those of `expected`
+/// Numeric tokens may differ by `tolerance`, other tokens must be identical.
+fn match_tokens(output: &str, expected: &str, tolerance: &Tolerance) -> bool {
+    let output: Vec<&str> = output.split_whitespace().collect();
+    let expected: Vec<&str> = expected.split_whitespace().collect();
+    output.len() == expected.len()
+        && output.iter().zip(expected.iter()).all(|(o, e)| {
+            o == e
+                || match (o.parse::<f64>(), e.parse::<f64>()) {
+                    (Ok(o), Ok(e)) => tolerance.matches(o, e),
+                    _ => false,
+                }
+        })
+}
+
+fn match_output(output: &str, expected: &Option<String>, test_case: &TestCase) -> bool {
+    if let Some(expected_output) = expected.as_ref() {
+        let (output, expected) = normalize(output, expected_output, test_case);
+        return match expected.as_str() {
+            "*" => !output.is_empty(),
+            o if test_case.columns.is_some() => {
+                o.lines().count() == output.lines().count()
+                    && o.lines()
+                        .zip(output.lines())
+                        .all(|(e, out)| match_line(out, e, test_case))
+            }
+            o => match test_case.tolerance.as_ref() {
+                // Tokens are compared regardless of the lines they are on
+                Some(tolerance) => match_tokens(&output, o, tolerance),
+                None => o == output,
+            },
+        };
+    }
+    true
+}
+
+/// Check that a (non-empty) partial output is a prefix of the expected output
+fn match_output_prefix(output: &str, expected: &Option<String>, test_case: &TestCase) -> bool {
+    match expected.as_ref() {
+        Some(expected_output) => {
+            let (output, expected) = normalize(output, expected_output, test_case);
+            !output.is_empty() && expected != "*" && expected.starts_with(&output)
+        }
+        None => false,
+    }
 }
 
 /// Running source analyses
+/// Sources larger than `config.max_source_bytes` are not analysed (so that huge generated
+/// sources do not slow down the evaluation), a finding is recorded for them instead.
 pub struct AnalysesExec<'a> {
     analysers: &'a Vec<Box<dyn Analyser>>,
+    config: &'a AnalysesConfig,
 }
 
 impl<'a> AnalysesExec<'a> {
-    pub fn new(analysers: &'a Vec<Box<dyn Analyser>>) -> Self {
-        Self { analysers }
+    pub fn new(analysers: &'a Vec<Box<dyn Analyser>>, config: &'a AnalysesConfig) -> Self {
+        Self { analysers, config }
     }
 }
 
 impl<'a> Module for AnalysesExec<'a> {
-    fn execute(&self, solution: &mut Solution, _verbosity: u32) -> Result<(), AtstError> {
+    fn name(&self) -> &str {
+        "analyses"
+    }
+
+    fn execute(&self, solution: &mut Solution, verbosity: u32) -> Result<(), AtstError> {
+        let size = solution.source.len() as u64;
+        if !self.analysers.is_empty() && size > self.config.max_source_bytes {
+            let message = format!(
+                "source too large for analysis ({} bytes, at most {} allowed)",
+                size, self.config.max_source_bytes
+            );
+            warn!("{}: {}", solution.name(), message);
+            if verbosity > 0 {
+                println!("  {}", message);
+            }
+            let severity = match self.config.oversize_penalty {
+                Some(penalty) => Severity::Penalty(penalty),
+                None => Severity::Report,
+            };
+            solution.findings.push(AnalyserFinding {
+                analyser: self.name().to_string(),
+                severity,
+                level: Level::Error,
+                finding: Finding::new(None, message),
+            });
+            solution.score += severity.penalty();
+            return Ok(());
+        }
         for analysis in self.analysers {
-            if analysis.analyse(solution)? {
-                solution.score += analysis.penalty();
+            let allowed = |line: &Option<usize>| {
+                solution
+                    .allowed
+                    .iter()
+                    .any(|(l, name)| Some(*l) == *line && name == analysis.name())
+            };
+            let findings: Vec<Finding> = analysis
+                .analyse(solution)?
+                .into_iter()
+                .filter(|finding| !allowed(&finding.line))
+                .collect();
+            solution
+                .findings
+                .extend(findings.iter().map(|finding| AnalyserFinding {
+                    analyser: analysis.name().to_string(),
+                    severity: analysis.severity(),
+                    level: analysis.level(),
+                    finding: finding.clone(),
+                }));
+            if findings.is_empty() {
+                debug!("{}: analyser {} passed", solution.name(), analysis.name());
+                continue;
+            }
+
+            if let Some(fail_score) = analysis.gate() {
+                let reason = format!("{}: {}", analysis.name(), findings[0].message);
+                info!("{}: tests gated by {}", solution.name(), reason);
+                if verbosity > 0 {
+                    println!("  {} (tests are not run)", reason);
+                }
+                solution.gate.get_or_insert((reason, fail_score));
+                continue;
             }
+
+            let penalty = analysis.penalty_for(&findings);
+            info!(
+                "{}: analyser {} matched, penalty {}",
+                solution.name(),
+                analysis.name(),
+                penalty
+            );
+            if verbosity > 0 {
+                println!("  {}: {}", analysis.name(), penalty);
+            }
+            for finding in &findings {
+                let line = finding.line.map(|l| format!("line {}: ", l));
+                debug!(
+                    "{}: analyser {}: {}{}",
+                    solution.name(),
+                    analysis.name(),
+                    line.as_deref().unwrap_or_default(),
+                    finding.message
+                );
+                if verbosity > 1 {
+                    println!(
+                        "    {}: {}{}",
+                        analysis.level(),
+                        line.unwrap_or_default(),
+                        finding.message
+                    );
+                }
+            }
+            solution.score += penalty;
+        }
+        if verbosity > 0 && !solution.findings.is_empty() {
+            let count = |level| {
+                solution
+                    .findings
+                    .iter()
+                    .filter(|f| f.level == level)
+                    .count()
+            };
+            println!(
+                "  findings: {} errors, {} warnings",
+                count(Level::Error),
+                count(Level::Warning)
+            );
         }
         Ok(())
     }
@@ -290,15 +1448,21 @@ pub struct ScriptExec {
 }
 
 impl ScriptExec {
-    pub fn new(script_path: &PathBuf) -> Self {
+    pub fn new(script_path: &Path) -> Self {
         Self {
-            script_path: script_path.clone(),
+            script_path: script_path.to_path_buf(),
         }
     }
 }
 
 impl Module for ScriptExec {
+    fn name(&self) -> &str {
+        "scripts"
+    }
+
     /// Just run the script inside the solution directory.
+    /// The score of the solution from the preceding modules is passed to the script in the
+    /// `ATST_SCORE_SO_FAR` environment variable.
     /// If the script produces a log file (expected format: <script-name>.log), read it and for all
     /// lines starting with <number>:, add <number> to the total score of the solution.
     fn execute(&self, solution: &mut Solution, _verbosity: u32) -> Result<(), AtstError> {
@@ -306,39 +1470,248 @@ impl Module for ScriptExec {
 
         let script = self.script_path.canonicalize().unwrap();
         let script_path = script.to_str().unwrap().to_string();
-        Command::new(script)
+        debug!("{}: running script {}", solution.name(), script_path);
+        solution
+            .command(script)
             .current_dir(&solution.path)
+            .env("ATST_SCORE_SO_FAR", solution.score.to_string())
             .status()
             .map_err(|_| AtstError::ExecError(script_path))?;
 
         // Read the log file, if one is produced
         let log_file = solution.path.join(format!("{}.log", script_name));
         for line in read_to_string(log_file).unwrap_or_default().lines() {
-            match line.split(':').nth(0).unwrap_or_default().parse::<f64>() {
-                Ok(n) => solution.score += n,
-                _ => {}
+            if let Ok(n) = line.split(':').next().unwrap_or_default().parse::<f64>() {
+                info!("{}: script {}: {}", solution.name(), script_name, line);
+                solution.score += n;
             }
         }
         Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_utils::get_solution;
-    use crate::{TestCase, TestCasesRequirement, DEFAULT_TEST_TIMEOUT};
+/// Scoring of the solution based on line coverage achieved by the tests written by the
+/// student (see `CoverageConfig`). Runs the instrumented executable on each test input
+/// and evaluates the coverage using gcov. If gcov is not available, the module is skipped.
+pub struct CoverageExec<'c> {
+    config: &'c CoverageConfig,
+    test_config: &'c TestConfig,
+}
 
-    #[test]
-    fn compiler_module_ok() {
-        let compiler = Compiler {
-            compiler: "gcc".to_string(),
-            c_flags: "-std=c99 -Wall -Wextra".to_string(),
-            ld_flags: String::new(),
-        };
+impl<'c> CoverageExec<'c> {
+    pub fn new(config: &'c CoverageConfig, test_config: &'c TestConfig) -> Self {
+        Self {
+            config,
+            test_config,
+        }
+    }
 
-        let src = "int main() {}";
-        let mut solution = get_solution(src, false);
+    /// Run the instrumented program `prog` with the contents of `input` on stdin
+    fn run_test(&self, solution: &Solution, prog: &Path, input: &Path) -> Result<(), AtstError> {
+        let mut cmd = solution
+            .command(prog)
+            .stdin(File::open(input)?)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .current_dir(&solution.path)
+            .spawn()?;
+        if cmd
+            .wait_timeout(Duration::from_millis(self.test_config.timeout))?
+            .is_none()
+        {
+            debug!(
+                "{}: coverage test {} timed out",
+                solution.name(),
+                input.display()
+            );
+            cmd.kill()?;
+            cmd.wait()?;
+        }
+        Ok(())
+    }
+
+    /// Get line coverage (in percent) of the solution source from gcov
+    fn line_coverage(&self, solution: &Solution, obj_file: &Path) -> Result<f64, AtstError> {
+        let output = solution
+            .command("gcov")
+            .arg("-n")
+            .arg("-o")
+            .arg(obj_file)
+            .arg(&solution.src_file)
+            .current_dir(&solution.path)
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|_| AtstError::ExecError("gcov".to_string()))?;
+        let re =
+            Regex::new(r"Lines executed:\s*([\d.]+)%").map_err(|_| AtstError::InternalError {
+                msg: "coverage regex error".to_string(),
+            })?;
+        Ok(re
+            .captures(&String::from_utf8_lossy(&output.stdout))
+            .and_then(|c| c[1].parse::<f64>().ok())
+            .unwrap_or(0.0))
+    }
+}
+
+impl<'c> Module for CoverageExec<'c> {
+    fn name(&self) -> &str {
+        "coverage"
+    }
+
+    fn execute(&self, solution: &mut Solution, verbosity: u32) -> Result<(), AtstError> {
+        if solution.gate.is_some() {
+            return Ok(());
+        }
+        if Command::new("gcov")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .status()
+            .is_err()
+        {
+            warn!("gcov not found, coverage is not evaluated");
+            return Ok(());
+        }
+
+        let prog = solution.path.join(coverage_bin_file(&solution.bin_file));
+        let obj_file = coverage_bin_file(&solution.bin_file).with_extension("o");
+        let _ = remove_file(solution.path.join(obj_file.with_extension("gcda")));
+
+        let mut inputs: Vec<PathBuf> = match solution.path.join(&self.config.tests_dir).read_dir() {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "in"))
+                .collect(),
+            Err(_) => vec![],
+        };
+        inputs.sort();
+
+        let coverage = match prog.exists() && !inputs.is_empty() {
+            true => {
+                for input in &inputs {
+                    self.run_test(solution, &prog, input)?;
+                }
+                self.line_coverage(solution, &obj_file)?
+            }
+            false => 0.0,
+        };
+        let score = self.config.score * (coverage / self.config.threshold).min(1.0);
+        info!(
+            "{}: line coverage {}% by {} test(s), score {}",
+            solution.name(),
+            coverage,
+            inputs.len(),
+            score
+        );
+        if verbosity > 0 {
+            println!("  coverage ({}%): {}", coverage, score);
+        }
+        solution.score += score;
+        Ok(())
+    }
+}
+
+/// Penalty for submissions after the deadline
+/// Must be run after all other modules since it scales down the total score.
+pub struct LatePenalty<'c> {
+    config: &'c LatePenaltyConfig,
+}
+
+impl<'c> LatePenalty<'c> {
+    pub fn new(config: &'c LatePenaltyConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the submission timestamp of the solution (None if it cannot be determined)
+    fn submitted_at(&self, solution: &Solution) -> Option<i64> {
+        match &self.config.timestamp {
+            TimestampSource::File(file) => {
+                parse_timestamp(&read_to_string(solution.path.join(file)).ok()?)
+            }
+            TimestampSource::SourceMtime => solution
+                .path
+                .join(&solution.src_file)
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs() as i64),
+        }
+    }
+}
+
+impl<'c> Module for LatePenalty<'c> {
+    fn name(&self) -> &str {
+        "late-penalty"
+    }
+
+    fn execute(&self, solution: &mut Solution, verbosity: u32) -> Result<(), AtstError> {
+        let submitted_at = match self.submitted_at(solution) {
+            Some(ts) => ts,
+            None => {
+                warn!(
+                    "{}: could not determine submission time, no late penalty applied",
+                    solution.name()
+                );
+                return Ok(());
+            }
+        };
+        if submitted_at <= self.config.deadline {
+            return Ok(());
+        }
+
+        // Each started day counts
+        let days_late = (submitted_at - self.config.deadline + 86399) / 86400;
+        let factor = match self.config.curve {
+            PenaltyCurve::Linear => 1.0 - self.config.per_day * days_late as f64,
+            PenaltyCurve::Exponential => (1.0 - self.config.per_day).powi(days_late as i32),
+        };
+        // Only positive scores are reduced, a late submission never gains points
+        let score = match solution.score > 0.0 {
+            true => solution.score * factor.max(0.0),
+            false => solution.score,
+        };
+        let penalty = (score - solution.score).min(0.0);
+        info!(
+            "{}: submitted {} day(s) late, penalty {}",
+            solution.name(),
+            days_late,
+            penalty
+        );
+        if verbosity > 0 {
+            println!("  late penalty ({} days): {}", days_late, penalty);
+        }
+        solution.score = score;
+        solution.late_penalty = Some(LatePenaltyResult {
+            days: days_late as u64,
+            penalty,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyses::{ConfiguredAnalyser, NoCallAnalyser, NoGlobalsAnalyser};
+    use crate::test_utils::get_solution;
+    use crate::{DataFile, TestCase, TestCasesRequirement};
+
+    #[test]
+    fn compiler_module_ok() {
+        let compiler = Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: "-std=c99 -Wall -Wextra".to_string(),
+            ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
+        };
+
+        let src = "int main() {}";
+        let mut solution = get_solution(src, false);
 
         let res = compiler.execute(&mut solution, 0);
         assert!(res.is_ok());
@@ -354,6 +1727,11 @@ mod tests {
             compiler: "gcc".to_string(),
             c_flags: "-std=c99 -Wall -Wextra".to_string(),
             ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
         };
 
         let src = "int main(int argc, char** argv) {}";
@@ -362,10 +1740,62 @@ mod tests {
         let res = compiler.execute(&mut solution, 0);
         assert!(res.is_ok());
 
-        assert!(solution.path.join(solution.obj_file).exists());
-        assert!(solution.path.join(solution.bin_file).exists());
+        assert!(solution.path.join(&solution.obj_file).exists());
+        assert!(solution.path.join(&solution.bin_file).exists());
         // Compilation with warning should subtract 0.5 pts from score
         assert_eq!(solution.score, -0.5);
+        assert_eq!(solution.compiler_warnings, Some(2));
+    }
+
+    #[test]
+    fn compiler_module_warnings_penalty() {
+        let compiler = |c_flags: &str, per_warning: bool| Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: c_flags.to_string(),
+            ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty {
+                penalty: -0.2,
+                per_warning,
+            },
+        };
+        let src = "int main() {\n    int x;\n    int y;\n    return 0;\n}";
+
+        // Penalized once
+        let mut solution = get_solution(src, false);
+        assert!(compiler("-Wunused-variable", false)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert_eq!(solution.compiler_warnings, Some(2));
+        assert_eq!(solution.score, -0.2);
+
+        // Penalized for each warning
+        let mut solution = get_solution(src, false);
+        assert!(compiler("-Wunused-variable", true)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert_eq!(solution.compiler_warnings, Some(2));
+        assert!((solution.score + 0.4).abs() < 1e-9);
+
+        // With -Werror, the build fails and warnings are not penalized
+        let mut solution = get_solution(src, false);
+        assert!(compiler("-Wunused-variable -Werror", true)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert!(!solution.path.join(&solution.bin_file).exists());
+        assert_eq!(solution.compiler_warnings, None);
+        assert_eq!(solution.score, 0.0);
+
+        // No warnings
+        let mut solution = get_solution("int main() { return 0; }", false);
+        assert!(compiler("-Wunused-variable", true)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert_eq!(solution.compiler_warnings, Some(0));
+        assert_eq!(solution.score, 0.0);
     }
 
     #[test]
@@ -374,6 +1804,11 @@ mod tests {
             compiler: "gcc".to_string(),
             c_flags: "-std=c99 -Wall -Wextra".to_string(),
             ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
         };
 
         let src = "int main() { notype x = 0; }";
@@ -388,6 +1823,90 @@ mod tests {
         assert_eq!(solution.score, 0.0);
     }
 
+    #[test]
+    fn compiler_module_binary_limits() {
+        let compiler = |limits: BinaryLimits| Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: String::new(),
+            ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: limits,
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
+        };
+        let src = "int main() { return 0; }";
+
+        // Penalized for each violated limit
+        let mut solution = get_solution(src, false);
+        let limits = BinaryLimits {
+            max_bytes: Some(100),
+            penalty: Some(-1.0),
+            ..Default::default()
+        };
+        assert!(compiler(limits).execute(&mut solution, 0).is_ok());
+        assert!(solution.path.join(&solution.bin_file).exists());
+        assert_eq!(solution.score, -1.0);
+        assert_eq!(solution.findings.len(), 1);
+        assert_eq!(solution.findings[0].analyser, "compile");
+        assert!(solution.findings[0]
+            .finding
+            .message
+            .starts_with("executable too large ("));
+
+        // Without a penalty, the executable is rejected
+        let mut solution = get_solution(src, false);
+        let limits = BinaryLimits {
+            max_bytes: Some(100),
+            ..Default::default()
+        };
+        assert!(compiler(limits).execute(&mut solution, 0).is_ok());
+        assert!(!solution.path.join(&solution.bin_file).exists());
+        assert_eq!(solution.score, 0.0);
+        assert_eq!(solution.findings[0].severity, Severity::Report);
+
+        // Linked statically on request
+        let mut solution = get_solution(src, false);
+        let limits = BinaryLimits {
+            max_bytes: Some(100 << 20),
+            require_static: true,
+            penalty: None,
+        };
+        assert!(compiler(limits).execute(&mut solution, 0).is_ok());
+        let bin_file = solution.path.join(&solution.bin_file);
+        assert_eq!(elf_interpreter(&bin_file), Ok(None));
+        assert!(solution.findings.is_empty());
+    }
+
+    #[test]
+    fn elf_interpreter_of_binaries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.c"), "int main() { return 0; }").unwrap();
+        for (flags, name) in [(vec![], "dynamic"), (vec!["-static"], "static")] {
+            let status = Command::new("gcc")
+                .args(flags)
+                .args(["-o", name, "main.c"])
+                .current_dir(dir.path())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        let interpreter = elf_interpreter(&dir.path().join("dynamic")).unwrap();
+        assert!(interpreter.is_some_and(|i| i.contains("ld")));
+        assert_eq!(elf_interpreter(&dir.path().join("static")), Ok(None));
+
+        assert_eq!(
+            elf_interpreter(&dir.path().join("main.c")),
+            Err("not an ELF file".to_string())
+        );
+        std::fs::write(dir.path().join("truncated"), b"\x7fELF\x02\x01").unwrap();
+        assert_eq!(
+            elf_interpreter(&dir.path().join("truncated")),
+            Err("truncated ELF file".to_string())
+        );
+        assert!(elf_interpreter(&dir.path().join("missing")).is_err());
+    }
+
     #[test]
     fn parser_module() {
         let parser = Parser {};
@@ -405,6 +1924,122 @@ int main() {
         assert!(res.is_ok());
         assert_eq!(solution.included, vec!["foo.h"]);
         assert_eq!(solution.source, "\nint x;\nint main() {\n    x = 5;\n}\n");
+        assert_eq!(solution.source_line(1), Some(3));
+        assert_eq!(
+            solution.source_line(solution.source.find("x =").unwrap()),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn parser_large_source() {
+        // Generated source of a few MB with many literals and macros
+        let lines = 50_000;
+        let mut src = String::from("#include <stdio.h>\n");
+        for i in 0..lines {
+            src += &format!("#define MSG_{} \"generated message number {}\"\n", i, i);
+        }
+        src += "int main() {\n";
+        for i in 0..lines {
+            src += &format!("    puts(MSG_{}); puts(\"literal {}\");\n", i, i);
+        }
+        src += "}\n";
+        let mut solution = get_solution(&src, false);
+
+        let start = std::time::Instant::now();
+        assert!(Parser {}.execute(&mut solution, 0).is_ok());
+        // Coarse bound, the parsing takes a fraction of it (quadratic parsing takes minutes)
+        assert!(start.elapsed() < Duration::from_secs(20));
+        assert_eq!(solution.defines.len(), lines);
+        assert_eq!(solution.literals.len(), 2 * lines);
+        assert_eq!(
+            solution.literals.last(),
+            Some(&(Some(2 * lines + 2), format!("literal {}", lines - 1)))
+        );
+    }
+
+    #[test]
+    fn parser_literals_and_defines() {
+        let src = r#"#include <stdio.h>
+#define MAX 10 // maximal count
+#define MSG "Total: " \
+            "%d\n"
+#define SQUARE(x) ((x) * (x))
+#define EMPTY
+#define MAX 20
+int main() {
+    printf(MSG, MAX);
+    wprintf(L"wide\t\"string\"\n");
+    puts("a\\b" /* comment */ "c");
+}"#;
+        let mut solution = get_solution(src, false);
+        assert!(Parser {}.execute(&mut solution, 0).is_ok());
+        assert_eq!(
+            solution.defines,
+            vec![
+                ("MAX".to_string(), "20".to_string()),
+                ("MSG".to_string(), r#""Total: " "%d\n""#.to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(
+            solution.literals,
+            vec![
+                (Some(9), "Total: %d\n".to_string()),
+                (Some(10), "wide\t\"string\"\n".to_string()),
+                (Some(11), "a\\bc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn analyses_allow_annotation() {
+        let src = "int x; /* atst:allow no-globals */
+int y; // atst:allow no-call, no-globals
+int z; // atst:allow no-call
+int main() {
+    return x + y + z;
+}";
+        let mut solution = get_solution(src, true);
+        assert!(Parser {}.execute(&mut solution, 0).is_ok());
+        let analysers: Vec<Box<dyn Analyser>> =
+            vec![Box::new(NoGlobalsAnalyser::new(-1.0, vec![]))];
+        let res =
+            AnalysesExec::new(&analysers, &AnalysesConfig::default()).execute(&mut solution, 0);
+        assert!(res.is_ok());
+        let lines: Vec<Option<usize>> = solution.findings.iter().map(|f| f.finding.line).collect();
+        assert_eq!(lines, vec![Some(3)]);
+        assert_eq!(solution.score, -1.0);
+    }
+
+    #[test]
+    fn analyses_source_too_large() {
+        let mut solution = get_solution("int x;\nint main() {\n    return x;\n}", false);
+        assert!(Parser {}.execute(&mut solution, 0).is_ok());
+        let analysers: Vec<Box<dyn Analyser>> =
+            vec![Box::new(NoGlobalsAnalyser::new(-1.0, vec![]))];
+        let mut config = AnalysesConfig {
+            max_source_bytes: 16,
+            oversize_penalty: None,
+        };
+        assert!(AnalysesExec::new(&analysers, &config)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert_eq!(solution.findings.len(), 1);
+        assert_eq!(solution.findings[0].analyser, "analyses");
+        assert!(solution.findings[0]
+            .finding
+            .message
+            .starts_with("source too large for analysis"));
+        assert_eq!(solution.score, 0.0);
+
+        config.oversize_penalty = Some(-2.0);
+        solution.findings.clear();
+        assert!(AnalysesExec::new(&analysers, &config)
+            .execute(&mut solution, 0)
+            .is_ok());
+        assert_eq!(solution.findings[0].severity, Severity::Penalty(-2.0));
+        assert_eq!(solution.score, -2.0);
     }
 
     #[test]
@@ -425,7 +2060,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0);
@@ -452,7 +2088,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0);
@@ -479,7 +2116,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0);
@@ -512,7 +2150,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0);
@@ -545,12 +2184,60 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0);
     }
 
+    #[test]
+    fn exec_test_penalty() {
+        let cases = vec![
+            TestCase {
+                args: vec!["hello".to_string()],
+                stderr: Some("".to_string()),
+                ..Default::default()
+            },
+            TestCase {
+                args: vec!["error".to_string()],
+                stderr: Some("".to_string()),
+                ..Default::default()
+            },
+        ];
+        let penalty = |requirement| Test {
+            name: "no stderr".to_string(),
+            score: -0.5,
+            requirement,
+            test_cases: cases.clone(),
+            ..Default::default()
+        };
+        let tests = vec![
+            penalty(TestCasesRequirement::ALL),
+            penalty(TestCasesRequirement::ANY),
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <string.h>
+               int main(int argc, char **argv) {
+                   if (strcmp(argv[1], "error") == 0)
+                       fprintf(stderr, "%s", argv[1]);
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        // Only the test requiring all cases to pass fails and gives the penalty
+        assert_eq!(solution.score, -0.5);
+        let results = &solution.test_results;
+        assert_eq!((results[0].score, results[0].max_score), (-0.5, 0.0));
+        assert_eq!(results[0].reason, Some(FailureReason::WrongOutput));
+        assert_eq!((results[1].score, results[1].reason), (0.0, None));
+    }
+
     #[test]
     fn exec_test_timeout() {
         let tests = vec![Test {
@@ -569,12 +2256,55 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, 100);
+        let config = TestConfig {
+            timeout: 100,
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 0.0)
     }
 
+    #[test]
+    fn exec_test_case_timeout() {
+        let test = |sleep_ms: u64, timeout: Option<u64>| Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                args: vec![sleep_ms.to_string()],
+                stdout: Some("done".to_string()),
+                timeout,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![
+            test(600, None),
+            test(600, Some(5000)),
+            test(50, Some(10)),
+            test(50, None),
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <stdlib.h>
+               #include <unistd.h>
+               int main(int argc, char **argv) {
+                   usleep(atoi(argv[1]) * 1000);
+                   printf("done");
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig {
+            timeout: 300,
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        let scores: Vec<f64> = solution.test_results.iter().map(|r| r.score).collect();
+        assert_eq!(scores, vec![0.0, 1.0, 0.0, 1.0]);
+    }
+
     #[test]
     fn exec_test_stderr() {
         let tests = vec![Test {
@@ -593,7 +2323,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0)
@@ -617,7 +2348,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0)
@@ -641,7 +2373,8 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 0.0)
@@ -666,9 +2399,1151 @@ int main() {
             "#,
             true,
         );
-        let test_exec = TestExec::new(&tests, DEFAULT_TEST_TIMEOUT);
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn exec_test_sort_fields() {
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some("a=1, B=2\nc=3,d=4".to_string()),
+                case_insensitive: true,
+                sort_fields: Some(",".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   printf("b=2,a=1\nd=4, c=3\n");
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn exec_test_unordered() {
+        let test = |stdout: &str| Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some(stdout.to_string()),
+                unordered: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![test("c\na\nb\na"), test("a\nb\nc")];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   printf("a\nb\na\nc\n");
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn exec_test_columns() {
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some("name   price\napple  1.50\npear   0.75".to_string()),
+                columns: Some(vec![0.0, 0.01]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   printf("%-8s%8s\n", "name", "price");
+                   printf("%-8s%8.3f\n", "apple", 1.5);
+                   printf("%-8s%8.3f\n", "pear", 0.749);
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
         let res = test_exec.execute(&mut solution, 0);
         assert!(res.is_ok());
         assert_eq!(solution.score, 1.0)
     }
+
+    #[test]
+    fn exec_test_tolerance() {
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some("pi = 3.1416\ne = 2.7183".to_string()),
+                tolerance: Some(Tolerance::Absolute(0.001)),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   printf("pi = %.5f\ne = %.5f\n", 3.14159, 2.71828);
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn match_output_trailing_whitespace() {
+        let mut test_case = TestCase::default();
+        let expected = Some("Sum: 10\nAvg: 2.5\n".to_string());
+        let output = "Sum: 10 \nAvg: 2.5\t\n\n\n";
+        assert!(!match_output(output, &expected, &test_case));
+        test_case.ignore_trailing_whitespace = true;
+        assert!(match_output(output, &expected, &test_case));
+        // Other whitespace still matters
+        assert!(!match_output("Sum:  10\nAvg: 2.5", &expected, &test_case));
+        assert!(!match_output("Sum: 10\n\nAvg: 2.5", &expected, &test_case));
+        // Combined with case folding
+        assert!(!match_output("SUM: 10 \nAVG: 2.5", &expected, &test_case));
+        test_case.case_insensitive = true;
+        assert!(match_output("SUM: 10 \nAVG: 2.5", &expected, &test_case));
+    }
+
+    #[test]
+    fn match_output_unordered() {
+        let mut test_case = TestCase {
+            unordered: true,
+            ..Default::default()
+        };
+        let expected = Some("apple 3\npear 1\napple 3\nplum 2\n".to_string());
+        assert!(match_output(
+            "plum 2\napple 3\napple 3\npear 1",
+            &expected,
+            &test_case
+        ));
+        // Duplicate lines are counted
+        assert!(!match_output(
+            "plum 2\napple 3\npear 1",
+            &expected,
+            &test_case
+        ));
+        assert!(!match_output(
+            "plum 2\napple 3\napple 3\npear 1\npear 1",
+            &expected,
+            &test_case
+        ));
+        // Trailing blank lines are ignored as for ordered outputs, other blank lines are not
+        assert!(match_output(
+            "pear 1\napple 3\nplum 2\napple 3\n\n\n",
+            &expected,
+            &test_case
+        ));
+        assert!(!match_output(
+            "pear 1\n\napple 3\nplum 2\napple 3",
+            &expected,
+            &test_case
+        ));
+        // Lines are sorted after case folding
+        assert!(!match_output(
+            "Plum 2\napple 3\nApple 3\npear 1",
+            &expected,
+            &test_case
+        ));
+        test_case.case_insensitive = true;
+        assert!(match_output(
+            "Plum 2\napple 3\nApple 3\npear 1",
+            &expected,
+            &test_case
+        ));
+    }
+
+    #[test]
+    fn match_output_tolerance() {
+        let mut test_case = TestCase {
+            tolerance: Some(Tolerance::Absolute(0.001)),
+            ..Default::default()
+        };
+        let expected = Some("3.1416 2.0\nok".to_string());
+        assert!(match_output("3.14159 2\nok", &expected, &test_case));
+        // Tokens are compared regardless of lines and spacing
+        assert!(match_output("3.14159\n2   ok", &expected, &test_case));
+        assert!(!match_output("3.14 2 ok", &expected, &test_case));
+        assert!(!match_output("3.1416 2 OK", &expected, &test_case));
+        assert!(!match_output("3.1416 2 ok 1", &expected, &test_case));
+        assert!(!match_output("3.1416 ok", &expected, &test_case));
+        test_case.tolerance = Some(Tolerance::Relative(0.01));
+        assert!(match_output("3.15 1.99 ok", &expected, &test_case));
+        assert!(!match_output("3.2 2 ok", &expected, &test_case));
+        test_case.tolerance = None;
+        assert!(!match_output("3.14159 2\nok", &expected, &test_case));
+    }
+
+    #[test]
+    fn match_output_unicode() {
+        let mut test_case = TestCase {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let expected = Some("Žluťoučký kůň".to_string());
+        assert!(match_output("ŽLUŤOUČKÝ KŮŇ", &expected, &test_case));
+        assert!(!match_output(
+            "Z\u{30c}lut\u{30c}ouc\u{30c}ky\u{301} ku\u{30a}n\u{30c}",
+            &expected,
+            &test_case
+        ));
+        test_case.normalize_unicode = true;
+        assert!(match_output(
+            "Z\u{30c}LUŤOUČKÝ KU\u{30a}N\u{30c}",
+            &expected,
+            &test_case
+        ));
+    }
+
+    #[test]
+    fn match_output_equivalences() {
+        let mut test_case = TestCase {
+            equivalences: Some(vec![vec!["true".to_string(), "1".to_string()]]),
+            ..Default::default()
+        };
+        let expected = Some("flag: true\ncount: 1".to_string());
+        assert!(match_output("flag: 1\ncount: true", &expected, &test_case));
+        // Only whole tokens are replaced
+        assert!(!match_output("flag: 10\ncount: 1", &expected, &test_case));
+        assert!(!match_output("flag: TRUE\ncount: 1", &expected, &test_case));
+        test_case.case_insensitive = true;
+        assert!(match_output("flag: TRUE\ncount: 1", &expected, &test_case));
+    }
+
+    #[test]
+    fn match_line_columns() {
+        let test_case = TestCase {
+            columns: Some(vec![0.0, 0.1]),
+            ..Default::default()
+        };
+        assert!(match_line("a 1.05 2.95", "a 1 3", &test_case));
+        assert!(!match_line("a 1.2 3", "a 1 3", &test_case));
+        assert!(!match_line("1 1 3", "2 1 3", &test_case));
+        assert!(!match_line("a 1", "a 1 3", &test_case));
+        assert!(!match_line("a 1.05", "a 1", &TestCase::default()));
+    }
+
+    #[test]
+    fn output_diff_lines() {
+        assert_eq!(
+            output_diff("a\nb", "a\nb", &TestCase::default(), 10),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            output_diff("a\nx\nc\nd", "a\nb\nc", &TestCase::default(), 10),
+            vec!["line 2: expected 'b', got 'x'", "line 4: unexpected 'd'"]
+        );
+        let output = (0..100)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            output_diff(&output, "", &TestCase::default(), 2),
+            vec![
+                "line 1: unexpected '0'",
+                "line 2: unexpected '1'",
+                "and 98 more differences"
+            ]
+        );
+        assert_eq!(
+            output_diff("", "a", &TestCase::default(), 0),
+            vec!["and 1 more differences"]
+        );
+    }
+
+    #[test]
+    fn sort_fields_lines() {
+        assert_eq!(sort_fields("c b a\nz  y", " "), "a b c\ny z");
+        assert_eq!(sort_fields("k=2;j=1", ";"), "j=1;k=2");
+        // Lines are not reordered
+        assert_ne!(sort_fields("b\na", " "), sort_fields("a\nb", " "));
+    }
+
+    #[test]
+    fn exec_test_env() {
+        let case = |env: &[(&str, &str)], stdout: &str| TestCase {
+            env: env
+                .iter()
+                .map(|(var, value)| (var.to_string(), value.to_string()))
+                .collect(),
+            stdout: Some(stdout.to_string()),
+            ..Default::default()
+        };
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![
+                case(&[("MAX_ITEMS", "10"), ("LC_ALL", "C")], "10 C"),
+                // Output derived from a seed is deterministic
+                case(&[("SEED", "42"), ("LC_ALL", "C")], "unset C 1386"),
+                // Variables of other cases are not set
+                case(&[("LC_ALL", "C")], "unset C"),
+            ],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <stdlib.h>
+               int main() {
+                   char *max = getenv("MAX_ITEMS");
+                   char *seed = getenv("SEED");
+                   printf("%s %s", max ? max : "unset", getenv("LC_ALL"));
+                   if (seed)
+                       printf(" %ld", atol(seed) * 33 % 10007);
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.score, 1.0);
+    }
+
+    #[test]
+    fn exec_test_programs() {
+        let mut solution = get_solution("int main() {}", false);
+        // Programs shifting letters of their input
+        let shift = r#"#include <stdio.h>
+                       int main() {
+                           int c;
+                           while ((c = getchar()) != EOF)
+                               putchar(c >= 'a' && c <= 'z' ? (c - 'a' + SHIFT + 26) % 26 + 'a' : c);
+                       }"#;
+        std::fs::write(solution.path.join("shift.c"), shift).unwrap();
+        let program = |name: &str, sources: &[&str], c_flags: &str| Program {
+            name: name.to_string(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            c_flags: Some(c_flags.to_string()),
+            ld_flags: None,
+        };
+        let compiler = Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: String::new(),
+            ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![
+                program("encode", &["shift.c"], "-DSHIFT=1"),
+                program("decode", &["shift.c"], "-DSHIFT=-1"),
+                program("broken", &["missing.c"], ""),
+            ],
+            warnings_penalty: WarningsPenalty::default(),
+        };
+        assert!(compiler.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.program_file("encode"), Some(Path::new("encode")));
+        assert!(solution.path.join("decode").exists());
+        assert_eq!(solution.program_file("broken"), None);
+
+        let test = |name: &str, programs: &[&str], stdin: &str, stdout: &str| Test {
+            name: name.to_string(),
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdin: Some(stdin.to_string()),
+                stdout: Some(stdout.to_string()),
+                ..Default::default()
+            }],
+            programs: programs.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let tests = vec![
+            test("encode", &["encode"], "abz", "bca"),
+            test("decode", &["decode"], "bca", "abz"),
+            test("round trip", &["encode", "decode"], "hello", "hello"),
+            test("twice", &["encode", "encode", "encode"], "a", "d"),
+            test("broken", &["encode", "broken"], "a", "b"),
+        ];
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        solution.executions = Some(vec![]);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.score, 4.0);
+        assert_eq!(
+            solution.test_results[4].reason,
+            Some(FailureReason::CompileFail)
+        );
+
+        // Each program of a pipeline is audited
+        let executions = solution.executions.take().unwrap();
+        let programs = executions
+            .iter()
+            .map(|e| {
+                Path::new(&e.command[0])
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            programs,
+            vec!["encode", "decode", "encode", "decode", "encode", "encode", "encode"]
+        );
+        assert!(executions.iter().all(|e| e.exit_code == Some(0)));
+        assert!(executions[2].input_hash.is_some());
+        assert!(executions[3].input_hash.is_none());
+    }
+
+    #[test]
+    fn exec_test_exec_prefix() {
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                args: vec!["arg".to_string()],
+                stdin: Some("input".to_string()),
+                stdout: Some("bar arg input".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <stdlib.h>
+               int main(int argc, char **argv) {
+                   char input[6];
+                   scanf("%5s", input);
+                   printf("%s %s %s", getenv("FOO"), argv[1], input);
+                }
+            "#,
+            true,
+        );
+        // Use env as the launcher to check that the prefix is really used
+        let config = TestConfig {
+            exec_prefix: vec!["env".to_string(), "FOO=bar".to_string()],
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn exec_test_sha256() {
+        let test = |digest: &str| Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout_sha256: Some(digest.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![
+            test("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"),
+            // Digest of "hello" (without the newline)
+            test("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"),
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   puts("hello");
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.score, 1.0);
+        assert_eq!(
+            solution.test_results[1].reason,
+            Some(FailureReason::WrongOutput)
+        );
+    }
+
+    #[test]
+    fn match_output_digest_mismatch() {
+        let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(match_output_digest("hello".as_bytes(), expected).is_ok());
+        assert_eq!(
+            match_output_digest("hello\n".as_bytes(), expected),
+            Err("obtained 6 bytes with SHA-256 \
+                 5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn exec_test_fixture() {
+        let input = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(input.path(), "fixture").unwrap();
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdin_file: Some(DataFile {
+                    name: "input".to_string(),
+                    path: input.path().to_path_buf(),
+                }),
+                stdout: Some("fixture".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   char input[8];
+                   scanf("%7s", input);
+                   printf("%s", input);
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.score, 1.0);
+    }
+
+    /// Peak and current resident memory of this process (in kB)
+    #[cfg(target_os = "linux")]
+    fn memory_usage() -> (u64, u64) {
+        let status = read_to_string("/proc/self/status").unwrap();
+        let field = |name: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .and_then(|value| value.trim().trim_end_matches(" kB").parse().ok())
+                .unwrap()
+        };
+        (field("VmHWM:"), field("VmRSS:"))
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn exec_test_streamed_files() {
+        const SIZE_KB: u64 = 64 * 1024;
+        let dir = tempfile::tempdir().unwrap();
+        let line = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ\n";
+        let mut data = File::create(dir.path().join("data")).unwrap();
+        for _ in 0..SIZE_KB * 1024 / line.len() as u64 {
+            data.write_all(line.as_bytes()).unwrap();
+        }
+        drop(data);
+        std::fs::copy(dir.path().join("data"), dir.path().join("other")).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("other"))
+            .unwrap()
+            .write_all(b"x")
+            .unwrap();
+
+        let file = |name: &str| DataFile {
+            name: format!("<{}", name),
+            path: dir.path().join(name),
+        };
+        let test = |stdout: &str| Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdin_file: Some(file("data")),
+                stdout_file: Some(file(stdout)),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![test("data"), test("other")];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   char buf[4096];
+                   size_t n;
+                   while ((n = fread(buf, 1, sizeof(buf), stdin)) > 0)
+                       fwrite(buf, 1, n, stdout);
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig {
+            timeout: 60000,
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        let (_, before) = memory_usage();
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        let (peak, _) = memory_usage();
+        assert_eq!(solution.score, 1.0);
+        assert_eq!(solution.test_results[1].score, 0.0);
+        // Neither the input nor the output are held in memory
+        assert!(peak.saturating_sub(before) < SIZE_KB / 2);
+    }
+
+    #[test]
+    fn exec_test_disk_limit() {
+        let test = |size: &str| Test {
+            score: 1.0,
+            test_cases: vec![TestCase {
+                args: vec![size.to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![test("10"), test("1000")];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <stdlib.h>
+               int main(int argc, char **argv) {
+                   FILE *f = fopen("out", "w");
+                   for (int i = 0; i < atoi(argv[1]); i++)
+                       fputc('x', f);
+                   fclose(f);
+                }
+            "#,
+            true,
+        );
+        let config = TestConfig {
+            max_workdir_bytes: Some(100),
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.score, 1.0);
+        assert_eq!(solution.test_results[0].reason, None);
+        assert_eq!(
+            solution.test_results[1].reason,
+            Some(FailureReason::DiskLimit)
+        );
+        // Cases are run in their own working directories
+        assert!(!solution.path.join("out").exists());
+    }
+
+    #[test]
+    fn exec_test_exit_code() {
+        let tests = vec![
+            Test {
+                score: 1.0,
+                test_cases: vec![TestCase {
+                    exit_code: Some(vec![3]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Test {
+                score: 2.0,
+                test_cases: vec![TestCase {
+                    exit_code: Some(vec![0, 3]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Test {
+                score: 4.0,
+                test_cases: vec![TestCase {
+                    exit_code: Some(vec![0, 2]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdlib.h>
+               int main() {
+                   exit(3);
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 3.0)
+    }
+
+    #[test]
+    fn exec_test_exit_code_crash() {
+        let tests = vec![Test {
+            score: 1.0,
+            test_cases: vec![
+                TestCase {
+                    args: vec!["ok".to_string()],
+                    exit_code: Some(vec![0]),
+                    ..Default::default()
+                },
+                TestCase {
+                    args: vec!["crash".to_string()],
+                    exit_code: Some(vec![0, 134, -6]),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }];
+        let mut solution = get_solution(
+            r#"#include <stdlib.h>
+               #include <string.h>
+               int main(int argc, char **argv) {
+                   if (strcmp(argv[1], "crash") == 0)
+                       abort();
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 0.0);
+        assert_eq!(solution.test_results[0].reason, Some(FailureReason::Crash));
+    }
+
+    #[test]
+    fn exit_code_mismatches() {
+        assert_eq!(
+            exit_code_mismatch(Some(3), None, &Some(vec![0, 2])),
+            "exit code 3 (expected 0 or 2)"
+        );
+        assert_eq!(
+            exit_code_mismatch(None, Some(11), &Some(vec![0])),
+            "program killed by signal 11 (expected exit code 0)"
+        );
+        assert_eq!(
+            exit_code_mismatch(None, None, &Some(vec![0])),
+            "program crashed (expected exit code 0)"
+        );
+    }
+
+    #[test]
+    fn exec_test_timeout_partial_credit() {
+        let tests = vec![
+            Test {
+                score: 2.0,
+                test_cases: vec![TestCase {
+                    stdout: Some("1\n2\n3\n4".to_string()),
+                    ..Default::default()
+                }],
+                timeout_partial_credit: Some(0.5),
+                ..Default::default()
+            },
+            Test {
+                score: 1.0,
+                test_cases: vec![TestCase {
+                    stdout: Some("1\n3\n".to_string()),
+                    ..Default::default()
+                }],
+                timeout_partial_credit: Some(0.5),
+                ..Default::default()
+            },
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               int main() {
+                   printf("1\n2\n");
+                   fflush(stdout);
+                   while (1) {}
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig {
+            timeout: 100,
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0)
+    }
+
+    #[test]
+    fn exec_test_order_by_cost() {
+        let test = |name: &str, stdout: &str, cost_hint: Option<f64>| Test {
+            name: name.to_string(),
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some(stdout.to_string()),
+                ..Default::default()
+            }],
+            cost_hint,
+            ..Default::default()
+        };
+        let tests = vec![
+            test("unknown", "ok", None),
+            test("stress", "nok", Some(30000.0)),
+            test("smoke", "ok", Some(50.0)),
+            test("other smoke", "ok", Some(50.0)),
+        ];
+        let mut config = TestConfig::default();
+        let source = r#"#include <stdio.h>
+                        int main() { printf("ok\n"); }
+                     "#;
+
+        let mut solution = get_solution(source, true);
+        let res = TestExec::new(&tests, &config).execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert!(solution.test_results.iter().all(|t| t.order.is_none()));
+
+        config.order = TestOrder::ByCost;
+        let mut solution = get_solution(source, true);
+        let res = TestExec::new(&tests, &config).execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 3.0);
+        // Results are in the order of the tests, each recording when it was run
+        let results: Vec<(&str, Option<usize>)> = solution
+            .test_results
+            .iter()
+            .map(|t| (t.name.as_str(), t.order))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("unknown", Some(3)),
+                ("stress", Some(2)),
+                ("smoke", Some(0)),
+                ("other smoke", Some(1)),
+            ]
+        );
+        assert_eq!(
+            solution.test_results[1].reason,
+            Some(FailureReason::WrongOutput)
+        );
+    }
+
+    #[test]
+    fn exec_test_failure_reasons() {
+        let test = |name: &str, args: &str, stdout: &str, exit_code: Option<Vec<i64>>| Test {
+            name: name.to_string(),
+            score: 1.0,
+            test_cases: vec![TestCase {
+                args: vec![args.to_string()],
+                stdout: Some(stdout.to_string()),
+                exit_code,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let tests = vec![
+            test("pass", "ok", "ok", None),
+            test("output", "ok", "nok", None),
+            test("timeout", "loop", "ok", None),
+            test("crash", "crash", "ok", None),
+            test("exit", "ok", "ok", Some(vec![1])),
+            Test {
+                name: "stderr".to_string(),
+                score: 1.0,
+                test_cases: vec![TestCase {
+                    args: vec!["ok".to_string()],
+                    stderr: Some("error".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Test {
+                name: "defines".to_string(),
+                score: 1.0,
+                defines: vec![("X".to_string(), String::new())],
+                ..Default::default()
+            },
+        ];
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #include <stdlib.h>
+               #include <string.h>
+               int main(int argc, char **argv) {
+                   if (strcmp(argv[1], "loop") == 0)
+                       while (1) {}
+                   if (strcmp(argv[1], "crash") == 0)
+                       abort();
+                   printf("ok\n");
+               }
+            "#,
+            true,
+        );
+        let config = TestConfig {
+            timeout: 100,
+            ..Default::default()
+        };
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 1.0);
+        let reasons: Vec<Option<FailureReason>> =
+            solution.test_results.iter().map(|t| t.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                None,
+                Some(FailureReason::WrongOutput),
+                Some(FailureReason::Timeout),
+                Some(FailureReason::Crash),
+                Some(FailureReason::ExitCode),
+                Some(FailureReason::WrongOutput),
+                Some(FailureReason::CompileFail),
+            ]
+        );
+        let case_reasons: Vec<Vec<Option<CaseFailure>>> = solution
+            .test_results
+            .iter()
+            .map(|t| t.cases.iter().map(|c| c.reason).collect())
+            .collect();
+        assert_eq!(
+            case_reasons,
+            vec![
+                vec![None],
+                vec![Some(CaseFailure::WrongStdout)],
+                vec![Some(CaseFailure::Timeout)],
+                vec![Some(CaseFailure::Crash(Some(6)))],
+                vec![Some(CaseFailure::WrongExitCode)],
+                vec![Some(CaseFailure::WrongStderr)],
+                vec![],
+            ]
+        );
+        assert_eq!(solution.test_results[0].cases[0].message, None);
+        assert_eq!(
+            solution.test_results[4].cases[0].message.as_deref(),
+            Some("exit code 0 (expected 1)")
+        );
+    }
+
+    #[test]
+    fn analyses_gate_tests() {
+        let tests = vec![Test {
+            name: "output".to_string(),
+            score: 1.0,
+            test_cases: vec![TestCase {
+                stdout: Some("ok".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let analysers: Vec<Box<dyn Analyser>> = vec![Box::new(ConfiguredAnalyser::new(
+            Box::new(NoCallAnalyser::new(vec!["system".to_string()], -1.0)),
+            Some(-5.0),
+            None,
+        ))];
+        let config = TestConfig::default();
+        let analyses_config = AnalysesConfig::default();
+        let analyses_exec = AnalysesExec::new(&analysers, &analyses_config);
+        let test_exec = TestExec::new(&tests, &config);
+
+        let mut solution = get_solution("#include <stdio.h>\nint main() { puts(\"ok\"); }", true);
+        assert!(analyses_exec.execute(&mut solution, 0).is_ok());
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(solution.gate(), None);
+        assert_eq!(solution.score, 1.0);
+
+        let mut solution = get_solution(
+            "#include <stdlib.h>\n#include <stdio.h>\nint main() { system(\"ls\"); puts(\"ok\"); }",
+            true,
+        );
+        assert!(analyses_exec.execute(&mut solution, 0).is_ok());
+        assert!(test_exec.execute(&mut solution, 0).is_ok());
+        assert_eq!(
+            solution.gate,
+            Some(("no-call: call of 'system'".to_string(), -5.0))
+        );
+        assert_eq!(solution.score, 0.0);
+        assert_eq!(solution.test_results[0].reason, Some(FailureReason::Gated));
+    }
+
+    #[test]
+    fn coverage_module() {
+        let src = r#"#include <stdio.h>
+int main() {
+    int x;
+    if (scanf("%d", &x) != 1)
+        return 1;
+    if (x > 0)
+        puts("positive");
+    else
+        puts("negative");
+    return 0;
+}"#;
+        let mut solution = get_solution(src, false);
+        let compiler = Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: String::new(),
+            ld_flags: String::new(),
+            define_sets: vec![],
+            coverage: true,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
+        };
+        let res = compiler.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert!(solution.path.join("test-coverage").exists());
+
+        let config = CoverageConfig {
+            score: 2.0,
+            threshold: 90.0,
+            tests_dir: PathBuf::from("tests"),
+        };
+        let test_config = TestConfig::default();
+        let coverage = CoverageExec::new(&config, &test_config);
+
+        // No tests written
+        solution.score = 0.0;
+        let res = coverage.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 0.0);
+
+        // Tests covering 6 out of 7 lines
+        std::fs::create_dir(solution.path.join("tests")).unwrap();
+        std::fs::write(solution.path.join("tests").join("1.in"), "5").unwrap();
+        std::fs::write(solution.path.join("tests").join("2.in"), "x").unwrap();
+        let res = coverage.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert!((solution.score - 2.0 * 85.71 / 90.0).abs() < 0.01);
+
+        // All lines covered, full score
+        std::fs::write(solution.path.join("tests").join("3.in"), "-1").unwrap();
+        solution.score = 0.0;
+        let res = coverage.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 2.0);
+    }
+
+    fn late_penalty_on(curve: PenaltyCurve, submitted_at: &str, score: f64) -> Solution {
+        let config = LatePenaltyConfig {
+            deadline: parse_timestamp("2024-01-31 23:59:59").unwrap(),
+            timestamp: TimestampSource::File(PathBuf::from("submitted-at")),
+            curve,
+            per_day: 0.25,
+        };
+        let mut solution = get_solution("int main() {}", false);
+        std::fs::write(solution.path.join("submitted-at"), submitted_at).unwrap();
+        solution.score = score;
+
+        let res = LatePenalty::new(&config).execute(&mut solution, 0);
+        assert!(res.is_ok());
+        solution
+    }
+
+    #[test]
+    fn late_penalty_on_time() {
+        let solution = late_penalty_on(PenaltyCurve::Linear, "2024-01-31 12:00", 4.0);
+        assert_eq!(solution.score, 4.0);
+        assert_eq!(solution.late_penalty, None);
+    }
+
+    #[test]
+    fn late_penalty_linear() {
+        // 1 day and 1 second late counts as 2 days
+        let solution = late_penalty_on(PenaltyCurve::Linear, "2024-02-02 00:00:00", 4.0);
+        assert_eq!(solution.score, 2.0);
+        assert_eq!(
+            solution.late_penalty,
+            Some(LatePenaltyResult {
+                days: 2,
+                penalty: -2.0
+            })
+        );
+        // Clamp at zero
+        assert_eq!(
+            late_penalty_on(PenaltyCurve::Linear, "2024-02-10 00:00:00", 4.0).score,
+            0.0
+        );
+        // Non-positive scores are kept
+        let solution = late_penalty_on(PenaltyCurve::Linear, "2024-02-01 00:00:00", -1.0);
+        assert_eq!(solution.score, -1.0);
+        assert_eq!(
+            solution.late_penalty,
+            Some(LatePenaltyResult {
+                days: 1,
+                penalty: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn late_penalty_exponential() {
+        assert_eq!(
+            late_penalty_on(PenaltyCurve::Exponential, "2024-02-02 00:00:00", 4.0).score,
+            2.25
+        );
+    }
+
+    #[test]
+    fn exec_test_defines() {
+        let tests = vec![
+            Test {
+                score: 1.0,
+                test_cases: vec![TestCase {
+                    stdout: Some("0".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Test {
+                score: 2.0,
+                test_cases: vec![TestCase {
+                    stdout: Some("10".to_string()),
+                    ..Default::default()
+                }],
+                defines: vec![("SIZE".to_string(), "10".to_string())],
+                ..Default::default()
+            },
+            Test {
+                score: 4.0,
+                test_cases: vec![TestCase {
+                    stdout: Some("20".to_string()),
+                    ..Default::default()
+                }],
+                defines: vec![("SIZE".to_string(), "20".to_string())],
+                ..Default::default()
+            },
+        ];
+        let compiler = Compiler {
+            compiler: "gcc".to_string(),
+            c_flags: String::new(),
+            ld_flags: String::new(),
+            define_sets: tests
+                .iter()
+                .filter(|t| !t.defines.is_empty())
+                .map(|t| t.defines.clone())
+                .collect(),
+            coverage: false,
+            binary_limits: BinaryLimits::default(),
+            programs: vec![],
+            warnings_penalty: WarningsPenalty::default(),
+        };
+        let mut solution = get_solution(
+            r#"#include <stdio.h>
+               #ifndef SIZE
+               #define SIZE 0
+               #endif
+               int main() {
+                   printf("%d", SIZE);
+               }
+            "#,
+            false,
+        );
+        let res = compiler.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        for test in &tests {
+            let bin_file = defines_bin_file(&solution.bin_file, &test.defines);
+            assert!(solution.path.join(bin_file).exists());
+        }
+
+        let config = TestConfig::default();
+        let test_exec = TestExec::new(&tests, &config);
+        let res = test_exec.execute(&mut solution, 0);
+        assert!(res.is_ok());
+        assert_eq!(solution.score, 7.0)
+    }
 }
@@ -0,0 +1,541 @@
+use crate::{AtstError, CaseFailure, CaseResult, FailureReason, TestResult};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use yaml_rust::{Yaml, YamlLoader};
+
+/// Results of a single solution in the JSON results format
+/// `status` is one of "evaluated", "compilation-failed", "gated", "no-source", and "error".
+/// `gate` is the reason of not running the tests of a "gated" solution, `error` is the
+/// reason of a solution not being evaluated at all ("error"). `overrides` describes the
+/// exceptions granted to the solution by its override file (see `SolutionOverride`).
+/// `metrics` are only set if they were requested (see `RunOptions::metrics`).
+/// `compiler_warnings` is the number of warnings of the compiler (if the solution was built).
+/// `late_penalty` is only set if the solution was submitted after the deadline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionResults {
+    pub name: String,
+    pub status: String,
+    pub score: f64,
+    pub gate: Option<String>,
+    pub error: Option<String>,
+    pub overrides: Option<String>,
+    pub metrics: Option<SourceMetrics>,
+    pub compiler_warnings: Option<usize>,
+    pub late_penalty: Option<LatePenaltyResult>,
+    pub tests: Vec<TestResult>,
+}
+
+/// Late penalty applied to a solution submitted `days` (started) days after the deadline
+/// `penalty` is the (non-positive) change of the score of the solution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatePenaltyResult {
+    pub days: u64,
+    pub penalty: f64,
+}
+
+/// Basic metrics of the source of a solution (for research on the submissions)
+/// `lines` are the non-blank lines which are not only comments, `longest_function` is the
+/// number of lines of the longest function body, and `comment_ratio` is the fraction of
+/// non-blank lines containing a comment. Metrics are computed from the source as it was
+/// submitted, so they are available even if it does not compile.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SourceMetrics {
+    pub lines: usize,
+    pub functions: usize,
+    pub includes: usize,
+    pub longest_function: usize,
+    pub comment_ratio: f64,
+}
+
+/// Read results of solutions from a JSON results file (as written by the `json` output)
+pub fn read_results(file: &Path) -> Result<Vec<SolutionResults>, AtstError> {
+    let err = |msg: &str| AtstError::InvalidResults {
+        path: file.display().to_string(),
+        msg: msg.to_string(),
+    };
+    let contents = std::fs::read_to_string(file).map_err(|e| err(&e.to_string()))?;
+    // JSON produced by the json output is valid YAML
+    let yaml = YamlLoader::load_from_str(&contents).map_err(|e| err(&e.to_string()))?;
+    let solutions = yaml
+        .first()
+        .and_then(|y| y["solutions"].as_vec())
+        .ok_or_else(|| err("missing list of solutions"))?;
+
+    let number = |y: &Yaml| match y {
+        Yaml::Integer(n) => Some(*n as f64),
+        y => y.as_f64(),
+    };
+    let string = |y: &Yaml| y.as_str().map(String::from);
+    solutions
+        .iter()
+        .map(|s| {
+            let name = string(&s["name"]).ok_or_else(|| err("solution without a name"))?;
+            let invalid = |field: &str| err(&format!("{}: invalid field '{}'", name, field));
+            let tests = s["tests"]
+                .as_vec()
+                .ok_or_else(|| invalid("tests"))?
+                .iter()
+                .map(|t| {
+                    Ok(TestResult {
+                        name: string(&t["name"]).ok_or_else(|| invalid("name"))?,
+                        score: number(&t["score"]).ok_or_else(|| invalid("score"))?,
+                        max_score: number(&t["max-score"]).ok_or_else(|| invalid("max-score"))?,
+                        reason: match &t["reason"] {
+                            Yaml::Null => None,
+                            r => Some(
+                                r.as_str()
+                                    .and_then(FailureReason::from_code)
+                                    .ok_or_else(|| invalid("reason"))?,
+                            ),
+                        },
+                        description: string(&t["description"]),
+                        hint: string(&t["hint"]),
+                        hash: string(&t["hash"]),
+                        cases: match &t["cases"] {
+                            Yaml::BadValue => vec![],
+                            cases => cases
+                                .as_vec()
+                                .ok_or_else(|| invalid("cases"))?
+                                .iter()
+                                .map(|c| {
+                                    Ok(CaseResult {
+                                        reason: match &c["reason"] {
+                                            Yaml::Null => None,
+                                            r => Some(
+                                                r.as_str()
+                                                    .and_then(CaseFailure::from_code)
+                                                    .ok_or_else(|| invalid("reason"))?,
+                                            ),
+                                        },
+                                        message: string(&c["message"]),
+                                    })
+                                })
+                                .collect::<Result<Vec<CaseResult>, AtstError>>()?,
+                        },
+                        order: match &t["order"] {
+                            Yaml::BadValue => None,
+                            Yaml::Integer(n) if *n >= 0 => Some(*n as usize),
+                            _ => Err(invalid("order"))?,
+                        },
+                    })
+                })
+                .collect::<Result<Vec<TestResult>, AtstError>>()?;
+            Ok(SolutionResults {
+                status: string(&s["status"]).ok_or_else(|| invalid("status"))?,
+                score: number(&s["score"]).ok_or_else(|| invalid("score"))?,
+                gate: string(&s["gate"]),
+                error: string(&s["error"]),
+                overrides: string(&s["overrides"]),
+                compiler_warnings: match &s["compiler-warnings"] {
+                    Yaml::BadValue => None,
+                    Yaml::Integer(n) if *n >= 0 => Some(*n as usize),
+                    _ => Err(invalid("compiler-warnings"))?,
+                },
+                late_penalty: match &s["late-penalty"] {
+                    Yaml::BadValue => None,
+                    l => Some(LatePenaltyResult {
+                        days: match &l["days"] {
+                            Yaml::Integer(n) if *n >= 0 => *n as u64,
+                            _ => Err(invalid("late-penalty.days"))?,
+                        },
+                        penalty: number(&l["penalty"])
+                            .ok_or_else(|| invalid("late-penalty.penalty"))?,
+                    }),
+                },
+                metrics: match &s["metrics"] {
+                    Yaml::BadValue => None,
+                    m => {
+                        let count = |field: &str| match &m[field] {
+                            Yaml::Integer(n) if *n >= 0 => Ok(*n as usize),
+                            _ => Err(invalid(&format!("metrics.{}", field))),
+                        };
+                        Some(SourceMetrics {
+                            lines: count("lines")?,
+                            functions: count("functions")?,
+                            includes: count("includes")?,
+                            longest_function: count("longest-function")?,
+                            comment_ratio: number(&m["comment-ratio"])
+                                .ok_or_else(|| invalid("metrics.comment-ratio"))?,
+                        })
+                    }
+                },
+                tests,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Merge results of solutions from multiple JSON results files (e.g. when solutions
+/// are evaluated on several machines). A solution may occur in more files only if it
+/// has the same results in all of them, otherwise all such conflicts are reported.
+/// The merged results are sorted by the names of the solutions.
+pub fn merge_results(files: &[PathBuf]) -> Result<Vec<SolutionResults>, AtstError> {
+    let mut merged: BTreeMap<String, (&Path, SolutionResults)> = BTreeMap::new();
+    let mut conflicts = vec![];
+    for file in files {
+        for results in read_results(file)? {
+            match merged.get(&results.name) {
+                Some((other, existing)) if *existing != results => conflicts.push(format!(
+                    "{}: score {} in {}, score {} in {}",
+                    results.name,
+                    existing.score,
+                    other.display(),
+                    results.score,
+                    file.display()
+                )),
+                Some(_) => {}
+                None => {
+                    merged.insert(results.name.clone(), (file, results));
+                }
+            }
+        }
+    }
+    if !conflicts.is_empty() {
+        return Err(AtstError::ResultsConflict { conflicts });
+    }
+    Ok(merged.into_values().map(|(_, results)| results).collect())
+}
+
+/// Write results of solutions to a JSON results file
+pub fn write_results(file: &Path, solutions: &[SolutionResults]) -> Result<(), AtstError> {
+    write_results_with_metadata(file, &[], solutions)
+}
+
+/// Write results of solutions to a JSON results file, including `metadata` of the run
+/// which produced them (e.g. its seed) as top-level fields with the given JSON values
+pub(crate) fn write_results_with_metadata(
+    file: &Path,
+    metadata: &[(&str, String)],
+    solutions: &[SolutionResults],
+) -> Result<(), AtstError> {
+    let solutions = solutions
+        .iter()
+        .map(|solution| {
+            let tests = solution
+                .tests
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{{ \"name\": {}, \"score\": {}, \"max-score\": {}, \"reason\": {}, \
+                         \"description\": {}, \"hint\": {}{}{}{} }}",
+                        json_string(&t.name),
+                        t.score,
+                        t.max_score,
+                        t.reason
+                            .map_or("null".to_string(), |r| json_string(r.code())),
+                        t.description
+                            .as_deref()
+                            .map_or("null".to_string(), json_string),
+                        t.hint.as_deref().map_or("null".to_string(), json_string),
+                        t.hash.as_deref().map_or(String::new(), |hash| format!(
+                            ", \"hash\": {}",
+                            json_string(hash)
+                        )),
+                        match t.cases.is_empty() {
+                            true => String::new(),
+                            false => format!(
+                                ", \"cases\": [{}]",
+                                t.cases
+                                    .iter()
+                                    .map(|c| format!(
+                                        "{{ \"reason\": {}, \"message\": {} }}",
+                                        c.reason
+                                            .map_or("null".to_string(), |r| json_string(&r.code())),
+                                        c.message
+                                            .as_deref()
+                                            .map_or("null".to_string(), json_string)
+                                    ))
+                                    .collect::<Vec<String>>()
+                                    .join(", ")
+                            ),
+                        },
+                        t.order
+                            .map_or(String::new(), |order| format!(", \"order\": {}", order))
+                    )
+                })
+                .collect::<Vec<String>>();
+            format!(
+                "    {{\n      \"name\": {},\n      \"status\": {},\n      \"score\": {},\n      \
+                 {}\"tests\": [{}]\n    }}",
+                json_string(&solution.name),
+                json_string(&solution.status),
+                solution.score,
+                [
+                    ("gate", &solution.gate),
+                    ("error", &solution.error),
+                    ("overrides", &solution.overrides),
+                ]
+                .iter()
+                .filter_map(|(field, value)| Some(format!(
+                    "\"{}\": {},\n      ",
+                    field,
+                    json_string(value.as_deref()?)
+                )))
+                .chain(
+                    solution
+                        .compiler_warnings
+                        .map(|n| format!("\"compiler-warnings\": {},\n      ", n))
+                )
+                .chain(solution.late_penalty.map(|l| format!(
+                    "\"late-penalty\": {{ \"days\": {}, \"penalty\": {} }},\n      ",
+                    l.days, l.penalty
+                )))
+                .chain(solution.metrics.as_ref().map(|m| format!(
+                    "\"metrics\": {{ \"lines\": {}, \"functions\": {}, \"includes\": {}, \
+                     \"longest-function\": {}, \"comment-ratio\": {} }},\n      ",
+                    m.lines, m.functions, m.includes, m.longest_function, m.comment_ratio
+                )))
+                .collect::<String>(),
+                match tests.is_empty() {
+                    true => String::new(),
+                    false => format!("\n        {}\n      ", tests.join(",\n        ")),
+                }
+            )
+        })
+        .collect::<Vec<String>>();
+    let json = format!(
+        "{{\n{}  \"solutions\": [\n{}\n  ]\n}}\n",
+        metadata
+            .iter()
+            .map(|(field, value)| format!("  {}: {},\n", json_string(field), value))
+            .collect::<String>(),
+        solutions.join(",\n")
+    );
+    std::fs::write(file, json).map_err(|e| AtstError::OutputError {
+        path: file.display().to_string(),
+        source: e,
+    })
+}
+
+/// Source metrics of `solutions` in CSV, one row per solution
+/// Solutions without metrics (e.g. without a source) have the metrics empty.
+pub(crate) fn metrics_csv(solutions: &[SolutionResults]) -> String {
+    let mut csv =
+        "solution,status,lines,functions,includes,longest-function,comment-ratio\n".to_string();
+    for solution in solutions {
+        let metrics = solution.metrics.as_ref().map_or(",,,,".to_string(), |m| {
+            format!(
+                "{},{},{},{},{}",
+                m.lines, m.functions, m.includes, m.longest_function, m.comment_ratio
+            )
+        });
+        csv += &format!(
+            "{},{},{}\n",
+            csv_field(&solution.name),
+            solution.status,
+            metrics
+        );
+    }
+    csv
+}
+
+/// Quote `s` as a CSV field if it contains a separator, a quote, or a line break
+fn csv_field(s: &str) -> String {
+    match s.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+/// Quote and escape `s` as a JSON string
+pub(crate) fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(name: &str, score: f64, reason: Option<FailureReason>) -> SolutionResults {
+        SolutionResults {
+            name: name.to_string(),
+            status: "evaluated".to_string(),
+            score,
+            gate: None,
+            error: None,
+            overrides: None,
+            metrics: None,
+            compiler_warnings: None,
+            late_penalty: None,
+            tests: vec![TestResult {
+                name: "test \"1\"".to_string(),
+                score,
+                max_score: 1.0,
+                reason,
+                description: Some("line\nbreak".to_string()),
+                hint: None,
+                hash: Some("0123456789abcdef".to_string()),
+                cases: vec![],
+                order: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn case_failure_codes() {
+        let failures = [
+            CaseFailure::WrongStdout,
+            CaseFailure::WrongStderr,
+            CaseFailure::WrongExitCode,
+            CaseFailure::Timeout,
+            CaseFailure::Crash(None),
+            CaseFailure::Crash(Some(11)),
+            CaseFailure::DiskLimit,
+        ];
+        for failure in failures {
+            assert_eq!(CaseFailure::from_code(&failure.code()), Some(failure));
+        }
+        assert_eq!(CaseFailure::Crash(Some(11)).code(), "CRASH:11");
+        assert_eq!(CaseFailure::from_code("CRASH:x"), None);
+
+        // All failures are kept when written to and read from JSON results
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("results.json");
+        let mut solution = results("xlogin00", 0.0, Some(FailureReason::WrongOutput));
+        solution.tests[0].cases = std::iter::once(None)
+            .chain(failures.iter().cloned().map(Some))
+            .map(|reason| CaseResult {
+                reason,
+                message: reason.map(|r| format!("\"{}\" failed", r.code())),
+            })
+            .collect();
+        let solutions = vec![solution];
+        assert!(write_results(&file, &solutions).is_ok());
+        assert_eq!(read_results(&file).unwrap(), solutions);
+    }
+
+    #[test]
+    fn json_string_escape() {
+        assert_eq!(json_string("test"), "\"test\"");
+        assert_eq!(
+            json_string("a \"b\"\\\n\u{1}"),
+            "\"a \\\"b\\\"\\\\\\n\\u0001\""
+        );
+    }
+
+    #[test]
+    fn write_read_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("results.json");
+        let mut ordered = results("xlogin01", 1.0, None);
+        ordered.tests[0].order = Some(0);
+        let solutions = vec![
+            results("xlogin00", 0.5, Some(FailureReason::Timeout)),
+            ordered,
+            SolutionResults {
+                name: "xlogin02".to_string(),
+                status: "no-source".to_string(),
+                score: 0.0,
+                gate: None,
+                error: None,
+                overrides: Some("timeout 10000 ms".to_string()),
+                metrics: None,
+                compiler_warnings: None,
+                late_penalty: None,
+                tests: vec![],
+            },
+            SolutionResults {
+                name: "xlogin03".to_string(),
+                status: "gated".to_string(),
+                score: -1.0,
+                gate: Some("no-call: call of 'system'".to_string()),
+                error: None,
+                overrides: None,
+                metrics: Some(SourceMetrics {
+                    lines: 12,
+                    functions: 2,
+                    includes: 1,
+                    longest_function: 7,
+                    comment_ratio: 0.25,
+                }),
+                compiler_warnings: Some(3),
+                late_penalty: Some(LatePenaltyResult {
+                    days: 2,
+                    penalty: -0.75,
+                }),
+                tests: vec![],
+            },
+            SolutionResults {
+                name: "xlogin04".to_string(),
+                status: "error".to_string(),
+                score: 0.0,
+                gate: None,
+                error: Some("cannot read directory".to_string()),
+                overrides: None,
+                metrics: None,
+                compiler_warnings: None,
+                late_penalty: None,
+                tests: vec![],
+            },
+        ];
+        assert!(write_results(&file, &solutions).is_ok());
+        assert_eq!(read_results(&file).unwrap(), solutions);
+
+        std::fs::write(&file, "{ \"solutions\": [ { \"name\": \"x\" } ] }").unwrap();
+        assert!(matches!(
+            read_results(&file),
+            Err(AtstError::InvalidResults { .. })
+        ));
+    }
+
+    #[test]
+    fn metrics_csv_rows() {
+        let mut solution = results("xlogin00", 1.0, None);
+        solution.metrics = Some(SourceMetrics {
+            lines: 10,
+            functions: 1,
+            includes: 2,
+            longest_function: 8,
+            comment_ratio: 0.1,
+        });
+        let mut no_source = results("x,\"y\"", 0.0, None);
+        no_source.status = "no-source".to_string();
+        assert_eq!(
+            metrics_csv(&[solution, no_source]),
+            "solution,status,lines,functions,includes,longest-function,comment-ratio\n\
+             xlogin00,evaluated,10,1,2,8,0.1\n\
+             \"x,\"\"y\"\"\",no-source,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn merge_results_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = (0..3)
+            .map(|i| dir.path().join(format!("{}.json", i)))
+            .collect();
+        write_results(
+            &files[0],
+            &[results("b", 1.0, None), results("c", 1.0, None)],
+        )
+        .unwrap();
+        write_results(&files[1], &[results("a", 0.0, Some(FailureReason::Crash))]).unwrap();
+        write_results(&files[2], &[results("c", 0.0, Some(FailureReason::Crash))]).unwrap();
+
+        let merged = merge_results(&files[..2]).unwrap();
+        assert_eq!(
+            merged.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        // The same results in multiple files are not a conflict
+        assert!(merge_results(&[files[0].clone(), files[0].clone()]).is_ok());
+
+        let res = merge_results(&files);
+        assert!(matches!(res, Err(AtstError::ResultsConflict { .. })));
+        assert!(res.unwrap_err().to_string().contains("c: score 1 in"));
+    }
+}
@@ -0,0 +1,560 @@
+//! Configuration files in TOML
+//! TOML documents are converted to the YAML representation of the configuration, so that
+//! all options are parsed (and validated) in the same way regardless of the format.
+//! Tables become dictionaries, arrays of tables become lists of dictionaries (e.g. `[[tests]]`),
+//! and scalars keep their types. Dates and times are not supported.
+
+use crate::config::ConfigError;
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// Parse a TOML document into a YAML dictionary
+pub fn load_from_str(source: &str) -> Result<Yaml, ConfigError> {
+    Parser {
+        chars: source.chars().collect(),
+        pos: 0,
+    }
+    .document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> ConfigError {
+        ConfigError::InvalidToml {
+            line: self.chars[..self.pos]
+                .iter()
+                .filter(|c| **c == '\n')
+                .count()
+                + 1,
+            msg: msg.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ConfigError> {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}'", c))),
+        }
+    }
+
+    /// Skip spaces and tabs
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace, comments, and newlines
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('#') => self.skip_comment(),
+                Some('\n') | Some('\r') => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip the rest of a line which may only contain a comment
+    fn end_of_line(&mut self) -> Result<(), ConfigError> {
+        self.skip_whitespace();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        if self.starts_with("\r\n") {
+            self.pos += 1;
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.error("expected the end of line")),
+        }
+    }
+
+    fn document(&mut self) -> Result<Yaml, ConfigError> {
+        let mut root = Hash::new();
+        // Path of the table into which the key/value pairs are inserted
+        let mut current: Vec<String> = vec![];
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => break,
+                Some('[') if self.starts_with("[[") => {
+                    self.pos += 2;
+                    let path = self.key()?;
+                    self.expect(']')?;
+                    self.expect(']')?;
+                    let (last, parent) = path.split_last().unwrap();
+                    let table = self.table(&mut root, parent)?;
+                    match table
+                        .entry(Yaml::String(last.clone()))
+                        .or_insert_with(|| Yaml::Array(vec![]))
+                    {
+                        Yaml::Array(tables) => tables.push(Yaml::Hash(Hash::new())),
+                        _ => return Err(self.error(&format!("'{}' is not an array", last))),
+                    }
+                    current = path;
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    current = self.key()?;
+                    self.expect(']')?;
+                    self.table(&mut root, &current)?;
+                }
+                Some(_) => {
+                    let (path, value) = self.key_value()?;
+                    let path = [current.clone(), path].concat();
+                    self.insert(&mut root, &path, value)?;
+                }
+            }
+            self.end_of_line()?;
+        }
+        Ok(Yaml::Hash(root))
+    }
+
+    /// Table at `path` in `root` (tables which do not exist are created)
+    /// Arrays of tables on the path stand for their last table.
+    fn table<'a>(&self, root: &'a mut Hash, path: &[String]) -> Result<&'a mut Hash, ConfigError> {
+        let mut table = root;
+        for key in path {
+            let value = table
+                .entry(Yaml::String(key.clone()))
+                .or_insert_with(|| Yaml::Hash(Hash::new()));
+            let value = match value {
+                Yaml::Array(tables) => tables.last_mut(),
+                value => Some(value),
+            };
+            table = match value {
+                Some(Yaml::Hash(hash)) => hash,
+                _ => return Err(self.error(&format!("'{}' is not a table", key))),
+            };
+        }
+        Ok(table)
+    }
+
+    fn insert(&self, root: &mut Hash, path: &[String], value: Yaml) -> Result<(), ConfigError> {
+        let (last, parent) = path.split_last().unwrap();
+        let table = self.table(root, parent)?;
+        if table.contains_key(&Yaml::String(last.clone())) {
+            return Err(self.error(&format!("duplicate key '{}'", last)));
+        }
+        table.insert(Yaml::String(last.clone()), value);
+        Ok(())
+    }
+
+    fn key_value(&mut self) -> Result<(Vec<String>, Yaml), ConfigError> {
+        let key = self.key()?;
+        self.expect('=')?;
+        self.skip_whitespace();
+        Ok((key, self.value()?))
+    }
+
+    /// Dotted key (e.g. `a."b.c".d`)
+    fn key(&mut self) -> Result<Vec<String>, ConfigError> {
+        let mut path = vec![];
+        loop {
+            self.skip_whitespace();
+            path.push(match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            });
+            self.skip_whitespace();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Yaml, ConfigError> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.multiline_basic_string(),
+            Some('"') => Ok(Yaml::String(self.basic_string()?)),
+            Some('\'') if self.starts_with("'''") => self.multiline_literal_string(),
+            Some('\'') => Ok(Yaml::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            _ if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Yaml::Boolean(true))
+            }
+            _ if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Yaml::Boolean(false))
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<Yaml, ConfigError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "+-._:".contains(c))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let number = text.replace('_', "");
+        let unsigned = number.trim_start_matches(['+', '-']);
+        let radix = match unsigned.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            if let Ok(n) = i64::from_str_radix(&unsigned[2..], radix) {
+                return Ok(Yaml::Integer(n));
+            }
+        } else if let Ok(n) = number.parse::<i64>() {
+            return Ok(Yaml::Integer(n));
+        } else if (unsigned == "inf" || unsigned == "nan" || unsigned.starts_with(char::is_numeric))
+            && number.parse::<f64>().is_ok()
+        {
+            return Ok(Yaml::Real(number));
+        }
+        self.pos = start;
+        Err(match text.contains(':') || text.matches('-').count() > 1 {
+            true => self.error("dates and times are not supported"),
+            false => self.error("invalid value"),
+        })
+    }
+
+    fn escape(&mut self) -> Result<char, ConfigError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        let unicode = |parser: &mut Self, len: usize| {
+            let code: String = parser.chars.iter().skip(parser.pos).take(len).collect();
+            parser.pos += len;
+            u32::from_str_radix(&code, 16)
+                .ok()
+                .filter(|_| code.len() == len)
+                .and_then(char::from_u32)
+                .ok_or_else(|| parser.error("invalid unicode escape"))
+        };
+        Ok(match c {
+            'b' => '\u{8}',
+            't' => '\t',
+            'n' => '\n',
+            'f' => '\u{c}',
+            'r' => '\r',
+            'e' => '\u{1b}',
+            '"' => '"',
+            '\\' => '\\',
+            'u' => unicode(self, 4)?,
+            'U' => unicode(self, 8)?,
+            _ => return Err(self.error(&format!("invalid escape sequence '\\{}'", c))),
+        })
+    }
+
+    fn basic_string(&mut self) -> Result<String, ConfigError> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(result);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    result.push(self.escape()?);
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    result.push(c);
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, ConfigError> {
+        self.expect('\'')?;
+        let start = self.pos;
+        while self.peek() != Some('\'') {
+            if matches!(self.peek(), None | Some('\n')) {
+                return Err(self.error("unterminated string"));
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(self.chars[start..self.pos - 1].iter().collect())
+    }
+
+    /// Skip the newline right after the opening delimiter of a multi-line string
+    fn skip_first_newline(&mut self) {
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.peek() == Some('\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn multiline_basic_string(&mut self) -> Result<Yaml, ConfigError> {
+        self.pos += 3;
+        self.skip_first_newline();
+        let mut result = String::new();
+        loop {
+            // Up to two quotes may directly precede the closing delimiter
+            if self.starts_with("\"\"\"") && !self.starts_with("\"\"\"\"\"\"") {
+                let quotes = (3..5)
+                    .take_while(|i| self.chars.get(self.pos + i) == Some(&'"'))
+                    .count();
+                result.extend(std::iter::repeat_n('"', quotes));
+                self.pos += quotes + 3;
+                return Ok(Yaml::String(result));
+            }
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => {
+                    self.pos += 1;
+                    // Line ending backslash trims all whitespace up to the next text
+                    let rest = self.chars[self.pos..].iter();
+                    let blank: Vec<&char> = rest.take_while(|c| c.is_whitespace()).collect();
+                    if blank.contains(&&'\n') {
+                        self.pos += blank.len();
+                    } else {
+                        result.push(self.escape()?);
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    result.push(c);
+                }
+            }
+        }
+    }
+
+    fn multiline_literal_string(&mut self) -> Result<Yaml, ConfigError> {
+        self.pos += 3;
+        self.skip_first_newline();
+        let start = self.pos;
+        while !self.starts_with("'''") || self.starts_with("''''") {
+            if self.peek().is_none() {
+                return Err(self.error("unterminated string"));
+            }
+            self.pos += 1;
+        }
+        self.pos += 3;
+        Ok(Yaml::String(
+            self.chars[start..self.pos - 3].iter().collect(),
+        ))
+    }
+
+    fn array(&mut self) -> Result<Yaml, ConfigError> {
+        self.expect('[')?;
+        let mut items = vec![];
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                break;
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => break,
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+        self.pos += 1;
+        Ok(Yaml::Array(items))
+    }
+
+    fn inline_table(&mut self) -> Result<Yaml, ConfigError> {
+        self.expect('{')?;
+        let mut table = Hash::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Yaml::Hash(table));
+        }
+        loop {
+            let (path, value) = self.key_value()?;
+            self.insert(&mut table, &path, value)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => break,
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+        self.pos += 1;
+        Ok(Yaml::Hash(table))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn yaml(source: &str) -> Yaml {
+        YamlLoader::load_from_str(source).unwrap().remove(0)
+    }
+
+    #[test]
+    fn toml_tables() {
+        let res = load_from_str(
+            r#"
+source = "proj.c"  # comment
+include = [
+  "a.yaml",
+  'b.toml', # trailing comma
+]
+
+[compiler]
+CC = "gcc"
+test-config.timeout = 100
+
+[[tests]]
+name = "first"
+score = 1.0
+test-cases = [ { args = "1", stdout = "a" }, { args = "2" } ]
+
+[[tests]]
+name = "second"
+score = 2
+
+[tests.env]
+"LC_ALL" = "C"
+
+[[tests.extra]]
+x = true
+"#,
+        );
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            yaml(
+                r#"
+source: proj.c
+include: [ a.yaml, b.toml ]
+compiler:
+  CC: gcc
+  test-config: { timeout: 100 }
+tests:
+  - name: first
+    score: 1.0
+    test-cases: [ { args: "1", stdout: a }, { args: "2" } ]
+  - name: second
+    score: 2
+    env: { LC_ALL: C }
+    extra: [ { x: true } ]
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn toml_scalars() {
+        let res = load_from_str(
+            r#"
+basic = "tab\tquote\"\u00e9"
+literal = 'C:\path'
+multi = """
+line 1
+line 2 \
+    continued
+"""
+raw = '''
+a\n'''
+ints = [ 1_000, -2, +3, 0x1f, 0o17, 0b101 ]
+floats = [ 0.5, -1e3, 3.0E-1, inf, -nan ]
+bools = [ true, false ]
+empty = {}
+"#,
+        );
+        assert!(res.is_ok());
+        let doc = res.unwrap();
+        assert_eq!(doc["basic"].as_str(), Some("tab\tquote\"é"));
+        assert_eq!(doc["literal"].as_str(), Some("C:\\path"));
+        assert_eq!(doc["multi"].as_str(), Some("line 1\nline 2 continued\n"));
+        assert_eq!(doc["raw"].as_str(), Some("a\\n"));
+        let ints: Vec<i64> = doc["ints"]
+            .as_vec()
+            .unwrap()
+            .iter()
+            .map(|n| n.as_i64().unwrap())
+            .collect();
+        assert_eq!(ints, vec![1000, -2, 3, 31, 15, 5]);
+        let floats: Vec<f64> = doc["floats"]
+            .as_vec()
+            .unwrap()
+            .iter()
+            .map(|n| n.as_f64().unwrap())
+            .collect();
+        assert_eq!(&floats[..3], &[0.5, -1000.0, 0.3]);
+        assert_eq!(floats[3], f64::INFINITY);
+        assert!(floats[4].is_nan());
+        assert_eq!(doc["bools"], yaml("[ true, false ]"));
+        assert_eq!(doc["empty"], Yaml::Hash(Hash::new()));
+    }
+
+    #[test]
+    fn toml_errors() {
+        for (source, line, msg) in [
+            ("a = 1\nb = \"x", 2, "unterminated string"),
+            ("a = 1\na = 2", 2, "duplicate key 'a'"),
+            ("a = 1\n[a]", 2, "'a' is not a table"),
+            ("a = 1 b = 2", 1, "expected the end of line"),
+            ("d = 2024-01-01", 1, "dates and times are not supported"),
+            ("a = [ 1 2 ]", 1, "expected ',' or ']'"),
+            ("= 1", 1, "expected a key"),
+            ("a = \"\\x\"", 1, "invalid escape sequence '\\x'"),
+        ] {
+            let res = load_from_str(source);
+            assert!(
+                matches!(&res, Err(ConfigError::InvalidToml { line: l, msg: m })
+                    if *l == line && m == msg),
+                "{}: {:?}",
+                source,
+                res
+            );
+        }
+    }
+}
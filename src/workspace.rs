@@ -0,0 +1,132 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Working directories of a single run
+/// All directories are created inside a uniquely named run directory under `root`, so
+/// parallel runs sharing the same root do not collide. The run directory (with all its
+/// contents) is removed when the workspace is dropped, also when unwinding from a panic,
+/// unless the workspace is created with `keep` set.
+pub struct Workspace {
+    dir: Option<TempDir>,
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Workspace {
+    pub fn new(root: &Path, keep: bool) -> io::Result<Self> {
+        std::fs::create_dir_all(root)?;
+        let dir = tempfile::Builder::new()
+            .prefix("atst-run-")
+            .tempdir_in(root)?;
+        Ok(Self {
+            path: dir.path().to_path_buf(),
+            dir: Some(dir),
+            keep,
+        })
+    }
+
+    /// Directory of the run
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create a new working directory for `case` of `solution`
+    /// The directory is named `<solution>/<case>`. If such a directory already exists
+    /// (e.g. the case is run repeatedly), a numeric suffix is added. Path separators
+    /// in `case` (which is usually derived from a test name) are replaced by '_'.
+    pub fn dir(&self, solution: &str, case: &str) -> io::Result<PathBuf> {
+        let case: String = case
+            .chars()
+            .map(|c| match std::path::is_separator(c) {
+                true => '_',
+                false => c,
+            })
+            .collect();
+        let parent = self.path.join(solution);
+        std::fs::create_dir_all(&parent)?;
+        for i in 0.. {
+            let dir = match i {
+                0 => parent.join(&case),
+                i => parent.join(format!("{}-{}", case, i)),
+            };
+            match std::fs::create_dir(&dir) {
+                Ok(()) => return Ok(dir),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            match self.keep {
+                true => {
+                    let _ = dir.into_path();
+                }
+                false => {
+                    let _ = dir.close();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path) -> usize {
+        dir.read_dir().unwrap().count()
+    }
+
+    #[test]
+    fn workspace_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(root.path(), false).unwrap();
+        let other = Workspace::new(root.path(), false).unwrap();
+        assert_ne!(workspace.path(), other.path());
+
+        let dir = workspace.dir("xlogin00", "test1").unwrap();
+        assert_eq!(dir, workspace.path().join("xlogin00").join("test1"));
+        assert!(dir.is_dir());
+        let again = workspace.dir("xlogin00", "test1").unwrap();
+        assert_eq!(again, workspace.path().join("xlogin00").join("test1-1"));
+        // Test names may contain path separators
+        let dir = workspace.dir("xlogin00", "input/output-0").unwrap();
+        assert_eq!(
+            dir,
+            workspace.path().join("xlogin00").join("input_output-0")
+        );
+
+        drop(workspace);
+        assert_eq!(entries(root.path()), 1);
+        drop(other);
+        assert_eq!(entries(root.path()), 0);
+    }
+
+    #[test]
+    fn workspace_keep() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(root.path(), true).unwrap();
+        let dir = workspace.dir("xlogin00", "test1").unwrap();
+        drop(workspace);
+        assert!(dir.is_dir());
+    }
+
+    #[test]
+    fn workspace_panic_cleanup() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().to_path_buf();
+        let res = std::panic::catch_unwind(move || {
+            let workspace = Workspace::new(&path, false).unwrap();
+            std::fs::write(workspace.dir("xlogin00", "test1").unwrap().join("out"), "x").unwrap();
+            panic!("module failed");
+        });
+        assert!(res.is_err());
+        assert_eq!(entries(root.path()), 0);
+    }
+}
@@ -0,0 +1,45 @@
+//! Environment passed to custom scripts run on solutions
+
+use atst::{run_with_options, Output, RunOptions};
+use std::path::Path;
+
+const CONFIG: &str = "
+source: main.c
+tests:
+  - name: hello
+    score: 1.0
+    stdout: hello
+  - name: world
+    score: 2.0
+    stdout: world
+scripts: [ env.sh ]
+";
+
+const HELLO: &str = "#include <stdio.h>\nint main() { puts(\"hello\"); }\n";
+
+// The script records its environment in the solution directory
+const SCRIPT: &str = "#!/bin/sh\nenv | grep '^ATST_' > env.txt\n";
+
+#[cfg(unix)]
+#[test]
+fn script_score_so_far() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("config.yaml"), CONFIG).unwrap();
+    let script = dir.path().join("env.sh");
+    std::fs::write(&script, SCRIPT).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::create_dir(dir.path().join("xlogin00")).unwrap();
+    std::fs::write(dir.path().join("xlogin00").join("main.c"), HELLO).unwrap();
+
+    let options = RunOptions {
+        outputs: vec![Output::Json(dir.path().join("results.json"))],
+        ..Default::default()
+    };
+    let res = run_with_options(dir.path(), Path::new("config.yaml"), &options);
+    assert!(res.is_ok());
+
+    let env = std::fs::read_to_string(dir.path().join("xlogin00").join("env.txt")).unwrap();
+    assert_eq!(env.lines().collect::<Vec<_>>(), vec!["ATST_SCORE_SO_FAR=1"]);
+}
@@ -0,0 +1,109 @@
+//! Solutions which cannot be evaluated are reported in the results and do not stop the run
+
+use atst::{read_results, run_with_options, Output, RunOptions, SolutionResults};
+use std::path::Path;
+
+/// Create a project in `dir` with the given config and solutions (name and source)
+fn project(dir: &Path, config: &str, solutions: &[(&str, &str)]) {
+    std::fs::write(dir.join("config.yaml"), config).unwrap();
+    for (name, source) in solutions {
+        std::fs::create_dir(dir.join(name)).unwrap();
+        std::fs::write(dir.join(name).join("main.c"), source).unwrap();
+    }
+}
+
+/// Evaluate the project in `dir` and return results of all its solutions
+fn evaluate(dir: &Path) -> Vec<SolutionResults> {
+    let json_file = dir.join("results.json");
+    let options = RunOptions {
+        outputs: vec![Output::Json(json_file.clone())],
+        ..Default::default()
+    };
+    let res = run_with_options(dir, Path::new("config.yaml"), &options);
+    assert!(res.is_ok());
+    read_results(&json_file).unwrap()
+}
+
+const CONFIG: &str = "
+source: main.c
+tests:
+  - name: hello
+    score: 1.0
+    stdout: hello
+";
+
+const HELLO: &str = "#include <stdio.h>\nint main() { puts(\"hello\"); }\n";
+
+#[test]
+fn invalid_source_name() {
+    let dir = tempfile::tempdir().unwrap();
+    project(
+        dir.path(),
+        &CONFIG.replace("main.c", ".."),
+        &[("xlogin00", HELLO)],
+    );
+    let results = evaluate(dir.path());
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status, "error");
+    assert_eq!(
+        results[0].error.as_deref(),
+        Some("invalid source file name '..'")
+    );
+    assert_eq!(results[0].tests.len(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_directory_name() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    project(dir.path(), CONFIG, &[("xlogin00", HELLO)]);
+    let invalid = dir.path().join(OsStr::from_bytes(b"xlogin\xff"));
+    std::fs::create_dir(&invalid).unwrap();
+    std::fs::write(invalid.join("main.c"), HELLO).unwrap();
+
+    let results = evaluate(dir.path());
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].name, "xlogin00");
+    assert_eq!(results[0].status, "evaluated");
+    assert_eq!(results[0].score, 1.0);
+    assert_eq!(results[1].name, "xlogin\u{fffd}");
+    assert_eq!(results[1].status, "error");
+    assert!(results[1]
+        .error
+        .as_ref()
+        .unwrap()
+        .contains("not valid UTF-8"));
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    project(
+        dir.path(),
+        CONFIG,
+        &[("xlogin00", HELLO), ("xlogin01", HELLO)],
+    );
+    let unreadable = dir.path().join("xlogin01");
+    std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o000)).unwrap();
+    // Permissions do not apply to privileged users
+    let readable = unreadable.read_dir().is_ok();
+
+    let results = evaluate(dir.path());
+    std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].status, "evaluated");
+    if !readable {
+        assert_eq!(results[1].status, "error");
+        assert!(results[1]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("cannot read directory"));
+    }
+}
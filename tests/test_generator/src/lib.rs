@@ -16,10 +16,14 @@ use syn::{parse_macro_input, LitStr};
 ///     <solution-2>/
 ///     ...
 ///     config.yaml
+///     config.toml (optional)
 ///     expected-results
 ///
 /// Generates one test for each solution in the project. The test evaluates
 /// the solution and compares the obtained result with the expected result.
+/// If the project has the same configuration in multiple formats, the solution
+/// is evaluated with each of them (one after another, since they share the solution
+/// directory).
 ///
 /// Expected results are specified in 'expected-results' which has the form:
 ///   <solution-1>: <expected-score>
@@ -47,7 +51,6 @@ pub fn generate_tests(input: TokenStream) -> TokenStream {
             fn test_{}() {{
                 let solution = \"{}\";
                 let project_path = std::path::PathBuf::from(\"{}\");
-                let config_file = std::path::PathBuf::from(\"config.yaml\");
 
                 let expected = std::fs::read_to_string(project_path.join(\"expected-scores\"))
                     .expect(\"Error opening expected-scores\")
@@ -57,11 +60,17 @@ pub fn generate_tests(input: TokenStream) -> TokenStream {
                     .trim()
                     .parse::<f64>().unwrap();
 
-                let res = atst::run(&project_path, &config_file, solution, 1);
+                for config in [\"config.yaml\", \"config.toml\"] {{
+                    let config_file = std::path::PathBuf::from(config);
+                    if !project_path.join(&config_file).exists() {{
+                        continue;
+                    }}
+                    let res = atst::run(&project_path, &config_file, solution, 1);
 
-                assert!(res.is_ok());
-                assert!(res.as_ref().unwrap().contains_key(solution));
-                assert_eq!(*res.as_ref().unwrap().get(solution).unwrap(), expected);
+                    assert!(res.is_ok(), \"{{}}: {{:?}}\", config, res.err());
+                    assert!(res.as_ref().unwrap().contains_key(solution));
+                    assert_eq!(*res.as_ref().unwrap().get(solution).unwrap(), expected);
+                }}
             }}\n",
             solution,
             solution,